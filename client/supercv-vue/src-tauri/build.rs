@@ -1,3 +1,6 @@
 fn main() {
+    // 使用内置的 protoc, 不依赖系统安装
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path().unwrap());
+    tonic_build::configure().compile_protos(&["proto/supercv.proto"], &["proto"]).unwrap();
     tauri_build::build()
 }
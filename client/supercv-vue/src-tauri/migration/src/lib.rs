@@ -1,6 +1,16 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20240714_065956_create_clipboard_table;
+mod m20240805_120000_add_clipboard_source;
+mod m20240806_120000_add_clipboard_app;
+mod m20240807_120000_add_clipboard_pinned;
+mod m20240808_120000_create_outbox_table;
+mod m20240809_120000_add_clipboard_indexes;
+mod m20240810_120000_add_clipboard_copy_count;
+mod m20240811_120000_add_clipboard_title_note;
+mod m20240811_130000_create_revision_table;
+mod m20240812_120000_add_clipboard_deleted_at;
+mod m20240813_120000_add_outbox_action;
 
 pub struct Migrator;
 
@@ -9,6 +19,16 @@ impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
         vec![
             Box::new(m20240714_065956_create_clipboard_table::Migration),
+            Box::new(m20240805_120000_add_clipboard_source::Migration),
+            Box::new(m20240806_120000_add_clipboard_app::Migration),
+            Box::new(m20240807_120000_add_clipboard_pinned::Migration),
+            Box::new(m20240808_120000_create_outbox_table::Migration),
+            Box::new(m20240809_120000_add_clipboard_indexes::Migration),
+            Box::new(m20240810_120000_add_clipboard_copy_count::Migration),
+            Box::new(m20240811_120000_add_clipboard_title_note::Migration),
+            Box::new(m20240811_130000_create_revision_table::Migration),
+            Box::new(m20240812_120000_add_clipboard_deleted_at::Migration),
+            Box::new(m20240813_120000_add_outbox_action::Migration),
         ]
     }
 }
@@ -0,0 +1,31 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// 记录内容来源的 selection: 0 CLIPBOARD, 1 PRIMARY (X11)
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HostClipboard::Table)
+                    .add_column(ColumnDef::new(HostClipboard::Source).integer().not_null().default(0))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(Table::alter().table(HostClipboard::Table).drop_column(HostClipboard::Source).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum HostClipboard {
+    Table,
+    Source,
+}
@@ -0,0 +1,53 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// 记录复制时的来源应用: 应用名(WM_CLASS), 进程名, 窗口标题
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite 的 ALTER TABLE 一次只能添加一列
+        for column in [HostClipboard::AppName, HostClipboard::ProcessName, HostClipboard::WindowTitle] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(HostClipboard::Table)
+                        .add_column(ColumnDef::new(column).string().not_null().default(""))
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_host_clipboard_app_name")
+                    .table(HostClipboard::Table)
+                    .col(HostClipboard::AppName)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name("idx_host_clipboard_app_name").table(HostClipboard::Table).to_owned())
+            .await?;
+        for column in [HostClipboard::AppName, HostClipboard::ProcessName, HostClipboard::WindowTitle] {
+            manager
+                .alter_table(Table::alter().table(HostClipboard::Table).drop_column(column).to_owned())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum HostClipboard {
+    Table,
+    AppName,
+    ProcessName,
+    WindowTitle,
+}
@@ -0,0 +1,31 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// 置顶的记录不会过期
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HostClipboard::Table)
+                    .add_column(ColumnDef::new(HostClipboard::Pinned).boolean().not_null().default(false))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(Table::alter().table(HostClipboard::Table).drop_column(HostClipboard::Pinned).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum HostClipboard {
+    Table,
+    Pinned,
+}
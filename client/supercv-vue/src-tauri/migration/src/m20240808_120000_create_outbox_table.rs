@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// 离线时上传失败的记录, 按 hash 去重, 联网后由同步任务重放
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Outbox::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Outbox::Id).integer().not_null().auto_increment().primary_key())
+                    .col(ColumnDef::new(Outbox::Hash).string().not_null())
+                    .col(ColumnDef::new(Outbox::Attempts).integer().not_null().default(0))
+                    .col(ColumnDef::new(Outbox::NextAttemptAt).big_integer().not_null())
+                    .col(ColumnDef::new(Outbox::LastError).string().not_null().default(""))
+                    .col(ColumnDef::new(Outbox::CreatedAt).big_integer().not_null())
+                    .index(Index::create().name("idx_outbox_hash_unique").col(Outbox::Hash).unique())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(Outbox::Table).to_owned()).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Outbox {
+    Table,
+    Id,
+    Hash,
+    Attempts,
+    NextAttemptAt,
+    LastError,
+    CreatedAt,
+}
@@ -0,0 +1,60 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// 分页按 (timestamp, id) 倒序翻页, 按类型过滤时走 (type, timestamp)
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_host_clipboard_type_timestamp")
+                    .table(HostClipboard::Table)
+                    .col(HostClipboard::Type)
+                    .col(HostClipboard::Timestamp)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_host_clipboard_timestamp_id")
+                    .table(HostClipboard::Table)
+                    .col(HostClipboard::Timestamp)
+                    .col(HostClipboard::Id)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_host_clipboard_timestamp_id")
+                    .table(HostClipboard::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_host_clipboard_type_timestamp")
+                    .table(HostClipboard::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum HostClipboard {
+    Table,
+    Id,
+    Type,
+    Timestamp,
+}
@@ -0,0 +1,44 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// 用户给记录加的标题和备注, 任意类型的记录都可以编辑
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // sqlite 一次只能添加一列
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HostClipboard::Table)
+                    .add_column(ColumnDef::new(HostClipboard::Title).string().not_null().default(""))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HostClipboard::Table)
+                    .add_column(ColumnDef::new(HostClipboard::Note).string().not_null().default(""))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(Table::alter().table(HostClipboard::Table).drop_column(HostClipboard::Note).to_owned())
+            .await?;
+        manager
+            .alter_table(Table::alter().table(HostClipboard::Table).drop_column(HostClipboard::Title).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum HostClipboard {
+    Table,
+    Title,
+    Note,
+}
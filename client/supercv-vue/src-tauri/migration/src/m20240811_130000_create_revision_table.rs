@@ -0,0 +1,65 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// 编辑前的记录内容, 用于撤销. 记录被删除时一起删除
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ClipboardRevision::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(ClipboardRevision::Id).integer().not_null().auto_increment().primary_key())
+                    .col(ColumnDef::new(ClipboardRevision::ClipboardId).integer().not_null())
+                    .col(ColumnDef::new(ClipboardRevision::Content).text().not_null())
+                    .col(ColumnDef::new(ClipboardRevision::Hash).string().not_null())
+                    .col(ColumnDef::new(ClipboardRevision::Title).string().not_null().default(""))
+                    .col(ColumnDef::new(ClipboardRevision::Note).string().not_null().default(""))
+                    .col(ColumnDef::new(ClipboardRevision::CreatedAt).big_integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_clipboard_revision_clipboard_id")
+                            .from(ClipboardRevision::Table, ClipboardRevision::ClipboardId)
+                            .to(HostClipboard::Table, HostClipboard::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_clipboard_revision_clipboard_id")
+                    .table(ClipboardRevision::Table)
+                    .col(ClipboardRevision::ClipboardId)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(ClipboardRevision::Table).to_owned()).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ClipboardRevision {
+    Table,
+    Id,
+    ClipboardId,
+    Content,
+    Hash,
+    Title,
+    Note,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum HostClipboard {
+    Table,
+    Id,
+}
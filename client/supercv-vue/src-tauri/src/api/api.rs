@@ -6,11 +6,16 @@ use crate::api::models::clipboard;
 use crate::api::models::device;
 use crate::api::models::file;
 use crate::api::models::user;
+use crate::utils::base64::encode_base64;
+use crate::utils::cipher::CryptoHelper;
+use crate::utils::password::{self, PasswordKdf};
 
-struct Api<T: ClientTrait + ClientDeviceTrait> {
+pub struct Api<T: ClientTrait + ClientDeviceTrait> {
 	client: ApiClient<T>,
 	user: Option<user::User>,
 	device: Option<device::Device>,
+	kdfs: user::AccountKdfs, // 本机记录的各账户的密码派生方式
+	persist: bool,           // 是否读写本地缓存的用户和 token
 }
 
 impl<T: ClientTrait + ClientDeviceTrait> Api<T> {
@@ -23,34 +28,140 @@ impl<T: ClientTrait + ClientDeviceTrait> Api<T> {
 			error!("load cache user {:?}", e);
 			None
 		});
-		Self { client, user, device }
+		let kdfs = user::AccountKdfs::load().await.unwrap_or_else(|e| {
+			error!("load cache kdf {:?}", e);
+			user::AccountKdfs::default()
+		});
+		Self {
+			client,
+			user,
+			device,
+			kdfs,
+			persist: true,
+		}
+	}
+
+	// 不读写本地缓存, 用于测试
+	#[cfg(test)]
+	pub fn in_memory(client: ApiClient<T>) -> Self {
+		Self {
+			client,
+			user: None,
+			device: None,
+			kdfs: user::AccountKdfs::default(),
+			persist: false,
+		}
+	}
+
+	pub fn user(&self) -> Option<&user::User> {
+		self.user.as_ref()
+	}
+
+	pub fn device(&self) -> Option<&device::Device> {
+		self.device.as_ref()
 	}
 
 	// User
-	pub async fn register_user(&mut self, create_user: user::UserRegister) -> Result<(), ClientError> {
+	// 密码用 Argon2id 派生后发送, DEK 用 super_key 加密后保存在服务端
+	pub async fn register_user(&mut self, username: String, email: String, password: &str, super_key: &str) -> Result<(), ClientError> {
+		let salt = password::gen_salt();
+		let password_hash = derive_password(password, PasswordKdf::Argon2id, &salt).await?;
+		let encrypted_dek = encode_base64(&CryptoHelper::new(super_key).encode_dek(&CryptoHelper::gen_dek()));
+		let create_user = user::UserRegister {
+			username: username.clone(),
+			email,
+			password_hash,
+			encrypted_dek,
+			salt,
+			kdf: PasswordKdf::Argon2id,
+		};
 		let user_resp = self.client.register_user(create_user).await?;
-		let user = user::User::from(user_resp);
-		user.save().await?;
-		self.user = Some(user);
-		Ok(())
+		self.save_kdf(&username, PasswordKdf::Argon2id).await?;
+		self.save_user(user_resp).await
 	}
 
-	pub async fn login_user(&mut self, login_user: user::UserLogin) -> Result<(), ClientError> {
+	// 先取回 salt 再派生 password_hash. 旧账户 (Legacy) 登录成功后迁移到 Argon2id, 迁移失败时返回错误, 下次登录重试
+	pub async fn login_user(&mut self, username: String, password: &str) -> Result<(), ClientError> {
+		let params = match self.client.get_user_salt(user::UserSaltQuery { username: username.clone() }).await {
+			Ok(params) => params,
+			// 旧服务端没有该接口
+			Err(ClientError::ApiError { code: 404, .. }) => user::UserSalt::default(),
+			Err(e) => return Err(e),
+		};
+		// 本机已经迁移过的账户不会再回到 Legacy
+		if params.kdf == PasswordKdf::Legacy && self.kdfs.get(&username) == Some(PasswordKdf::Argon2id) {
+			return Err(ClientError::UnexpectedError(format!(
+				"Server asked for the legacy password hash of {}, which already uses argon2id",
+				username
+			)));
+		}
+		let password_hash = derive_password(password, params.kdf, &params.salt).await?;
+		let login_user = user::UserLogin {
+			username: username.clone(),
+			password_hash: password_hash.clone(),
+		};
 		let user_resp = self.client.login_user(login_user).await?;
-		let user = user::User::from(user_resp);
-		user.save().await?;
-		self.user = Some(user);
+
+		if params.kdf == PasswordKdf::Legacy {
+			let salt = password::gen_salt();
+			let upgrade = user::UserUpgradePassword {
+				username: username.clone(),
+				password_hash,
+				new_password_hash: derive_password(password, PasswordKdf::Argon2id, &salt).await?,
+				salt,
+				kdf: PasswordKdf::Argon2id,
+			};
+			self.client.upgrade_user_password(upgrade).await?;
+		}
+		self.save_kdf(&username, PasswordKdf::Argon2id).await?;
+		self.save_user(user_resp).await
+	}
+
+	// 清除缓存的用户和 token
+	pub async fn logout(&mut self) -> Result<(), ClientError> {
+		if self.persist {
+			user::User::clear().await?;
+			user::Token::clear().await?;
+		}
+		self.user = None;
 		Ok(())
 	}
 
-	pub async fn reset_user_password(&mut self, reset_user: user::UserResetPassword) -> Result<(), ClientError> {
-		let user_resp = self.client.reset_user(reset_user).await?;
+	async fn save_kdf(&mut self, username: &str, kdf: PasswordKdf) -> Result<(), ClientError> {
+		self.kdfs.set(username, kdf);
+		if self.persist {
+			self.kdfs.save().await?;
+		}
+		Ok(())
+	}
+
+	async fn save_user(&mut self, user_resp: user::UserResp) -> Result<(), ClientError> {
+		let token = user_resp.token.clone();
 		let user = user::User::from(user_resp);
-		user.save().await?;
+		if self.persist {
+			if let Some(token) = token {
+				token.save().await?;
+			}
+			user.save().await?;
+		}
 		self.user = Some(user);
 		Ok(())
 	}
 
+	pub async fn reset_user_password(&mut self, username: String, email: String, password: &str) -> Result<(), ClientError> {
+		let salt = password::gen_salt();
+		let reset_user = user::UserResetPassword {
+			username: username.clone(),
+			email,
+			password_hash: derive_password(password, PasswordKdf::Argon2id, &salt).await?,
+			salt,
+			kdf: PasswordKdf::Argon2id,
+		};
+		let user_resp = self.client.reset_user(reset_user).await?;
+		self.save_kdf(&username, PasswordKdf::Argon2id).await?;
+		self.save_user(user_resp).await
+	}
+
 	// Device
 	pub async fn create_device(&mut self, create_device: device::CreateDevice) -> Result<(), ClientError> {
 		let device_resp = self.client.create_device(create_device).await?;
@@ -93,18 +204,17 @@ impl<T: ClientTrait + ClientDeviceTrait> Api<T> {
 
 	// File
 	pub async fn upload_file(&self, file_path: &str) -> Result<file::FileResp, ClientError> {
-		let user_id = self
-			.user
-			.as_ref()
-			.ok_or(ClientError::UnexpectedError("User not logged in".to_string()))?
-			.id;
-		self.client.upload_file(user_id, file_path).await
+		self.client.upload_file(file_path).await
 	}
 
 	pub async fn get_file(&self, uri: &str) -> Result<Vec<u8>, ClientError> {
 		self.client.get_file(uri).await
 	}
 
+	pub async fn download_file(&self, uri: &str, dest: &std::path::Path) -> Result<u64, ClientError> {
+		self.client.download_file(uri, dest).await
+	}
+
 	// Clipboard
 	pub async fn create_clipboard(&self, create_clipboard: clipboard::CreateClipboard) -> Result<clipboard::ClipboardResp, ClientError> {
 		self.client.create_clipboard(create_clipboard).await
@@ -113,4 +223,17 @@ impl<T: ClientTrait + ClientDeviceTrait> Api<T> {
 	pub async fn get_clipboards_by_id(&self, content_id: i32) -> Result<clipboard::ClipboardResp, ClientError> {
 		self.client.get_clipboards_by_id(content_id).await
 	}
+
+	pub async fn delete_clipboard(&self, delete_clipboard: clipboard::DeleteClipboard) -> Result<bool, ClientError> {
+		self.client.delete_clipboard(delete_clipboard).await
+	}
+}
+
+// Argon2id 较慢, 放到 blocking 线程执行
+async fn derive_password(password: &str, kdf: PasswordKdf, salt: &str) -> Result<String, ClientError> {
+	let (password, salt) = (password.to_string(), salt.to_string());
+	tokio::task::spawn_blocking(move || password::hash_password(&password, kdf, &salt))
+		.await
+		.map_err(|e| ClientError::UnexpectedError(e.to_string()))?
+		.map_err(ClientError::Cipher)
 }
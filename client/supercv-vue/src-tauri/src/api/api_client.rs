@@ -21,14 +21,23 @@ impl<T: ClientTrait + ClientDeviceTrait> ApiClient<T> {
 	pub async fn reset_user(&self, entity: user::UserResetPassword) -> Result<user::UserResp, ClientError> {
 		self.client.reset_user(entity).await
 	}
+	pub async fn get_user_salt(&self, entity: user::UserSaltQuery) -> Result<user::UserSalt, ClientError> {
+		self.client.get_user_salt(entity).await
+	}
+	pub async fn upgrade_user_password(&self, entity: user::UserUpgradePassword) -> Result<user::UserResp, ClientError> {
+		self.client.upgrade_user_password(entity).await
+	}
 
 	// File
-	pub async fn upload_file(&self, user_id: i32, file_path: &str) -> Result<file::FileResp, ClientError> {
-		self.client.upload_file(user_id, file_path).await
+	pub async fn upload_file(&self, file_path: &str) -> Result<file::FileResp, ClientError> {
+		self.client.upload_file(file_path).await
 	}
 	pub async fn get_file(&self, uri: &str) -> Result<Vec<u8>, ClientError> {
 		self.client.get_file(uri).await
 	}
+	pub async fn download_file(&self, uri: &str, dest: &std::path::Path) -> Result<u64, ClientError> {
+		self.client.download_file(uri, dest).await
+	}
 
 	// Clipboard
 	pub async fn create_clipboard(&self, create_clipboard: clipboard::CreateClipboard) -> Result<clipboard::ClipboardResp, ClientError> {
@@ -37,6 +46,9 @@ impl<T: ClientTrait + ClientDeviceTrait> ApiClient<T> {
 	pub async fn get_clipboards_by_id(&self, content_id: i32) -> Result<clipboard::ClipboardResp, ClientError> {
 		self.client.get_clipboards_by_id(content_id).await
 	}
+	pub async fn delete_clipboard(&self, delete_clipboard: clipboard::DeleteClipboard) -> Result<bool, ClientError> {
+		self.client.delete_clipboard(delete_clipboard).await
+	}
 
 	// Device
 	pub async fn create_device(&self, create_device: device::CreateDevice) -> Result<device::DeviceResp, ClientError> {
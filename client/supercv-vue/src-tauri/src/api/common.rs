@@ -1,17 +1,40 @@
 use crate::api::models::{clipboard, device, file, user};
 
 use crate::api::models::device::{Device, DeviceResp};
-use std::fmt;
+use crate::utils::cipher::CipherError;
+use sea_orm::DbErr;
+use std::error::Error;
+use std::path::Path;
+use std::{fmt, io};
 
-#[derive(Debug, Clone)]
+// 由底层错误转换来的保留原来的错误, 可以通过 source() 取得
+#[derive(Debug)]
 pub enum ClientError {
 	NetworkError(String),
 	ApiError { code: i32, message: String },
 	SerializationError(String),
 	UnexpectedError(String),
+	Http(reqwest::Error), // 请求没有发出或者读取响应失败
+	Io(io::Error),        // 本地文件和缓存
+	Json(serde_json::Error),
+	Toml(Box<dyn Error + Send + Sync>), // 本地缓存的用户和设备等
+	Db(DbErr),
+	Cipher(CipherError),
 }
 
-
+impl Error for ClientError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			ClientError::Http(e) => Some(e),
+			ClientError::Io(e) => Some(e),
+			ClientError::Json(e) => Some(e),
+			ClientError::Toml(e) => Some(e.as_ref()),
+			ClientError::Db(e) => Some(e),
+			ClientError::Cipher(e) => Some(e),
+			_ => None,
+		}
+	}
+}
 
 impl fmt::Display for ClientError {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -20,23 +43,76 @@ impl fmt::Display for ClientError {
 			ClientError::ApiError { code, message } => write!(f, "API error ({}): {}", code, message),
 			ClientError::SerializationError(e) => write!(f, "Serialization error: {}", e),
 			ClientError::UnexpectedError(e) => write!(f, "Unexpected error: {}", e),
+			ClientError::Http(e) => write!(f, "Network error: {}", e),
+			ClientError::Io(e) => write!(f, "File io error: {}", e),
+			ClientError::Json(e) => write!(f, "Serialization error: {}", e),
+			ClientError::Toml(e) => write!(f, "Serialization error: {}", e),
+			ClientError::Db(e) => write!(f, "Database error: {}", e),
+			ClientError::Cipher(e) => write!(f, "{}", e),
 		}
 	}
 }
+
+impl From<reqwest::Error> for ClientError {
+	fn from(e: reqwest::Error) -> Self {
+		ClientError::Http(e)
+	}
+}
+
+impl From<io::Error> for ClientError {
+	fn from(e: io::Error) -> Self {
+		ClientError::Io(e)
+	}
+}
+
+impl From<serde_json::Error> for ClientError {
+	fn from(e: serde_json::Error) -> Self {
+		ClientError::Json(e)
+	}
+}
+
+impl From<toml::de::Error> for ClientError {
+	fn from(e: toml::de::Error) -> Self {
+		ClientError::Toml(Box::new(e))
+	}
+}
+
+impl From<toml::ser::Error> for ClientError {
+	fn from(e: toml::ser::Error) -> Self {
+		ClientError::Toml(Box::new(e))
+	}
+}
+
+impl From<DbErr> for ClientError {
+	fn from(e: DbErr) -> Self {
+		ClientError::Db(e)
+	}
+}
+
+impl From<CipherError> for ClientError {
+	fn from(e: CipherError) -> Self {
+		ClientError::Cipher(e)
+	}
+}
+
 #[async_trait::async_trait]
 pub trait ClientTrait {
 	// User
 	async fn register_user(&self, create_user: user::UserRegister) -> Result<user::UserResp, ClientError>;
 	async fn login_user(&self, entity: user::UserLogin) -> Result<user::UserResp, ClientError>;
 	async fn reset_user(&self, entity: user::UserResetPassword) -> Result<user::UserResp, ClientError>;
+	async fn get_user_salt(&self, entity: user::UserSaltQuery) -> Result<user::UserSalt, ClientError>;
+	async fn upgrade_user_password(&self, entity: user::UserUpgradePassword) -> Result<user::UserResp, ClientError>;
 
 	// File
-	async fn upload_file(&self, user_id: i32, file_path: &str) -> Result<file::FileResp, ClientError>;
+	async fn upload_file(&self, file_path: &str) -> Result<file::FileResp, ClientError>;
 	async fn get_file(&self, uri: &str) -> Result<Vec<u8>, ClientError>;
+	async fn download_file(&self, uri: &str, dest: &Path) -> Result<u64, ClientError>;
 
 	// Clipboard
 	async fn create_clipboard(&self, create_clipboard: clipboard::CreateClipboard) -> Result<clipboard::ClipboardResp, ClientError>;
 	async fn get_clipboards_by_id(&self, content_id: i32) -> Result<clipboard::ClipboardResp, ClientError>;
+	async fn delete_clipboard(&self, delete_clipboard: clipboard::DeleteClipboard) -> Result<bool, ClientError>;
 
 	// Device
 	async fn create_device(&self, create_device: device::CreateDevice) -> Result<device::DeviceResp, ClientError>;
@@ -0,0 +1,204 @@
+// proto/supercv.proto 生成的代码, 以及与 api::models 之间的转换
+use crate::api::models::{clipboard, device, file, user};
+use crate::utils::password::PasswordKdf;
+
+pub mod pb {
+	tonic::include_proto!("supercv.v1");
+}
+
+// User
+impl From<pb::UserResp> for user::UserResp {
+	fn from(resp: pb::UserResp) -> Self {
+		user::UserResp {
+			id: resp.id,
+			username: resp.username,
+			email: resp.email,
+			password_hash: resp.password_hash,
+			encrypted_dek: resp.encrypted_dek,
+			created_at: resp.created_at,
+			updated_at: resp.updated_at,
+			token: resp.token.map(user::Token::from),
+		}
+	}
+}
+
+impl From<pb::Token> for user::Token {
+	fn from(token: pb::Token) -> Self {
+		user::Token {
+			access_token: token.access_token,
+			refresh_token: token.refresh_token,
+			expires_at: token.expires_at,
+		}
+	}
+}
+
+impl From<user::UserRegister> for pb::UserRegister {
+	fn from(entity: user::UserRegister) -> Self {
+		pb::UserRegister {
+			username: entity.username,
+			email: entity.email,
+			password_hash: entity.password_hash,
+			encrypted_dek: entity.encrypted_dek,
+			salt: entity.salt,
+			kdf: entity.kdf.as_str().to_string(),
+		}
+	}
+}
+
+impl From<user::UserLogin> for pb::UserLogin {
+	fn from(entity: user::UserLogin) -> Self {
+		pb::UserLogin {
+			username: entity.username,
+			password_hash: entity.password_hash,
+		}
+	}
+}
+
+impl From<user::UserResetPassword> for pb::UserResetPassword {
+	fn from(entity: user::UserResetPassword) -> Self {
+		pb::UserResetPassword {
+			username: entity.username,
+			email: entity.email,
+			password_hash: entity.password_hash,
+			salt: entity.salt,
+			kdf: entity.kdf.as_str().to_string(),
+		}
+	}
+}
+
+impl From<user::UserSaltQuery> for pb::UserSaltQuery {
+	fn from(entity: user::UserSaltQuery) -> Self {
+		pb::UserSaltQuery { username: entity.username }
+	}
+}
+
+impl From<pb::UserSalt> for user::UserSalt {
+	fn from(resp: pb::UserSalt) -> Self {
+		user::UserSalt {
+			salt: resp.salt,
+			kdf: PasswordKdf::from_name(&resp.kdf),
+		}
+	}
+}
+
+impl From<user::UserUpgradePassword> for pb::UserUpgradePassword {
+	fn from(entity: user::UserUpgradePassword) -> Self {
+		pb::UserUpgradePassword {
+			username: entity.username,
+			password_hash: entity.password_hash,
+			new_password_hash: entity.new_password_hash,
+			salt: entity.salt,
+			kdf: entity.kdf.as_str().to_string(),
+		}
+	}
+}
+
+// Device
+impl From<pb::DeviceResp> for device::DeviceResp {
+	fn from(resp: pb::DeviceResp) -> Self {
+		device::DeviceResp {
+			id: resp.id,
+			name: resp.name,
+			uuid: resp.uuid,
+			user_id: resp.user_id,
+			icon: resp.icon,
+			created_at: resp.created_at,
+			updated_at: resp.updated_at,
+		}
+	}
+}
+
+impl From<device::CreateDevice> for pb::CreateDevice {
+	fn from(entity: device::CreateDevice) -> Self {
+		pb::CreateDevice {
+			name: entity.name,
+			uuid: entity.uuid,
+			user_id: entity.user_id,
+		}
+	}
+}
+
+impl pb::UpdateDevice {
+	pub fn new(entity: device::UpdateDevice, device_id: i32) -> Self {
+		pb::UpdateDevice {
+			device_id,
+			name: entity.name,
+			icon: entity.icon,
+			user_id: entity.user_id,
+		}
+	}
+}
+
+impl From<device::SyncItem> for pb::SyncItem {
+	fn from(item: device::SyncItem) -> Self {
+		pb::SyncItem {
+			client_id: item.client_id,
+			timestamp: item.timestamp,
+			hash: item.hash,
+		}
+	}
+}
+
+impl pb::SyncDevice {
+	pub fn new(entity: device::SyncDevice, device_id: i32) -> Self {
+		pb::SyncDevice {
+			device_id,
+			start_at: entity.start_at,
+			end_at: entity.end_at,
+			items: entity.items.into_iter().map(pb::SyncItem::from).collect(),
+		}
+	}
+}
+
+impl From<pb::SyncDeviceResult> for device::SyncDeviceResult {
+	fn from(result: pb::SyncDeviceResult) -> Self {
+		device::SyncDeviceResult {
+			update_client_ids: result.update_client_ids,
+			download_server_ids: result.download_server_ids,
+		}
+	}
+}
+
+// File
+impl From<pb::FileResp> for file::FileResp {
+	fn from(resp: pb::FileResp) -> Self {
+		file::FileResp { uri: resp.uri }
+	}
+}
+
+// Clipboard
+impl From<pb::ClipboardResp> for clipboard::ClipboardResp {
+	fn from(resp: pb::ClipboardResp) -> Self {
+		clipboard::ClipboardResp {
+			id: resp.id,
+			device_id: resp.device_id,
+			r#type: resp.r#type,
+			content: resp.content,
+			path: resp.path,
+			hash: resp.hash,
+			timestamp: resp.timestamp,
+		}
+	}
+}
+
+impl From<clipboard::DeleteClipboard> for pb::DeleteClipboard {
+	fn from(entity: clipboard::DeleteClipboard) -> Self {
+		pb::DeleteClipboard {
+			device_id: entity.device_id,
+			hash: entity.hash,
+		}
+	}
+}
+
+impl From<clipboard::CreateClipboard> for pb::CreateClipboard {
+	fn from(entity: clipboard::CreateClipboard) -> Self {
+		pb::CreateClipboard {
+			device_id: entity.device_id,
+			r#type: entity.r#type,
+			content: entity.content,
+			path: entity.path,
+			hash: entity.hash,
+			timestamp: entity.timestamp,
+		}
+	}
+}
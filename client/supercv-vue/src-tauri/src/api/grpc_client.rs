@@ -0,0 +1,180 @@
+use crate::api::common::{ClientDeviceTrait, ClientError, ClientTrait};
+use crate::api::grpc::pb;
+use crate::api::grpc::pb::clipboard_service_client::ClipboardServiceClient;
+use crate::api::grpc::pb::device_service_client::DeviceServiceClient;
+use crate::api::grpc::pb::file_service_client::FileServiceClient;
+use crate::api::grpc::pb::user_service_client::UserServiceClient;
+use crate::api::models::clipboard::{ClipboardResp, CreateClipboard, DeleteClipboard};
+use crate::api::models::device::{CreateDevice, Device, DeviceResp, SyncDevice, SyncDeviceResult, UpdateDevice};
+use crate::api::models::file::FileResp;
+use crate::api::models::user::{UserLogin, UserRegister, UserResetPassword, UserResp, UserSalt, UserSaltQuery, UserUpgradePassword};
+use tokio_stream::{Stream, StreamExt};
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Code, Status};
+
+impl From<Status> for ClientError {
+	fn from(status: Status) -> Self {
+		match status.code() {
+			Code::Unavailable | Code::DeadlineExceeded | Code::Cancelled => ClientError::NetworkError(status.message().to_string()),
+			code => ClientError::ApiError {
+				code: code as i32,
+				message: status.message().to_string(),
+			},
+		}
+	}
+}
+
+// tonic 的 client 克隆只是复制 Channel 句柄, 每次请求克隆一份以满足 &mut self
+#[derive(Clone)]
+pub struct GrpcClient {
+	user: UserServiceClient<Channel>,
+	device: DeviceServiceClient<Channel>,
+	file: FileServiceClient<Channel>,
+	clipboard: ClipboardServiceClient<Channel>,
+}
+
+impl GrpcClient {
+	pub async fn connect(url: String) -> Result<Self, ClientError> {
+		let channel = Endpoint::from_shared(url)
+			.map_err(|e| ClientError::NetworkError(e.to_string()))?
+			.connect()
+			.await
+			.map_err(|e| ClientError::NetworkError(e.to_string()))?;
+		Ok(Self::with_channel(channel))
+	}
+
+	pub fn with_channel(channel: Channel) -> Self {
+		GrpcClient {
+			user: UserServiceClient::new(channel.clone()),
+			device: DeviceServiceClient::new(channel.clone()),
+			file: FileServiceClient::new(channel.clone()),
+			clipboard: ClipboardServiceClient::new(channel),
+		}
+	}
+
+	// 服务端推送同一用户其他设备新增的剪贴板记录, 连接断开时流结束
+	pub async fn watch_clipboards(
+		&self,
+		user_id: i32,
+		exclude_device_id: i32,
+	) -> Result<impl Stream<Item = Result<ClipboardResp, ClientError>>, ClientError> {
+		let request = pb::WatchClipboards { user_id, exclude_device_id };
+		let stream = self.clipboard.clone().watch(request).await?.into_inner();
+		Ok(stream.map(|item| item.map(ClipboardResp::from).map_err(ClientError::from)))
+	}
+}
+
+#[async_trait::async_trait]
+impl ClientTrait for GrpcClient {
+	async fn register_user(&self, create_user: UserRegister) -> Result<UserResp, ClientError> {
+		let resp = self.user.clone().register(pb::UserRegister::from(create_user)).await?;
+		Ok(resp.into_inner().into())
+	}
+
+	async fn login_user(&self, entity: UserLogin) -> Result<UserResp, ClientError> {
+		let resp = self.user.clone().login(pb::UserLogin::from(entity)).await?;
+		Ok(resp.into_inner().into())
+	}
+
+	async fn reset_user(&self, entity: UserResetPassword) -> Result<UserResp, ClientError> {
+		let resp = self.user.clone().reset(pb::UserResetPassword::from(entity)).await?;
+		Ok(resp.into_inner().into())
+	}
+
+	async fn get_user_salt(&self, entity: UserSaltQuery) -> Result<UserSalt, ClientError> {
+		let resp = self.user.clone().salt(pb::UserSaltQuery::from(entity)).await?;
+		Ok(resp.into_inner().into())
+	}
+
+	async fn upgrade_user_password(&self, entity: UserUpgradePassword) -> Result<UserResp, ClientError> {
+		let resp = self.user.clone().upgrade_password(pb::UserUpgradePassword::from(entity)).await?;
+		Ok(resp.into_inner().into())
+	}
+
+	async fn upload_file(&self, file_path: &str) -> Result<FileResp, ClientError> {
+		let content = tokio::fs::read(file_path)
+			.await
+			.map_err(|e| ClientError::UnexpectedError(format!("Failed to read file: {}", e)))?;
+
+		// 从 file_path 中提取文件名
+		let file_name = std::path::Path::new(file_path)
+			.file_name()
+			.and_then(|name| name.to_str())
+			.ok_or_else(|| ClientError::UnexpectedError("Invalid file path".to_string()))?;
+
+		let request = pb::UploadFile {
+			file_name: file_name.to_string(),
+			content,
+		};
+		let resp = self.file.clone().upload(request).await?;
+		Ok(resp.into_inner().into())
+	}
+
+	async fn get_file(&self, uri: &str) -> Result<Vec<u8>, ClientError> {
+		let resp = self.file.clone().get(pb::GetFile { uri: uri.to_string() }).await?;
+		Ok(resp.into_inner().content)
+	}
+
+	async fn download_file(&self, uri: &str, dest: &std::path::Path) -> Result<u64, ClientError> {
+		let content = self.get_file(uri).await?;
+		tokio::fs::write(dest, &content)
+			.await
+			.map_err(|e| ClientError::UnexpectedError(format!("Failed to write file: {}", e)))?;
+		Ok(content.len() as u64)
+	}
+
+	async fn create_clipboard(&self, entity: CreateClipboard) -> Result<ClipboardResp, ClientError> {
+		let resp = self.clipboard.clone().create(pb::CreateClipboard::from(entity)).await?;
+		Ok(resp.into_inner().into())
+	}
+
+	async fn get_clipboards_by_id(&self, content_id: i32) -> Result<ClipboardResp, ClientError> {
+		let resp = self.clipboard.clone().get(pb::ClipboardId { content_id }).await?;
+		Ok(resp.into_inner().into())
+	}
+
+	async fn delete_clipboard(&self, entity: DeleteClipboard) -> Result<bool, ClientError> {
+		let resp = self.clipboard.clone().delete(pb::DeleteClipboard::from(entity)).await?;
+		Ok(resp.into_inner().deleted)
+	}
+
+	async fn create_device(&self, entity: CreateDevice) -> Result<DeviceResp, ClientError> {
+		let resp = self.device.clone().create(pb::CreateDevice::from(entity)).await?;
+		Ok(resp.into_inner().into())
+	}
+
+	async fn update_device(&self, entity: UpdateDevice, device_id: i32) -> Result<DeviceResp, ClientError> {
+		let resp = self.device.clone().update(pb::UpdateDevice::new(entity, device_id)).await?;
+		Ok(resp.into_inner().into())
+	}
+
+	async fn delete_device(&self, device_id: i32) -> Result<bool, ClientError> {
+		let resp = self.device.clone().delete(pb::DeviceId { device_id }).await?;
+		Ok(resp.into_inner().deleted)
+	}
+
+	async fn get_devices_by_user_id(&self, user_id: i32) -> Result<Vec<DeviceResp>, ClientError> {
+		let resp = self.device.clone().list_by_user(pb::UserId { user_id }).await?;
+		Ok(resp.into_inner().devices.into_iter().map(DeviceResp::from).collect())
+	}
+
+	async fn sync_device(&self, entity: SyncDevice, device_id: i32) -> Result<SyncDeviceResult, ClientError> {
+		let resp = self.device.clone().sync(pb::SyncDevice::new(entity, device_id)).await?;
+		Ok(resp.into_inner().into())
+	}
+}
+
+#[async_trait::async_trait]
+impl ClientDeviceTrait for GrpcClient {
+	async fn device_resp2device(&self, device_resp: DeviceResp) -> Result<Device, ClientError> {
+		let icon_bytes = self.get_file(&device_resp.icon).await?;
+		let local_path = device_resp.save_local_path(icon_bytes).await?;
+		Ok(Device {
+			id: device_resp.id,
+			name: device_resp.name,
+			uuid: device_resp.uuid,
+			user_id: device_resp.user_id,
+			icon: local_path,
+		})
+	}
+}
@@ -1,12 +1,29 @@
 use crate::api::common::{ClientDeviceTrait, ClientError, ClientTrait};
-use crate::api::models::clipboard::{ClipboardResp, CreateClipboard};
+use crate::api::models::clipboard::{ClipboardResp, CreateClipboard, DeleteClipboard};
 use crate::api::models::device::{CreateDevice, Device, DeviceResp, SyncDevice, SyncDeviceResult, UpdateDevice};
-use crate::api::models::file;
-use crate::api::models::file::FileResp;
-use crate::api::models::user::{User, UserLogin, UserRegister, UserResetPassword, UserResp};
-use reqwest::Client;
+use crate::api::models::file::{FileResp, ProgressCallback, TransferDirection, TransferProgress, UploadComplete, UploadInit, UploadSession};
+use crate::api::models::user::{
+	Token, TokenRefresh, UserLogin, UserRegister, UserResetPassword, UserResp, UserSalt, UserSaltQuery, UserUpgradePassword,
+};
+use log::warn;
+use reqwest::header::{CONTENT_LENGTH, RANGE};
+use reqwest::multipart::{Form, Part};
+use reqwest::{Body, Client, RequestBuilder, Response, StatusCode};
 use serde::Deserialize;
-use tokio::io::AsyncReadExt;
+use sha2::{Digest, Sha256};
+use std::io::{Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::{Mutex, RwLock};
+use tokio_stream::StreamExt;
+use tokio_util::io::ReaderStream;
+
+// 超过一个分片的文件走可续传的分片上传
+const DEFAULT_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+// 进度通知的最小间隔
+const PROGRESS_STEP: u64 = 1024 * 1024;
 
 #[derive(Deserialize)]
 struct ApiResponse<T> {
@@ -18,6 +35,40 @@ struct ApiResponse<T> {
 pub struct HttpClient {
 	client: Client,
 	base_url: String,
+	token: RwLock<Option<Token>>,
+	refresh_lock: Mutex<()>,
+	progress: Option<ProgressCallback>,
+	chunk_size: u64,
+}
+
+// 按 PROGRESS_STEP 节流通知传输进度, 传输完成时一定通知一次
+struct ProgressReporter {
+	callback: ProgressCallback,
+	name: String,
+	direction: TransferDirection,
+	total: u64,
+	transferred: AtomicU64,
+	reported: AtomicU64,
+}
+
+impl ProgressReporter {
+	fn reset(&self, transferred: u64) {
+		self.transferred.store(transferred, Ordering::Relaxed);
+	}
+
+	fn advance(&self, len: u64) {
+		let transferred = self.transferred.fetch_add(len, Ordering::Relaxed) + len;
+		let reported = self.reported.load(Ordering::Relaxed);
+		if transferred > reported && (transferred >= self.total || transferred - reported >= PROGRESS_STEP) {
+			self.reported.store(transferred, Ordering::Relaxed);
+			(self.callback)(TransferProgress {
+				name: self.name.clone(),
+				direction: self.direction,
+				transferred: transferred.min(self.total),
+				total: self.total,
+			});
+		}
+	}
 }
 
 impl HttpClient {
@@ -25,15 +76,195 @@ impl HttpClient {
 		HttpClient {
 			client: Client::new(),
 			base_url,
+			token: RwLock::new(None),
+			refresh_lock: Mutex::new(()),
+			progress: None,
+			chunk_size: DEFAULT_CHUNK_SIZE,
+		}
+	}
+
+	// 上传下载文件时回调传输进度
+	pub fn with_progress(mut self, progress: ProgressCallback) -> Self {
+		self.progress = Some(progress);
+		self
+	}
+
+	pub fn with_chunk_size(mut self, chunk_size: u64) -> Self {
+		self.chunk_size = chunk_size.max(1);
+		self
+	}
+
+	// 使用已保存的 token, 一般为启动时从凭据管理中读取的
+	pub fn with_token(mut self, token: Option<Token>) -> Self {
+		self.token = RwLock::new(token);
+		self
+	}
+
+	pub async fn token(&self) -> Option<Token> {
+		self.token.read().await.clone()
+	}
+
+	// 请求都带上 Authorization, 401 时刷新 token 后重试一次. 可能发送两次, build 每次重新构造请求
+	async fn send<F>(&self, build: F) -> Result<Response, ClientError>
+	where
+		F: Fn(&Client) -> RequestBuilder,
+	{
+		self.try_send(|client| Ok(build(client))).await
+	}
+
+	// 构造请求可能失败, 例如流式请求体每次都要重新打开文件
+	async fn try_send<F>(&self, build: F) -> Result<Response, ClientError>
+	where
+		F: Fn(&Client) -> Result<RequestBuilder, ClientError>,
+	{
+		let access_token = self.token.read().await.as_ref().map(|token| token.access_token.clone());
+		let response = self.send_with_token(&build, access_token.as_deref()).await?;
+		let stale = match access_token {
+			Some(access_token) if response.status() == StatusCode::UNAUTHORIZED => access_token,
+			_ => return Ok(response),
+		};
+		match self.refresh(&stale).await? {
+			Some(access_token) => self.send_with_token(&build, Some(&access_token)).await,
+			None => Ok(response),
+		}
+	}
+
+	// 注册登录等不需要 token 的请求
+	async fn send_anonymous<F>(&self, build: F) -> Result<Response, ClientError>
+	where
+		F: Fn(&Client) -> RequestBuilder,
+	{
+		self.send_with_token(&|client| Ok(build(client)), None).await
+	}
+
+	async fn send_with_token<F>(&self, build: &F, access_token: Option<&str>) -> Result<Response, ClientError>
+	where
+		F: Fn(&Client) -> Result<RequestBuilder, ClientError>,
+	{
+		let mut request = build(&self.client)?;
+		if let Some(access_token) = access_token {
+			request = request.bearer_auth(access_token);
+		}
+		request.send().await.map_err(ClientError::Http)
+	}
+
+	// 多个请求同时 401 时只刷新一次, 返回新的 access_token. refresh_token 也失效时清除 token, 需要重新登录
+	async fn refresh(&self, stale: &str) -> Result<Option<String>, ClientError> {
+		let _guard = self.refresh_lock.lock().await;
+		let refresh_token = match self.token.read().await.as_ref() {
+			Some(token) if token.access_token != stale => return Ok(Some(token.access_token.clone())),
+			Some(token) => token.refresh_token.clone(),
+			None => return Ok(None),
+		};
+
+		let url = format!("{}/user/refresh", self.base_url);
+		let response = self
+			.client
+			.post(&url)
+			.json(&TokenRefresh { refresh_token })
+			.send()
+			.await
+			.map_err(ClientError::Http)?;
+		match self.handle_response::<Token>(response).await {
+			Ok(token) => {
+				if let Err(e) = token.save().await {
+					warn!("Failed to save refreshed token: {}", e);
+				}
+				let access_token = token.access_token.clone();
+				*self.token.write().await = Some(token);
+				Ok(Some(access_token))
+			},
+			Err(ClientError::ApiError { code: 401, .. }) => {
+				warn!("Refresh token expired, login required");
+				*self.token.write().await = None;
+				if let Err(e) = Token::clear().await {
+					warn!("Failed to clear token: {}", e);
+				}
+				Ok(None)
+			},
+			Err(e) => Err(e),
+		}
+	}
+
+	fn reporter(&self, name: &str, direction: TransferDirection, total: u64, start: u64) -> Option<Arc<ProgressReporter>> {
+		self.progress.clone().map(|callback| {
+			Arc::new(ProgressReporter {
+				callback,
+				name: name.to_string(),
+				direction,
+				total,
+				transferred: AtomicU64::new(start),
+				reported: AtomicU64::new(start),
+			})
+		})
+	}
+
+	// 一次上传整个文件, 请求体边读边发. 服务端从 token 中取得用户
+	async fn upload_single(&self, path: &Path, file_name: &str, size: u64) -> Result<FileResp, ClientError> {
+		let url = format!("{}/file", self.base_url);
+		let response = self
+			.try_send(|client| {
+				let reporter = self.reporter(file_name, TransferDirection::Upload, size, 0);
+				let part = Part::stream_with_length(file_body(path, 0, size, reporter)?, size).file_name(file_name.to_string());
+				Ok(client.post(&url).multipart(Form::new().part("file", part)))
+			})
+			.await?;
+		self.handle_response(response).await
+	}
+
+	// 大文件分片上传, 中断后从服务端记录的位置继续, 完成时服务端校验整个文件的 sha256
+	async fn upload_chunked(&self, path: &Path, file_name: &str, size: u64) -> Result<FileResp, ClientError> {
+		let hash = sha256_file(path).await?;
+		let url = format!("{}/file/upload", self.base_url);
+		let init = UploadInit {
+			file_name: file_name.to_string(),
+			size,
+			hash: hash.clone(),
+		};
+		let response = self.send(|client| client.post(&url).json(&init)).await?;
+		let session: UploadSession = self.handle_response(response).await?;
+
+		let url = format!("{}/file/upload/{}", self.base_url, session.upload_id);
+		let reporter = self.reporter(file_name, TransferDirection::Upload, size, session.offset);
+		let mut offset = session.offset;
+		while offset < size {
+			let len = self.chunk_size.min(size - offset);
+			let response = self
+				.try_send(|client| {
+					// 401 后重发同一分片时, 上一次已计入的字节不再重复计算
+					if let Some(reporter) = &reporter {
+						reporter.reset(offset);
+					}
+					let body = file_body(path, offset, len, reporter.clone())?;
+					Ok(client.put(&url).query(&[("offset", offset)]).header(CONTENT_LENGTH, len).body(body))
+				})
+				.await?;
+			let session: UploadSession = self.handle_response(response).await?;
+			// 以服务端实际收到的字节数为准, 没有前进时停止, 避免死循环
+			if session.offset <= offset {
+				return Err(ClientError::UnexpectedError(format!("Upload stalled at offset {}", offset)));
+			}
+			offset = session.offset;
+		}
+
+		let url = format!("{}/complete", url);
+		let response = self.send(|client| client.post(&url).json(&UploadComplete { hash: hash.clone() })).await?;
+		self.handle_response(response).await
+	}
+
+	// 注册和登录返回的 token 用于之后的请求
+	async fn set_token_from(&self, resp: &UserResp) {
+		if let Some(token) = &resp.token {
+			*self.token.write().await = Some(token.clone());
 		}
 	}
 
 	async fn handle_response<T: for<'de> Deserialize<'de>>(&self, response: reqwest::Response) -> Result<T, ClientError> {
 		let status = response.status();
-		let body = response.text().await.map_err(|e| ClientError::NetworkError(e.to_string()))?;
+		let body = response.text().await.map_err(ClientError::Http)?;
 
 		if status.is_success() {
-			let api_response: ApiResponse<T> = serde_json::from_str(&body).map_err(|e| ClientError::SerializationError(e.to_string()))?;
+			let api_response: ApiResponse<T> = serde_json::from_str(&body)?;
 
 			if api_response.code == 200 {
 				api_response.data.ok_or_else(|| ClientError::ApiError {
@@ -59,84 +290,122 @@ impl HttpClient {
 impl ClientTrait for HttpClient {
 	async fn register_user(&self, create_user: UserRegister) -> Result<UserResp, ClientError> {
 		let url = format!("{}/user/register", self.base_url);
-		let response = self
-			.client
-			.post(&url)
-			.json(&create_user)
-			.send()
-			.await
-			.map_err(|e| ClientError::NetworkError(e.to_string()))?;
-		self.handle_response(response).await
+		let response = self.send_anonymous(|client| client.post(&url).json(&create_user)).await?;
+		let resp = self.handle_response(response).await?;
+		self.set_token_from(&resp).await;
+		Ok(resp)
 	}
 
 	async fn login_user(&self, entity: UserLogin) -> Result<UserResp, ClientError> {
 		let url = format!("{}/user/login", self.base_url);
-		let response = self
-			.client
-			.post(&url)
-			.json(&entity)
-			.send()
-			.await
-			.map_err(|e| ClientError::NetworkError(e.to_string()))?;
-		self.handle_response(response).await
+		let response = self.send_anonymous(|client| client.post(&url).json(&entity)).await?;
+		let resp = self.handle_response(response).await?;
+		self.set_token_from(&resp).await;
+		Ok(resp)
 	}
 
 	async fn reset_user(&self, entity: UserResetPassword) -> Result<UserResp, ClientError> {
 		let url = format!("{}/user/reset", self.base_url);
-		let response = self
-			.client
-			.post(&url)
-			.json(&entity)
-			.send()
-			.await
-			.map_err(|e| ClientError::NetworkError(e.to_string()))?;
+		let response = self.send_anonymous(|client| client.post(&url).json(&entity)).await?;
 		self.handle_response(response).await
 	}
 
-	async fn upload_file(&self, user_id: i32, file_path: &str) -> Result<FileResp, ClientError> {
-		let url = format!("{}/file/{}", self.base_url, user_id);
-
-		let mut file = tokio::fs::File::open(file_path)
-			.await
-			.map_err(|e| ClientError::UnexpectedError(format!("Failed to open file: {}", e)))?;
+	async fn get_user_salt(&self, entity: UserSaltQuery) -> Result<UserSalt, ClientError> {
+		let url = format!("{}/user/salt", self.base_url);
+		let response = self.send_anonymous(|client| client.post(&url).json(&entity)).await?;
+		self.handle_response(response).await
+	}
 
-		let mut buffer = Vec::new();
-		file.read_to_end(&mut buffer)
-			.await
-			.map_err(|e| ClientError::UnexpectedError(format!("Failed to read file: {}", e)))?;
+	async fn upgrade_user_password(&self, entity: UserUpgradePassword) -> Result<UserResp, ClientError> {
+		let url = format!("{}/user/password", self.base_url);
+		let response = self.send(|client| client.post(&url).json(&entity)).await?;
+		self.handle_response(response).await
+	}
 
+	async fn upload_file(&self, file_path: &str) -> Result<FileResp, ClientError> {
+		let path = Path::new(file_path);
 		// 从 file_path 中提取文件名
-		let file_name = std::path::Path::new(file_path)
+		let file_name = path
 			.file_name()
 			.and_then(|name| name.to_str())
 			.ok_or_else(|| ClientError::UnexpectedError("Invalid file path".to_string()))?;
+		let size = tokio::fs::metadata(path).await.map_err(ClientError::Io)?.len();
+		if size > self.chunk_size {
+			match self.upload_chunked(path, file_name, size).await {
+				// 服务端不支持分片上传时退回一次上传. 旧服务端把 /file/upload 当作 /file/{user_id}, 返回 422
+				Err(ClientError::ApiError { code: 404 | 405 | 422, .. }) => {
+					warn!("Chunked upload is not supported by the server, upload {} in one request", file_name)
+				},
+				result => return result,
+			}
+		}
+		self.upload_single(path, file_name, size).await
+	}
 
-		let part = reqwest::multipart::Part::bytes(buffer).file_name(file_name.to_string());
-
-		let form = reqwest::multipart::Form::new().part("file", part);
+	// 先写入 dest.part, 中断后用 Range 续传, 完成后再改名为 dest
+	async fn download_file(&self, uri: &str, dest: &Path) -> Result<u64, ClientError> {
+		let url = format!("{}{}", self.base_url, uri);
+		let part_path = PathBuf::from(format!("{}.part", dest.display()));
+		let mut existing = tokio::fs::metadata(&part_path).await.map(|meta| meta.len()).unwrap_or(0);
+		let response = loop {
+			let response = self
+				.send(|client| match existing {
+					0 => client.get(&url),
+					existing => client.get(&url).header(RANGE, format!("bytes={}-", existing)),
+				})
+				.await?;
+			// 本地的部分文件比服务端的还大, 服务端文件已变化, 重新下载
+			if response.status() == StatusCode::RANGE_NOT_SATISFIABLE && existing > 0 {
+				existing = 0;
+				continue;
+			}
+			break response;
+		};
 
-		let response = self
-			.client
-			.post(&url)
-			.multipart(form)
-			.send()
+		let status = response.status();
+		if !status.is_success() {
+			return Err(ClientError::ApiError {
+				code: status.as_u16() as i32,
+				message: format!("Failed to get file: {}", status),
+			});
+		}
+		// 服务端不支持 Range 时返回整个文件
+		let start = if status == StatusCode::PARTIAL_CONTENT { existing } else { 0 };
+		let mut file = tokio::fs::OpenOptions::new()
+			.create(true)
+			.write(true)
+			.append(start > 0)
+			.truncate(start == 0)
+			.open(&part_path)
 			.await
-			.map_err(|e| ClientError::NetworkError(e.to_string()))?;
+			.map_err(ClientError::Io)?;
 
-		self.handle_response(response).await
+		let total = start + response.content_length().unwrap_or(0);
+		let name = dest.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+		let reporter = self.reporter(&name, TransferDirection::Download, total, start);
+		let mut written = start;
+		let mut stream = response.bytes_stream();
+		while let Some(chunk) = stream.next().await {
+			let chunk = chunk.map_err(ClientError::Http)?;
+			file.write_all(&chunk).await.map_err(ClientError::Io)?;
+			written += chunk.len() as u64;
+			if let Some(reporter) = &reporter {
+				reporter.advance(chunk.len() as u64);
+			}
+		}
+		file.flush().await.map_err(ClientError::Io)?;
+		drop(file);
+		tokio::fs::rename(&part_path, dest).await.map_err(ClientError::Io)?;
+		Ok(written)
 	}
 
 	async fn get_file(&self, uri: &str) -> Result<Vec<u8>, ClientError> {
 		let url = format!("{}{}", self.base_url, uri);
 
-		let response = self.client.get(&url).send().await.map_err(|e| ClientError::NetworkError(e.to_string()))?;
+		let response = self.send(|client| client.get(&url)).await?;
 
 		if response.status().is_success() {
-			response
-				.bytes()
-				.await
-				.map(|b| b.to_vec())
-				.map_err(|e| ClientError::NetworkError(e.to_string()))
+			response.bytes().await.map(|b| b.to_vec()).map_err(ClientError::Http)
 		} else {
 			Err(ClientError::ApiError {
 				code: response.status().as_u16() as i32,
@@ -147,80 +416,83 @@ impl ClientTrait for HttpClient {
 
 	async fn create_clipboard(&self, entity: CreateClipboard) -> Result<ClipboardResp, ClientError> {
 		let url = format!("{}/content", self.base_url);
-		let response = self
-			.client
-			.post(&url)
-			.json(&entity)
-			.send()
-			.await
-			.map_err(|e| ClientError::NetworkError(e.to_string()))?;
+		let response = self.send(|client| client.post(&url).json(&entity)).await?;
 		self.handle_response(response).await
 	}
 
 	async fn get_clipboards_by_id(&self, content_id: i32) -> Result<ClipboardResp, ClientError> {
 		let url = format!("{}/content/{}", self.base_url, content_id);
-		let response = self.client.get(&url).send().await.map_err(|e| ClientError::NetworkError(e.to_string()))?;
+		let response = self.send(|client| client.get(&url)).await?;
+		self.handle_response(response).await
+	}
+
+	async fn delete_clipboard(&self, entity: DeleteClipboard) -> Result<bool, ClientError> {
+		let url = format!("{}/content/{}/{}", self.base_url, entity.device_id, entity.hash);
+		let response = self.send(|client| client.delete(&url)).await?;
 		self.handle_response(response).await
 	}
 
 	async fn create_device(&self, entity: CreateDevice) -> Result<DeviceResp, ClientError> {
 		let url = format!("{}/device", self.base_url);
-		let response = self
-			.client
-			.post(&url)
-			.json(&entity)
-			.send()
-			.await
-			.map_err(|e| ClientError::NetworkError(e.to_string()))?;
+		let response = self.send(|client| client.post(&url).json(&entity)).await?;
 		self.handle_response(response).await
 	}
 
 	async fn update_device(&self, entity: UpdateDevice, device_id: i32) -> Result<DeviceResp, ClientError> {
 		let url = format!("{}/device/{}", self.base_url, device_id);
-		let response = self
-			.client
-			.post(&url)
-			.json(&entity)
-			.send()
-			.await
-			.map_err(|e| ClientError::NetworkError(e.to_string()))?;
+		let response = self.send(|client| client.post(&url).json(&entity)).await?;
 		self.handle_response(response).await
 	}
 
 	async fn delete_device(&self, device_id: i32) -> Result<bool, ClientError> {
 		let url = format!("{}/device/{}", self.base_url, device_id);
-		let response = self
-			.client
-			.delete(&url)
-			.send()
-			.await
-			.map_err(|e| ClientError::NetworkError(e.to_string()))?;
+		let response = self.send(|client| client.delete(&url)).await?;
 		self.handle_response(response).await
 	}
 
 	async fn get_devices_by_user_id(&self, user_id: i32) -> Result<Vec<DeviceResp>, ClientError> {
 		let url = format!("{}/device/user/{}", self.base_url, user_id);
-		let response = self.client.get(&url).send().await.map_err(|e| ClientError::NetworkError(e.to_string()))?;
+		let response = self.send(|client| client.get(&url)).await?;
 		self.handle_response(response).await
 	}
 
 	async fn sync_device(&self, entity: SyncDevice, device_id: i32) -> Result<SyncDeviceResult, ClientError> {
 		let url = format!("{}/device/{}/sync", self.base_url, device_id);
-		let response = self
-			.client
-			.post(&url)
-			.json(&entity)
-			.send()
-			.await
-			.map_err(|e| ClientError::NetworkError(e.to_string()))?;
+		let response = self.send(|client| client.post(&url).json(&entity)).await?;
 		self.handle_response(response).await
 	}
 }
 
+// 从 offset 开始读取 len 字节的流式请求体, 不把文件读入内存
+fn file_body(path: &Path, offset: u64, len: u64, reporter: Option<Arc<ProgressReporter>>) -> Result<Body, ClientError> {
+	let mut file = std::fs::File::open(path).map_err(ClientError::Io)?;
+	file.seek(SeekFrom::Start(offset)).map_err(ClientError::Io)?;
+	let stream = ReaderStream::new(tokio::fs::File::from_std(file).take(len)).map(move |chunk| {
+		if let (Ok(bytes), Some(reporter)) = (&chunk, &reporter) {
+			reporter.advance(bytes.len() as u64);
+		}
+		chunk
+	});
+	Ok(Body::wrap_stream(stream))
+}
+
+async fn sha256_file(path: &Path) -> Result<String, ClientError> {
+	let mut file = tokio::fs::File::open(path).await.map_err(ClientError::Io)?;
+	let mut hasher = Sha256::new();
+	let mut buffer = vec![0u8; 64 * 1024];
+	loop {
+		match file.read(&mut buffer).await.map_err(ClientError::Io)? {
+			0 => break,
+			n => hasher.update(&buffer[..n]),
+		}
+	}
+	Ok(format!("{:x}", hasher.finalize()))
+}
+
 #[async_trait::async_trait]
 impl ClientDeviceTrait for HttpClient {
 	async fn device_resp2device(&self, device_resp: DeviceResp) -> Result<Device, ClientError> {
-		let icon_bytes = self.get_file(&*device_resp.icon).await?;
+		let icon_bytes = self.get_file(&device_resp.icon).await?;
 		let local_path = device_resp.save_local_path(icon_bytes).await?;
 		Ok(Device {
 			id: device_resp.id,
@@ -1,9 +1,10 @@
+#[allow(clippy::module_inception)]
 pub mod api;
 pub mod api_client;
 pub mod common;
 pub mod grpc;
-mod grpc_client;
-pub mod http;
+pub mod grpc_client;
 pub mod http_client;
 pub mod models;
+pub mod sync;
 pub mod tests;
@@ -22,3 +22,10 @@ pub struct CreateClipboard {
 	pub hash: String,
 	pub timestamp: i64,
 }
+
+// 删除设备上传的记录, hash 为加密后的 hash
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeleteClipboard {
+	pub device_id: i32,
+	pub hash: String,
+}
@@ -70,19 +70,15 @@ impl DeviceResp {
 		let icons_path = CONFIG.read().unwrap().icon_path.clone();
 		let icon_name = format!("{}.png", self.uuid);
 		let icon_path = icons_path.join(icon_name);
-		fs::write(&icon_path, icon_bytes)
-			.await
-			.map_err(|e| ClientError::UnexpectedError(e.to_string()))?;
+		fs::write(&icon_path, icon_bytes).await?;
 		Ok(icon_path.display().to_string())
 	}
 }
 
 impl Device {
 	pub async fn save(&self) -> Result<(), ClientError> {
-		let content = toml::to_string(self).map_err(|e| ClientError::UnexpectedError(e.to_string()))?;
-		fs::write(&*DEVICE_CACHE_PATH, content)
-			.await
-			.map_err(|e| ClientError::UnexpectedError(e.to_string()))?;
+		let content = toml::to_string(self)?;
+		fs::write(&*DEVICE_CACHE_PATH, content).await?;
 		Ok(())
 	}
 
@@ -91,10 +87,8 @@ impl Device {
 		if !DEVICE_CACHE_PATH.exists() {
 			return Ok(None);
 		}
-		let content = fs::read_to_string(&*DEVICE_CACHE_PATH)
-			.await
-			.map_err(|e| ClientError::UnexpectedError(e.to_string()))?;
-		let device: Device = toml::from_str(&content).map_err(|e| ClientError::UnexpectedError(e.to_string()))?;
+		let content = fs::read_to_string(&*DEVICE_CACHE_PATH).await?;
+		let device: Device = toml::from_str(&content)?;
 		Ok(Some(device))
 	}
 }
@@ -1,6 +1,44 @@
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FileResp {
-    pub uri: String,
-}
\ No newline at end of file
+	pub uri: String,
+}
+
+// 分片上传: 先登记文件, 服务端返回已收到的字节数, 断点续传时从该位置继续
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UploadInit {
+	pub file_name: String,
+	pub size: u64,
+	pub hash: String, // 整个文件的 sha256, 完成时由服务端校验
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UploadSession {
+	pub upload_id: String,
+	pub offset: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UploadComplete {
+	pub hash: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferDirection {
+	Upload,
+	Download,
+}
+
+// 文件传输进度, 通过事件转发给前端
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransferProgress {
+	pub name: String,
+	pub direction: TransferDirection,
+	pub transferred: u64,
+	pub total: u64,
+}
+
+pub type ProgressCallback = Arc<dyn Fn(TransferProgress) + Send + Sync>;
@@ -1,7 +1,9 @@
 use crate::api::common::ClientError;
 use crate::utils::config::CONFIG;
+use crate::utils::password::PasswordKdf;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use tokio::fs;
 
@@ -10,8 +12,16 @@ lazy_static! {
 		let cache_dir = CONFIG.read().unwrap().cache_dir.clone();
 		cache_dir.join("user.toml")
 	};
+	static ref KDF_CACHE_PATH: PathBuf = {
+		let cache_dir = CONFIG.read().unwrap().cache_dir.clone();
+		cache_dir.join("kdf.toml")
+	};
 }
 
+// 系统凭据管理中的条目名
+const KEYRING_SERVICE: &str = "supercv";
+const KEYRING_TOKEN_USER: &str = "token";
+
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct UserResp {
 	pub id: i32,
@@ -21,6 +31,20 @@ pub struct UserResp {
 	pub encrypted_dek: String,
 	pub created_at: i64,
 	pub updated_at: i64,
+	#[serde(default)]
+	pub token: Option<Token>, // 注册和登录时返回
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Token {
+	pub access_token: String,
+	pub refresh_token: String,
+	pub expires_at: i64, // access_token 过期时间
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TokenRefresh {
+	pub refresh_token: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -29,6 +53,8 @@ pub struct UserRegister {
 	pub email: String,
 	pub password_hash: String,
 	pub encrypted_dek: String,
+	pub salt: String,
+	pub kdf: PasswordKdf,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -42,14 +68,40 @@ pub struct UserResetPassword {
 	pub username: String,
 	pub email: String,
 	pub password_hash: String,
+	pub salt: String,
+	pub kdf: PasswordKdf,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UserSaltQuery {
+	pub username: String,
+}
+
+// 登录前取回的 salt 和派生方式, 旧账户为 Legacy
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct UserSalt {
+	#[serde(default)]
+	pub salt: String,
+	#[serde(default)]
+	pub kdf: PasswordKdf,
+}
+
+// 旧账户登录成功后换成新的 password_hash, 需要带上旧的 password_hash 校验
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UserUpgradePassword {
+	pub username: String,
+	pub password_hash: String,
+	pub new_password_hash: String,
+	pub salt: String,
+	pub kdf: PasswordKdf,
 }
 
+// 缓存在 user.toml 的账户信息, 不包括 password_hash (可以直接用来登录)
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct User {
 	pub id: i32,
 	pub username: String,
 	pub email: String,
-	pub password_hash: String,
 	pub encrypted_dek: String,
 }
 
@@ -59,7 +111,6 @@ impl From<UserResp> for User {
 			id: resp.id,
 			username: resp.username,
 			email: resp.email,
-			password_hash: resp.password_hash,
 			encrypted_dek: resp.encrypted_dek,
 		}
 	}
@@ -67,10 +118,8 @@ impl From<UserResp> for User {
 
 impl User {
 	pub async fn save(&self) -> Result<(), ClientError> {
-		let content = toml::to_string(self).map_err(|e| ClientError::UnexpectedError(e.to_string()))?;
-		fs::write(&*USER_CACHE_PATH, content)
-			.await
-			.map_err(|e| ClientError::UnexpectedError(e.to_string()))?;
+		let content = toml::to_string(self)?;
+		fs::write(&*USER_CACHE_PATH, content).await?;
 		Ok(())
 	}
 
@@ -78,10 +127,92 @@ impl User {
 		if !USER_CACHE_PATH.exists() {
 			return Ok(None);
 		}
-		let content = fs::read_to_string(&*USER_CACHE_PATH)
-			.await
-			.map_err(|e| ClientError::UnexpectedError(e.to_string()))?;
-		let user: User = toml::from_str(&content).map_err(|e| ClientError::UnexpectedError(e.to_string()))?;
+		let content = fs::read_to_string(&*USER_CACHE_PATH).await?;
+		let user: User = toml::from_str(&content)?;
+		// 旧版本写入的 user.toml 包括明文的 password_hash, 读取后重新写入去掉
+		if toml::to_string(&user)? != content {
+			user.save().await?;
+		}
 		Ok(Some(user))
 	}
+
+	// 退出登录时删除缓存的用户
+	pub async fn clear() -> Result<(), ClientError> {
+		if !USER_CACHE_PATH.exists() {
+			return Ok(());
+		}
+		Ok(fs::remove_file(&*USER_CACHE_PATH).await?)
+	}
+}
+
+// 本机登录或注册过的账户使用的密码派生方式. 已经是 Argon2id 的账户不再接受服务端返回的 Legacy,
+// 避免中间人伪造 salt 接口的响应, 让客户端发送不加盐的 password_hash
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct AccountKdfs {
+	#[serde(default)]
+	accounts: HashMap<String, PasswordKdf>,
+}
+
+impl AccountKdfs {
+	pub fn get(&self, username: &str) -> Option<PasswordKdf> {
+		self.accounts.get(username).copied()
+	}
+
+	pub fn set(&mut self, username: &str, kdf: PasswordKdf) {
+		self.accounts.insert(username.to_string(), kdf);
+	}
+
+	pub async fn save(&self) -> Result<(), ClientError> {
+		let content = toml::to_string(self)?;
+		fs::write(&*KDF_CACHE_PATH, content).await?;
+		Ok(())
+	}
+
+	pub async fn load() -> Result<AccountKdfs, ClientError> {
+		if !KDF_CACHE_PATH.exists() {
+			return Ok(AccountKdfs::default());
+		}
+		let content = fs::read_to_string(&*KDF_CACHE_PATH).await?;
+		Ok(toml::from_str(&content)?)
+	}
+}
+
+// token 保存在系统的凭据管理 (Keychain / Credential Manager / Secret Service), 不写入 user.toml
+impl Token {
+	pub async fn save(&self) -> Result<(), ClientError> {
+		let content = serde_json::to_string(self)?;
+		with_keyring(move |entry| entry.set_password(&content)).await?.map_err(keyring_error)
+	}
+
+	pub async fn load() -> Result<Option<Token>, ClientError> {
+		let content = match with_keyring(|entry| entry.get_password()).await? {
+			Ok(content) => content,
+			Err(keyring::Error::NoEntry) => return Ok(None),
+			Err(e) => return Err(keyring_error(e)),
+		};
+		let token = serde_json::from_str(&content)?;
+		Ok(Some(token))
+	}
+
+	pub async fn clear() -> Result<(), ClientError> {
+		match with_keyring(|entry| entry.delete_password()).await? {
+			Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+			Err(e) => Err(keyring_error(e)),
+		}
+	}
+}
+
+// 凭据管理的接口是阻塞的 (Linux 下经过 D-Bus), 放到 blocking 线程执行
+async fn with_keyring<R, F>(f: F) -> Result<keyring::Result<R>, ClientError>
+where
+	R: Send + 'static,
+	F: FnOnce(&keyring::Entry) -> keyring::Result<R> + Send + 'static,
+{
+	tokio::task::spawn_blocking(move || f(&keyring::Entry::new(KEYRING_SERVICE, KEYRING_TOKEN_USER)?))
+		.await
+		.map_err(|e| ClientError::UnexpectedError(e.to_string()))
+}
+
+fn keyring_error(e: keyring::Error) -> ClientError {
+	ClientError::UnexpectedError(format!("Credential store: {}", e))
 }
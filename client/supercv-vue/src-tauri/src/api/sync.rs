@@ -0,0 +1,987 @@
+// 后台同步: 按时间窗口把本地记录的摘要发给服务端,
+// 上传服务端缺少的记录 (update_client_ids), 下载本地缺少的记录 (download_server_ids).
+// 内容, 路径和文件都用 DEK 端到端加密, 服务端只能看到密文和带密钥的哈希.
+// 离线时新建和上传失败的记录进入 outbox 表, 联网后按退避时间重放. 本地清除的记录也通过 outbox 在服务端删除,
+// 删除完成前和本地还在撤销窗口内的记录都不会被下载回来. 登录和设备管理等请求由用户发起,
+// 失败时直接把错误返回给界面, 不进入 outbox.
+// 每次同步传输的文件总量受 max_sync_bytes 限制, 超出后停在当前窗口, 下次从该窗口继续
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{TimeZone, Utc};
+use lazy_static::lazy_static;
+use log::{error, info, warn};
+use rand::Rng;
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::fs;
+use tokio::task::JoinHandle;
+
+use crate::api::common::{ClientError, ClientTrait};
+use crate::api::http_client::HttpClient;
+use crate::api::models::clipboard::{ClipboardResp, CreateClipboard, DeleteClipboard};
+use crate::api::models::device::{Device, SyncDevice, SyncItem};
+use crate::api::models::user::{Token, User};
+use crate::clipboard_helper::parse_paths;
+use crate::core::events::{self, ClipboardEvent};
+use crate::core::pasteboard::{ContentType, PasteboardContent};
+use crate::db::crud;
+use crate::db::entities::host_clipboard::Model;
+use crate::db::entities::outbox::{self, OutboxAction};
+use crate::utils::cipher::{CipherError, CryptoHelper, PayloadCipher};
+use crate::utils::config::{SyncConfig, CONFIG};
+use crate::utils::time::get_current_timestamp;
+
+lazy_static! {
+	static ref SYNC_TASK: std::sync::Mutex<Option<JoinHandle<()>>> = std::sync::Mutex::new(None);
+}
+
+// 加密前的 content, 原始 hash 也放在密文里, 下载后本地记录的 hash 与来源设备一致
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct SealedContent {
+	content: String,
+	hash: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SyncCheckpoint {
+	pub device_id: i32,
+	pub synced_until: i64, // 该时间之前的窗口都已同步完成
+}
+
+impl SyncCheckpoint {
+	pub async fn save(&self, path: &Path) -> Result<(), ClientError> {
+		let content = toml::to_string(self)?;
+		fs::write(path, content).await?;
+		Ok(())
+	}
+
+	pub async fn load(path: &Path) -> Result<Option<SyncCheckpoint>, ClientError> {
+		if !path.exists() {
+			return Ok(None);
+		}
+		let content = fs::read_to_string(path).await?;
+		let checkpoint = toml::from_str(&content)?;
+		Ok(Some(checkpoint))
+	}
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct SyncReport {
+	pub windows: usize,
+	pub uploaded: usize,
+	pub downloaded: usize,
+	pub queued: usize, // 上传失败, 进入 outbox 的记录数
+	pub limited: bool, // 达到 max_sync_bytes, 剩余的窗口留到下次同步
+}
+
+// 单条记录的上传或下载结果
+#[derive(Debug, PartialEq)]
+enum Transfer {
+	Done,
+	Skipped, // 本地文件已删除或无法解密
+	Limited, // 达到本次同步的流量上限
+}
+
+pub struct SyncEngine<T: ClientTrait> {
+	client: T,
+	db: DatabaseConnection,
+	device: Device,
+	cipher: PayloadCipher,
+	config: SyncConfig,
+	checkpoint_path: PathBuf,
+	files_path: PathBuf, // 下载的图片和文件
+	spent: AtomicU64,    // 本次同步已传输的文件字节数
+}
+
+// 用 super_key 解开本地缓存用户的 DEK, 启动后台同步, 已在运行时重新启动
+pub async fn start(db: DatabaseConnection, super_key: &str) -> Result<(), ClientError> {
+	let config = CONFIG.read().unwrap().user_config.sync_config.clone();
+	if !config.enabled || config.server_url.is_empty() {
+		return Err(ClientError::UnexpectedError("Sync is disabled".to_string()));
+	}
+	let user = User::load()
+		.await?
+		.ok_or(ClientError::UnexpectedError("User not logged in".to_string()))?;
+	let device = Device::load()
+		.await?
+		.ok_or(ClientError::UnexpectedError("Device not registered".to_string()))?;
+	let dek = CryptoHelper::open_dek(super_key, &user.encrypted_dek)?;
+
+	let client = HttpClient::new(config.server_url.clone())
+		.with_token(Token::load().await?)
+		.with_progress(Arc::new(|progress| events::publish(ClipboardEvent::TransferProgress(progress))));
+	let task = SyncEngine::new(client, db, device, PayloadCipher::new(&dek), config).spawn();
+	if let Some(previous) = SYNC_TASK.lock().unwrap().replace(task) {
+		previous.abort();
+	}
+	Ok(())
+}
+
+// 停止后台同步, 例如退出登录时
+pub fn stop() {
+	if let Some(task) = SYNC_TASK.lock().unwrap().take() {
+		task.abort();
+	}
+}
+
+impl<T: ClientTrait + Send + Sync + 'static> SyncEngine<T> {
+	pub fn new(client: T, db: DatabaseConnection, device: Device, cipher: PayloadCipher, config: SyncConfig) -> Self {
+		let (checkpoint_path, files_path) = {
+			let config = CONFIG.read().unwrap();
+			(config.cache_dir.join("sync.toml"), config.files_path.join("sync"))
+		};
+		Self {
+			client,
+			db,
+			device,
+			cipher,
+			config,
+			checkpoint_path,
+			files_path,
+			spent: AtomicU64::new(0),
+		}
+	}
+
+	pub fn with_paths(mut self, checkpoint_path: PathBuf, files_path: PathBuf) -> Self {
+		self.checkpoint_path = checkpoint_path;
+		self.files_path = files_path;
+		self
+	}
+
+	// 定时同步, 期间 outbox 中有到期的记录时先重放
+	pub fn spawn(self) -> JoinHandle<()> {
+		tokio::spawn(async move {
+			let mut next_sync = 0;
+			loop {
+				match self.flush_outbox().await {
+					Ok(0) => {},
+					Ok(replayed) => info!("Outbox replayed {} entries", replayed),
+					Err(e) => error!("Outbox replay failed: {}", e),
+				}
+				if get_current_timestamp() >= next_sync {
+					match self.sync_once().await {
+						Ok(report) => info!("Sync finished: {:?}", report),
+						Err(e) => error!("Sync failed: {}", e),
+					}
+					next_sync = get_current_timestamp() + self.config.interval_secs as i64;
+				}
+				let wake = match self.next_wake(next_sync).await {
+					Ok(wake) => wake,
+					Err(e) => {
+						error!("Failed to read outbox: {}", e);
+						next_sync
+					},
+				};
+				tokio::time::sleep(Duration::from_secs((wake - get_current_timestamp()).max(1) as u64)).await;
+			}
+		})
+	}
+
+	// 下一次同步和最早到期的 outbox 记录中较早的时间
+	async fn next_wake(&self, next_sync: i64) -> Result<i64, ClientError> {
+		let next_outbox = crud::outbox::get_next_outbox_attempt(&self.db).await?;
+		Ok(next_outbox.map_or(next_sync, |at| at.min(next_sync)))
+	}
+
+	// 从上次的检查点同步到当前时间, 每完成一个窗口保存一次检查点, 失败后下次从该窗口重试
+	pub async fn sync_once(&self) -> Result<SyncReport, ClientError> {
+		let now = get_current_timestamp();
+		let window_secs = self.config.window_secs.max(1);
+		let mut checkpoint = match SyncCheckpoint::load(&self.checkpoint_path).await? {
+			// 其他设备可能较晚才上传, 每次都重新核对最近一个窗口
+			Some(checkpoint) if checkpoint.device_id == self.device.id => SyncCheckpoint {
+				device_id: checkpoint.device_id,
+				synced_until: checkpoint.synced_until - window_secs,
+			},
+			_ => SyncCheckpoint {
+				device_id: self.device.id,
+				synced_until: self.initial_start(now).await?,
+			},
+		};
+
+		let mut report = SyncReport::default();
+		self.spent.store(0, Ordering::Relaxed);
+		while checkpoint.synced_until < now {
+			let start = checkpoint.synced_until;
+			let end = (start + window_secs).min(now);
+			let window = self.sync_window(start, end).await?;
+			report.windows += 1;
+			report.uploaded += window.uploaded;
+			report.downloaded += window.downloaded;
+			report.queued += window.queued;
+			if window.limited {
+				// 窗口未完成, 检查点不前进
+				info!("Sync size limit reached at window {}", start);
+				report.limited = true;
+				break;
+			}
+			checkpoint.synced_until = end;
+			checkpoint.save(&self.checkpoint_path).await?;
+		}
+		Ok(report)
+	}
+
+	// 首次同步从最早的本地记录开始, 本地没有记录时只同步最近一个窗口
+	async fn initial_start(&self, now: i64) -> Result<i64, ClientError> {
+		let earliest = crud::host_clipboard::get_earliest_clipboard_timestamp(&self.db).await?;
+		let recent = now - self.config.window_secs.max(1);
+		Ok(earliest.map_or(recent, |ts| (ts - 1).min(recent)))
+	}
+
+	// 上传时的网络错误不会中断窗口, 记录进入 outbox 后继续
+	async fn sync_window(&self, start: i64, end: i64) -> Result<SyncReport, ClientError> {
+		let rows = crud::host_clipboard::get_clipboard_entries_in_range(&self.db, start, end).await?;
+		let sync_device = SyncDevice {
+			start_at: start as i32,
+			end_at: end as i32,
+			items: rows
+				.iter()
+				.map(|row| SyncItem {
+					client_id: row.id,
+					timestamp: row.timestamp as i32,
+					hash: self.cipher.keyed_hash(&row.hash),
+				})
+				.collect(),
+		};
+		let result = match self.retry(|| self.client.sync_device(sync_device.clone(), self.device.id)).await {
+			Ok(result) => result,
+			Err(e) if is_retryable(&e) => {
+				// 离线时窗口内的记录都可能还没有上传, 先放入 outbox, 联网后按退避时间重放, 不必等到下一次同步
+				for row in &rows {
+					crud::outbox::enqueue_outbox_entry(&self.db, &row.hash, get_current_timestamp()).await?;
+				}
+				return Err(e);
+			},
+			Err(e) => return Err(e),
+		};
+
+		let mut report = SyncReport::default();
+		for id in &result.update_client_ids {
+			match rows.iter().find(|row| row.id == *id) {
+				Some(row) => match self.upload(row).await {
+					Ok(Transfer::Done) => report.uploaded += 1,
+					Ok(Transfer::Skipped) => {},
+					Ok(Transfer::Limited) => {
+						report.limited = true;
+						return Ok(report);
+					},
+					Err(e) if is_retryable(&e) => {
+						warn!("Upload clipboard entry {} failed, queued in outbox: {}", row.id, e);
+						crud::outbox::enqueue_outbox_entry(&self.db, &row.hash, get_current_timestamp()).await?;
+						report.queued += 1;
+					},
+					Err(e) => return Err(e),
+				},
+				None => warn!("Server requested unknown clipboard entry {}", id),
+			}
+		}
+		for id in &result.download_server_ids {
+			match self.download(*id).await? {
+				Transfer::Done => report.downloaded += 1,
+				Transfer::Skipped => {},
+				Transfer::Limited => {
+					report.limited = true;
+					return Ok(report);
+				},
+			}
+		}
+		Ok(report)
+	}
+
+	// 重放到期的 outbox 记录, 返回成功处理的条数. 失败的按指数退避加随机抖动重新安排
+	pub async fn flush_outbox(&self) -> Result<usize, ClientError> {
+		let entries = crud::outbox::get_due_outbox_entries(&self.db, get_current_timestamp(), 100).await?;
+		self.spent.store(0, Ordering::Relaxed);
+		let mut replayed = 0;
+		for entry in entries {
+			match self.replay(&entry).await {
+				Ok(()) => {
+					crud::outbox::delete_outbox_entry(&self.db, entry.id).await?;
+					replayed += 1;
+				},
+				Err(e) => {
+					let delay = outbox_delay(&self.config, entry.attempts);
+					warn!("Replay outbox entry {} failed, retry in {}s: {}", entry.hash, delay, e);
+					crud::outbox::reschedule_outbox_entry(&self.db, entry, get_current_timestamp() + delay as i64, e.to_string()).await?;
+				},
+			}
+		}
+		Ok(replayed)
+	}
+
+	async fn replay(&self, entry: &outbox::Model) -> Result<(), ClientError> {
+		match OutboxAction::from_i32(entry.action) {
+			Some(OutboxAction::Upload) => self.replay_upload(entry).await,
+			Some(OutboxAction::Delete) => self.replay_delete(entry).await,
+			None => Err(ClientError::UnexpectedError(format!("Unknown outbox action {}", entry.action))),
+		}
+	}
+
+	// 重放前先向服务端核对该 hash, 已上传过 (例如上次的响应丢失) 的不再重复创建
+	async fn replay_upload(&self, entry: &outbox::Model) -> Result<(), ClientError> {
+		let row = crud::host_clipboard::get_clipboard_entry_by_hash(&self.db, &entry.hash).await?;
+		let Some(row) = row else {
+			return Ok(()); // 本地已删除
+		};
+		let sync_device = SyncDevice {
+			start_at: row.timestamp as i32,
+			end_at: row.timestamp as i32,
+			items: vec![SyncItem {
+				client_id: row.id,
+				timestamp: row.timestamp as i32,
+				hash: self.cipher.keyed_hash(&row.hash),
+			}],
+		};
+		let result = self.client.sync_device(sync_device, self.device.id).await?;
+		if result.update_client_ids.contains(&row.id) && self.upload(&row).await? == Transfer::Limited {
+			return Err(ClientError::UnexpectedError("Sync size limit reached".to_string()));
+		}
+		Ok(())
+	}
+
+	// 清除后又重新复制的记录不再删除, 服务端已经没有该记录时同样视为完成
+	async fn replay_delete(&self, entry: &outbox::Model) -> Result<(), ClientError> {
+		if crud::host_clipboard::get_clipboard_entry_by_hash(&self.db, &entry.hash).await?.is_some() {
+			return Ok(());
+		}
+		let delete_clipboard = DeleteClipboard {
+			device_id: self.device.id,
+			hash: self.cipher.keyed_hash(&entry.hash),
+		};
+		self.retry(|| self.client.delete_clipboard(delete_clipboard.clone())).await?;
+		Ok(())
+	}
+
+	// 本地文件已被删除时跳过该记录
+	async fn upload(&self, row: &Model) -> Result<Transfer, ClientError> {
+		let files = match ContentType::from_i32(row.r#type) {
+			Some(ContentType::Image) => vec![row.path.clone()],
+			Some(ContentType::File) => parse_paths(row.path.clone()),
+			_ => vec![],
+		};
+		if !files.is_empty() {
+			let mut size = 0;
+			for file in &files {
+				size += fs::metadata(file).await.map(|meta| meta.len()).unwrap_or(0);
+			}
+			if !self.reserve(size) {
+				return Ok(Transfer::Limited);
+			}
+		}
+
+		let path = match ContentType::from_i32(row.r#type) {
+			Some(ContentType::Image) => match self.upload_file(&row.path).await? {
+				Some(uri) => uri,
+				None => return Ok(Transfer::Skipped),
+			},
+			Some(ContentType::File) => {
+				let mut uris = Vec::new();
+				for path in &files {
+					if let Some(uri) = self.upload_file(path).await? {
+						uris.push(uri);
+					}
+				}
+				if uris.is_empty() {
+					return Ok(Transfer::Skipped);
+				}
+				json!(uris).to_string()
+			},
+			_ => row.path.clone(),
+		};
+		let create_clipboard = self.seal(row, &path);
+		self.retry(|| self.client.create_clipboard(create_clipboard.clone())).await?;
+		Ok(Transfer::Done)
+	}
+
+	// 每次同步传输的文件总量不超过 max_sync_bytes (0 为不限制).
+	// 第一个文件总是允许, 否则超过上限的单个文件永远无法同步
+	fn reserve(&self, size: u64) -> bool {
+		let spent = self.spent.load(Ordering::Relaxed);
+		if self.config.max_sync_bytes > 0 && spent > 0 && spent + size > self.config.max_sync_bytes {
+			return false;
+		}
+		self.spent.fetch_add(size, Ordering::Relaxed);
+		true
+	}
+
+	fn exhausted(&self) -> bool {
+		self.config.max_sync_bytes > 0 && self.spent.load(Ordering::Relaxed) >= self.config.max_sync_bytes
+	}
+
+	fn seal(&self, row: &Model, path: &str) -> CreateClipboard {
+		let sealed = SealedContent {
+			content: row.content.clone(),
+			hash: row.hash.clone(),
+		};
+		CreateClipboard {
+			device_id: self.device.id,
+			r#type: row.r#type,
+			content: self.cipher.encrypt_str(&json!(sealed).to_string()),
+			path: self.cipher.encrypt_str(path),
+			hash: self.cipher.keyed_hash(&row.hash),
+			timestamp: row.timestamp,
+		}
+	}
+
+	// 返回 (content, 原始 hash, 服务端路径)
+	fn open(&self, resp: &ClipboardResp) -> Result<(SealedContent, String), CipherError> {
+		let sealed = self.cipher.decrypt_str(&resp.content)?;
+		let sealed: SealedContent = serde_json::from_str(&sealed).map_err(|e| CipherError::InvalidData(e.to_string()))?;
+		let path = self.cipher.decrypt_str(&resp.path)?;
+		Ok((sealed, path))
+	}
+
+	// 文件先分段加密到临时文件再上传, 文件名也不暴露给服务端
+	async fn upload_file(&self, path: &str) -> Result<Option<String>, ClientError> {
+		if let Err(e) = fs::metadata(path).await {
+			warn!("Skip uploading file {}: {}", path, e);
+			return Ok(None);
+		}
+		let upload_dir = self.files_path.join("upload");
+		fs::create_dir_all(&upload_dir).await?;
+		let encrypted_path = upload_dir.join(format!("{}.bin", uuid::Uuid::new_v4()));
+		if let Err(e) = self.cipher.encrypt_file(Path::new(path), &encrypted_path).await {
+			let _ = fs::remove_file(&encrypted_path).await;
+			return Err(ClientError::UnexpectedError(format!("Failed to encrypt {}: {}", path, e)));
+		}
+
+		let encrypted_path_str = encrypted_path.display().to_string();
+		let result = self.retry(|| self.client.upload_file(&encrypted_path_str)).await;
+		let _ = fs::remove_file(&encrypted_path).await;
+		Ok(Some(result?.uri))
+	}
+
+	// 无法解密的记录 (其他密钥或未加密) 和本地已删除的记录跳过
+	async fn download(&self, id: i32) -> Result<Transfer, ClientError> {
+		let resp = self.retry(|| self.client.get_clipboards_by_id(id)).await?;
+		let (sealed, server_path) = match self.open(&resp) {
+			Ok(opened) => opened,
+			Err(e) => {
+				warn!("Skip clipboard {} from server: {}", id, e);
+				return Ok(Transfer::Skipped);
+			},
+		};
+		// 在下载文件之前检查, 写入时 add_synced_clipboard_entry 会再检查一次
+		if self.is_deleted(&sealed.hash).await? {
+			return Ok(Transfer::Skipped);
+		}
+		let content_type =
+			ContentType::from_i32(resp.r#type).ok_or_else(|| ClientError::SerializationError(format!("Unknown clipboard type {}", resp.r#type)))?;
+		if content_type != ContentType::Text && self.exhausted() {
+			return Ok(Transfer::Limited);
+		}
+		let path = match content_type {
+			ContentType::Image => self.download_file(id, &server_path).await?,
+			ContentType::File => {
+				let mut paths = Vec::new();
+				for uri in parse_paths(server_path) {
+					paths.push(self.download_file(id, &uri).await?);
+				}
+				json!(paths).to_string()
+			},
+			ContentType::Text => server_path,
+		};
+
+		let mut item = PasteboardContent::new(sealed.content, content_type, sealed.hash, Some(path));
+		item.date_time = Utc
+			.timestamp_opt(resp.timestamp, 0)
+			.single()
+			.ok_or_else(|| ClientError::SerializationError(format!("Invalid timestamp {}", resp.timestamp)))?
+			.fixed_offset();
+		match crud::host_clipboard::add_synced_clipboard_entry(&self.db, item).await? {
+			Some(entry) => {
+				events::publish(ClipboardEvent::Captured(entry));
+				Ok(Transfer::Done)
+			},
+			None => Ok(Transfer::Skipped),
+		}
+	}
+
+	// 本地已删除还在撤销窗口内, 或者已清除, 等待在服务端删除
+	async fn is_deleted(&self, hash: &str) -> Result<bool, ClientError> {
+		let trashed = crud::host_clipboard::get_clipboard_entry_by_hash_including_deleted(&self.db, hash)
+			.await?
+			.is_some_and(|row| row.deleted_at.is_some());
+		Ok(trashed || crud::outbox::is_outbox_delete_pending(&self.db, hash).await?)
+	}
+
+	// 密文直接写入磁盘再分段解密. 临时文件名固定, 中断后下次同步可以续传
+	async fn download_file(&self, id: i32, uri: &str) -> Result<String, ClientError> {
+		let file_name = uri.rsplit('/').next().unwrap_or_default();
+		let download_dir = self.files_path.join("download");
+		fs::create_dir_all(&download_dir).await?;
+		let encrypted_path = download_dir.join(format!("{}_{}.bin", id, file_name));
+		let size = self.retry(|| self.client.download_file(uri, &encrypted_path)).await?;
+		self.spent.fetch_add(size, Ordering::Relaxed);
+
+		let path = self.files_path.join(format!("{}_{}", id, file_name));
+		let result = self.cipher.decrypt_file(&encrypted_path, &path).await;
+		let _ = fs::remove_file(&encrypted_path).await;
+		result.map_err(|e| ClientError::SerializationError(format!("Failed to decrypt {}: {}", uri, e)))?;
+		Ok(path.display().to_string())
+	}
+
+	// 网络错误和服务端 5xx 按指数退避重试, 其他错误直接返回
+	async fn retry<R, F, Fut>(&self, mut f: F) -> Result<R, ClientError>
+	where
+		F: FnMut() -> Fut,
+		Fut: Future<Output = Result<R, ClientError>>,
+	{
+		let mut attempt = 0;
+		loop {
+			match f().await {
+				Err(e) if is_retryable(&e) && attempt < self.config.max_retries => {
+					let delay = self.config.retry_delay_ms.saturating_mul(1 << attempt.min(16));
+					warn!("Sync request failed, retry in {}ms: {}", delay, e);
+					tokio::time::sleep(Duration::from_millis(delay)).await;
+					attempt += 1;
+				},
+				result => return result,
+			}
+		}
+	}
+}
+
+fn is_retryable(e: &ClientError) -> bool {
+	match e {
+		ClientError::NetworkError(_) | ClientError::Http(_) => true,
+		ClientError::ApiError { code, .. } => *code >= 500,
+		_ => false,
+	}
+}
+
+// base * 2^attempts, 不超过 max, 再在 [delay/2, delay] 之间随机, 避免多台设备同时重试
+fn outbox_delay(config: &SyncConfig, attempts: i32) -> u64 {
+	let base = config.outbox_base_delay_secs.max(1);
+	let max = config.outbox_max_delay_secs.max(base);
+	let delay = base.saturating_mul(1 << attempts.clamp(0, 16)).min(max);
+	rand::thread_rng().gen_range(delay / 2..=delay)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::core::trash;
+	use crate::db::testing::{temp_db, TempDir};
+	use crate::utils::hash::hash_str;
+	use mockito::{mock, Matcher};
+
+	const DEK: [u8; 32] = [7; 32];
+
+	struct TestEnv {
+		dir: TempDir,
+		db: DatabaseConnection,
+	}
+
+	impl TestEnv {
+		async fn new(name: &str) -> Self {
+			let (db, dir) = temp_db(&format!("sync_{}", name)).await;
+			Self { dir, db }
+		}
+
+		fn engine(&self) -> SyncEngine<HttpClient> {
+			let device = Device {
+				id: 7,
+				name: "linux".to_string(),
+				uuid: "uuid".to_string(),
+				user_id: 9,
+				icon: "".to_string(),
+			};
+			let config = SyncConfig {
+				enabled: true,
+				server_url: mockito::server_url(),
+				retry_delay_ms: 1,
+				window_secs: 30 * 24 * 60 * 60,
+				..SyncConfig::default()
+			};
+			SyncEngine::new(
+				HttpClient::new(mockito::server_url()),
+				self.db.clone(),
+				device,
+				PayloadCipher::new(&DEK),
+				config,
+			)
+			.with_paths(self.dir.join("sync.toml"), self.dir.join("files"))
+		}
+
+		async fn add(&self, mut item: PasteboardContent, timestamp: i64) -> Model {
+			item.date_time = Utc.timestamp_opt(timestamp, 0).unwrap().fixed_offset();
+			crud::host_clipboard::add_clipboard_entry(&self.db, item).await.unwrap()
+		}
+	}
+
+	fn api_response(data: serde_json::Value) -> String {
+		json!({ "code": 200, "data": data, "error_msg": null }).to_string()
+	}
+
+	// 模拟其他设备上传到服务端的记录
+	fn remote_clipboard(id: i32, r#type: i32, content: &str, hash: &str, path: &str, timestamp: i64) -> serde_json::Value {
+		let cipher = PayloadCipher::new(&DEK);
+		let sealed = SealedContent {
+			content: content.to_string(),
+			hash: hash.to_string(),
+		};
+		json!({
+			"id": id, "device_id": 8, "type": r#type, "content": cipher.encrypt_str(&json!(sealed).to_string()),
+			"path": cipher.encrypt_str(path), "hash": cipher.keyed_hash(hash), "timestamp": timestamp,
+		})
+	}
+
+	#[tokio::test]
+	async fn test_seal_and_open() {
+		let env = TestEnv::new("seal").await;
+		let engine = env.engine();
+		let row = env
+			.add(
+				PasteboardContent::new("secret".to_string(), ContentType::Text, hash_str("secret"), None),
+				100,
+			)
+			.await;
+
+		let sealed = engine.seal(&row, "/files/9/secret.png");
+		for field in [&sealed.content, &sealed.path, &sealed.hash] {
+			assert!(!field.contains("secret"));
+		}
+		assert_ne!(sealed.hash, row.hash);
+		assert_eq!(sealed.hash, PayloadCipher::new(&DEK).keyed_hash(&row.hash));
+
+		let resp = ClipboardResp {
+			id: 1,
+			device_id: sealed.device_id,
+			r#type: sealed.r#type,
+			content: sealed.content,
+			path: sealed.path,
+			hash: sealed.hash,
+			timestamp: sealed.timestamp,
+		};
+		let (content, path) = engine.open(&resp).unwrap();
+		assert_eq!(content.content, "secret");
+		assert_eq!(content.hash, row.hash);
+		assert_eq!(path, "/files/9/secret.png");
+
+		// 其他用户的 DEK 无法解密
+		let mut other = env.engine();
+		other.cipher = PayloadCipher::new(&[8; 32]);
+		assert_eq!(other.open(&resp).err(), Some(CipherError::DecryptFailed));
+	}
+
+	#[tokio::test]
+	async fn test_sync_uploads_and_downloads() {
+		let env = TestEnv::new("roundtrip").await;
+		let cipher = PayloadCipher::new(&DEK);
+		let now = get_current_timestamp();
+		let text = env
+			.add(
+				PasteboardContent::new("local".to_string(), ContentType::Text, hash_str("local"), None),
+				now - 60,
+			)
+			.await;
+		let img_path = env.dir.join("local.png");
+		std::fs::write(&img_path, b"png").unwrap();
+		let img_path = img_path.display().to_string();
+		let img = env
+			.add(
+				PasteboardContent::new(
+					"Img: local.png".to_string(),
+					ContentType::Image,
+					hash_str(&img_path),
+					Some(img_path.clone()),
+				),
+				now - 30,
+			)
+			.await;
+
+		let sync_mock = mock("POST", "/device/7/sync")
+			.match_body(Matcher::PartialJson(
+				json!({ "items": [{ "hash": cipher.keyed_hash(&text.hash) }, { "hash": cipher.keyed_hash(&img.hash) }] }),
+			))
+			.with_body(api_response(json!({
+				"update_client_ids": [text.id, img.id],
+				"download_server_ids": [42, 43, 44],
+			})))
+			.expect(1)
+			.create();
+		// 上传的是加密后的临时文件
+		let upload_mock = mock("POST", "/file")
+			.match_body(Matcher::Regex(r#"filename="[0-9a-f-]+\.bin""#.to_string()))
+			.with_body(api_response(json!({ "uri": "/files/9/upload.bin" })))
+			.expect(1)
+			.create();
+		let create_mock = mock("POST", "/content")
+			.match_body(Matcher::PartialJson(json!({ "device_id": 7 })))
+			.with_body(api_response(remote_clipboard(1, 0, "local", &text.hash, "", text.timestamp)))
+			.expect(2)
+			.create();
+		let get_text_mock = mock("GET", "/content/42")
+			.with_body(api_response(remote_clipboard(42, 0, "来自服务端", "remote-text", "", now - 45)))
+			.create();
+		let get_img_mock = mock("GET", "/content/43")
+			.with_body(api_response(remote_clipboard(
+				43,
+				1,
+				"Img: remote.png",
+				"remote-img",
+				"/files/9/remote.png",
+				now - 40,
+			)))
+			.create();
+		let encrypted_file = cipher.encrypt(b"remote png");
+		let get_file_mock = mock("GET", "/files/9/remote.png").with_body(encrypted_file).create();
+		// 未加密的旧数据被跳过
+		let plain_mock = mock("GET", "/content/44")
+			.with_body(api_response(json!({
+				"id": 44, "device_id": 8, "type": 0, "content": "cGxhaW4=", "path": "", "hash": "plain", "timestamp": now - 20,
+			})))
+			.create();
+
+		let report = env.engine().sync_once().await.unwrap();
+		assert_eq!(
+			report,
+			SyncReport {
+				windows: 1,
+				uploaded: 2,
+				downloaded: 2,
+				queued: 0,
+				limited: false,
+			}
+		);
+		for m in [
+			sync_mock,
+			upload_mock,
+			create_mock,
+			get_text_mock,
+			get_img_mock,
+			get_file_mock,
+			plain_mock,
+		] {
+			m.assert();
+		}
+
+		let rows = crud::host_clipboard::get_clipboard_entries_in_range(&env.db, 0, now).await.unwrap();
+		assert_eq!(rows.len(), 4);
+		let remote_text = rows.iter().find(|row| row.hash == "remote-text").unwrap();
+		assert_eq!(remote_text.content, "来自服务端");
+		assert_eq!(remote_text.timestamp, now - 45);
+		let remote_img = rows.iter().find(|row| row.hash == "remote-img").unwrap();
+		assert!(remote_img.path.ends_with("43_remote.png"));
+		assert_eq!(std::fs::read(&remote_img.path).unwrap(), b"remote png");
+		// 加密用的临时文件已删除
+		assert_eq!(std::fs::read_dir(env.dir.join("files").join("upload")).unwrap().count(), 0);
+
+		let checkpoint = SyncCheckpoint::load(&env.dir.join("sync.toml")).await.unwrap().unwrap();
+		assert_eq!(checkpoint.device_id, 7);
+		assert!(checkpoint.synced_until >= now);
+	}
+
+	#[tokio::test]
+	async fn test_deleted_entry_stays_deleted_after_sync() {
+		let env = TestEnv::new("deleted").await;
+		let engine = env.engine();
+		let cipher = PayloadCipher::new(&DEK);
+		let now = get_current_timestamp();
+		let row = env
+			.add(
+				PasteboardContent::new("gone".to_string(), ContentType::Text, hash_str("gone"), None),
+				now - 60,
+			)
+			.await;
+		trash::delete_entries(&env.db, vec![row.id]).await.unwrap();
+		let trashed = || async {
+			crud::host_clipboard::get_clipboard_entry_by_hash_including_deleted(&env.db, &row.hash)
+				.await
+				.unwrap()
+		};
+
+		// 撤销窗口内, 本地不再上报该记录, 服务端要求下载时跳过
+		let sync_mock = mock("POST", "/device/7/sync")
+			.match_body(Matcher::PartialJson(json!({ "items": [] })))
+			.with_body(api_response(json!({ "update_client_ids": [], "download_server_ids": [42] })))
+			.expect(2)
+			.create();
+		let get_mock = mock("GET", "/content/42")
+			.with_body(api_response(remote_clipboard(42, 0, "gone", &row.hash, "", row.timestamp)))
+			.expect(2)
+			.create();
+		assert_eq!(engine.sync_once().await.unwrap().downloaded, 0);
+		let entry = trashed().await.unwrap();
+		assert!(entry.deleted_at.is_some());
+		assert_eq!(entry.copy_count, row.copy_count);
+
+		// 清除后等待在服务端删除, 期间同步也不会下载回来
+		crud::host_clipboard::purge_deleted_clipboard_entries(&env.db, i64::MAX).await.unwrap();
+		crud::outbox::enqueue_outbox_delete(&env.db, &row.hash, now).await.unwrap();
+		assert_eq!(engine.sync_once().await.unwrap().downloaded, 0);
+		assert!(trashed().await.is_none());
+		sync_mock.assert();
+		get_mock.assert();
+
+		let delete_mock = mock("DELETE", format!("/content/7/{}", cipher.keyed_hash(&row.hash)).as_str())
+			.with_body(api_response(json!(true)))
+			.expect(1)
+			.create();
+		assert_eq!(engine.flush_outbox().await.unwrap(), 1);
+		delete_mock.assert();
+		assert_eq!(crud::outbox::count_outbox_entries(&env.db).await.unwrap(), 0);
+	}
+
+	#[tokio::test]
+	async fn test_sync_stops_at_size_limit() {
+		let env = TestEnv::new("limit").await;
+		let mut engine = env.engine();
+		engine.config.max_sync_bytes = 15;
+		let now = get_current_timestamp();
+		let mut rows = Vec::new();
+		for (name, offset) in [("a.png", 60), ("b.png", 30)] {
+			let path = env.dir.join(name).display().to_string();
+			std::fs::write(&path, [0u8; 10]).unwrap();
+			let item = PasteboardContent::new(name.to_string(), ContentType::Image, hash_str(&path), Some(path));
+			rows.push(env.add(item, now - offset).await);
+		}
+
+		let sync_mock = mock("POST", "/device/7/sync")
+			.with_body(api_response(json!({
+				"update_client_ids": [rows[0].id, rows[1].id],
+				"download_server_ids": [42],
+			})))
+			.expect(1)
+			.create();
+		let upload_mock = mock("POST", "/file")
+			.with_body(api_response(json!({ "uri": "/files/9/upload.bin" })))
+			.expect(1)
+			.create();
+		let create_mock = mock("POST", "/content")
+			.with_body(api_response(remote_clipboard(1, 1, "a.png", &rows[0].hash, "", rows[0].timestamp)))
+			.expect(1)
+			.create();
+		let get_mock = mock("GET", "/content/42").expect(0).create();
+
+		// 第一个文件总是上传, 第二个超过上限, 窗口未完成, 检查点不保存
+		let report = engine.sync_once().await.unwrap();
+		assert_eq!(report.uploaded, 1);
+		assert!(report.limited);
+		for m in [sync_mock, upload_mock, create_mock, get_mock] {
+			m.assert();
+		}
+		assert_eq!(SyncCheckpoint::load(&env.dir.join("sync.toml")).await.unwrap(), None);
+	}
+
+	#[tokio::test]
+	async fn test_sync_retries_and_keeps_checkpoint_on_failure() {
+		let env = TestEnv::new("retry").await;
+		let engine = env.engine();
+		let empty_result = api_response(json!({ "update_client_ids": [], "download_server_ids": [] }));
+
+		// 503 后重试成功
+		let unavailable = mock("POST", "/device/7/sync").with_status(503).expect(1).create();
+		let ok = mock("POST", "/device/7/sync").with_body(&empty_result).expect(1).create();
+		let report = engine.sync_once().await.unwrap();
+		assert_eq!(report.windows, 1);
+		unavailable.assert();
+		ok.assert();
+		let checkpoint = SyncCheckpoint::load(&env.dir.join("sync.toml")).await.unwrap().unwrap();
+
+		// 非 5xx 错误不重试, 检查点保持不变
+		let bad_request = mock("POST", "/device/7/sync").with_status(400).expect(1).create();
+		assert!(matches!(engine.sync_once().await, Err(ClientError::ApiError { code: 400, .. })));
+		bad_request.assert();
+		assert_eq!(SyncCheckpoint::load(&env.dir.join("sync.toml")).await.unwrap(), Some(checkpoint));
+	}
+
+	#[tokio::test]
+	async fn test_failed_upload_is_queued_and_replayed() {
+		let env = TestEnv::new("outbox").await;
+		let engine = env.engine();
+		let now = get_current_timestamp();
+		let row = env
+			.add(
+				PasteboardContent::new("offline".to_string(), ContentType::Text, hash_str("offline"), None),
+				now - 60,
+			)
+			.await;
+		let pending = api_response(json!({ "update_client_ids": [row.id], "download_server_ids": [] }));
+		let outbox_count = || async { crud::outbox::count_outbox_entries(&env.db).await.unwrap() };
+
+		// 创建记录时服务端不可用, 重试用完后进入 outbox, 检查点照常前进
+		let sync_mock = mock("POST", "/device/7/sync").with_body(&pending).expect(1).create();
+		let unavailable = mock("POST", "/content").with_status(503).expect(4).create();
+		let report = engine.sync_once().await.unwrap();
+		assert_eq!(report.queued, 1);
+		assert_eq!(report.uploaded, 0);
+		sync_mock.assert();
+		unavailable.assert();
+		assert_eq!(outbox_count().await, 1);
+		assert!(SyncCheckpoint::load(&env.dir.join("sync.toml")).await.unwrap().is_some());
+
+		// 恢复后先核对 hash 再上传
+		let check_mock = mock("POST", "/device/7/sync")
+			.match_body(Matcher::PartialJson(json!({ "start_at": row.timestamp, "end_at": row.timestamp })))
+			.with_body(&pending)
+			.expect(1)
+			.create();
+		let create_mock = mock("POST", "/content")
+			.with_body(api_response(remote_clipboard(1, 0, "offline", &row.hash, "", row.timestamp)))
+			.expect(1)
+			.create();
+		assert_eq!(engine.flush_outbox().await.unwrap(), 1);
+		check_mock.assert();
+		create_mock.assert();
+		assert_eq!(outbox_count().await, 0);
+
+		// 服务端已有该记录时不再重复创建
+		crud::outbox::enqueue_outbox_entry(&env.db, &row.hash, now).await.unwrap();
+		let synced = mock("POST", "/device/7/sync")
+			.with_body(api_response(json!({ "update_client_ids": [], "download_server_ids": [] })))
+			.expect(1)
+			.create();
+		let create_mock = mock("POST", "/content").expect(0).create();
+		assert_eq!(engine.flush_outbox().await.unwrap(), 1);
+		synced.assert();
+		create_mock.assert();
+		assert_eq!(outbox_count().await, 0);
+	}
+
+	#[tokio::test]
+	async fn test_offline_replay_is_rescheduled_with_backoff() {
+		let env = TestEnv::new("backoff").await;
+		let mut engine = env.engine();
+		// 不可达的地址, 模拟离线
+		engine.client = HttpClient::new("http://127.0.0.1:1".to_string());
+		let now = get_current_timestamp();
+		env.add(
+			PasteboardContent::new("later".to_string(), ContentType::Text, hash_str("later"), None),
+			now - 60,
+		)
+		.await;
+
+		// 离线时新建的记录进入 outbox
+		assert!(matches!(engine.sync_once().await, Err(ClientError::Http(_))));
+		assert_eq!(crud::outbox::count_outbox_entries(&env.db).await.unwrap(), 1);
+
+		assert_eq!(engine.flush_outbox().await.unwrap(), 0);
+		let entry = crud::outbox::get_due_outbox_entries(&env.db, i64::MAX, 10).await.unwrap().remove(0);
+		assert_eq!(entry.attempts, 1);
+		assert!(!entry.last_error.is_empty());
+		let base = engine.config.outbox_base_delay_secs as i64;
+		assert!(entry.next_attempt_at >= now + base / 2);
+		assert!(crud::outbox::get_due_outbox_entries(&env.db, now, 10).await.unwrap().is_empty());
+	}
+
+	#[test]
+	fn test_outbox_delay_is_capped_with_jitter() {
+		let config = SyncConfig {
+			outbox_base_delay_secs: 10,
+			outbox_max_delay_secs: 100,
+			..SyncConfig::default()
+		};
+		for _ in 0..20 {
+			assert!((5..=10).contains(&outbox_delay(&config, 0)));
+			assert!((20..=40).contains(&outbox_delay(&config, 2)));
+			assert!((50..=100).contains(&outbox_delay(&config, 30)));
+		}
+	}
+}
@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use tokio_stream::StreamExt;
+
+	use crate::api::common::ClientTrait;
+	use crate::api::models::clipboard::CreateClipboard;
+	use crate::api::models::device::CreateDevice;
+	use crate::api::tests::grpc_client::mock_server;
+
+	fn create_clipboard(device_id: i32, content: &str) -> CreateClipboard {
+		CreateClipboard {
+			device_id,
+			r#type: 0,
+			content: content.to_string(),
+			path: "".to_string(),
+			hash: format!("hash-{}", content),
+			timestamp: 100,
+		}
+	}
+
+	#[tokio::test]
+	async fn test_create_and_get_clipboard() {
+		let client = mock_server::start().await;
+
+		let created = client.create_clipboard(create_clipboard(1, "hello")).await.unwrap();
+		assert_eq!(created.content, "hello");
+		assert_eq!(client.get_clipboards_by_id(created.id).await.unwrap(), created);
+		assert!(client.get_clipboards_by_id(created.id + 100).await.is_err());
+	}
+
+	#[tokio::test]
+	async fn test_watch_clipboards() {
+		let client = mock_server::start().await;
+		let mut devices = Vec::new();
+		for (uuid, user_id) in [("local", 9), ("remote", 9), ("other", 10)] {
+			let device = CreateDevice {
+				name: uuid.to_string(),
+				uuid: uuid.to_string(),
+				user_id,
+			};
+			devices.push(client.create_device(device).await.unwrap());
+		}
+		let (local, remote, other) = (&devices[0], &devices[1], &devices[2]);
+
+		let mut stream = client.watch_clipboards(9, local.id).await.unwrap();
+		// 本机和其他用户的记录不会推送
+		client.create_clipboard(create_clipboard(local.id, "local")).await.unwrap();
+		client.create_clipboard(create_clipboard(other.id, "other")).await.unwrap();
+		let expected = client.create_clipboard(create_clipboard(remote.id, "remote")).await.unwrap();
+
+		let received = tokio::time::timeout(Duration::from_secs(5), stream.next())
+			.await
+			.unwrap()
+			.unwrap()
+			.unwrap();
+		assert_eq!(received, expected);
+	}
+}
@@ -0,0 +1,76 @@
+#[cfg(test)]
+mod tests {
+	use crate::api::common::ClientTrait;
+	use crate::api::models::clipboard::CreateClipboard;
+	use crate::api::models::device::{CreateDevice, SyncDevice, SyncItem, UpdateDevice};
+	use crate::api::tests::grpc_client::mock_server;
+
+	#[tokio::test]
+	async fn test_device() {
+		let client = mock_server::start().await;
+
+		let create_device = CreateDevice {
+			name: "macos".to_string(),
+			uuid: "uuid-1".to_string(),
+			user_id: 9,
+		};
+		let device = client.create_device(create_device).await.unwrap();
+		assert_eq!(device.name, "macos");
+		assert_eq!(device.user_id, 9);
+
+		let update_device = UpdateDevice {
+			name: Some("linux".to_string()),
+			icon: None,
+			user_id: None,
+		};
+		let updated = client.update_device(update_device, device.id).await.unwrap();
+		assert_eq!(updated.id, device.id);
+		assert_eq!(updated.name, "linux");
+		assert_eq!(updated.icon, device.icon);
+
+		let devices = client.get_devices_by_user_id(9).await.unwrap();
+		assert_eq!(devices.len(), 1);
+		assert!(client.get_devices_by_user_id(10).await.unwrap().is_empty());
+
+		assert!(client.delete_device(device.id).await.unwrap());
+		assert!(!client.delete_device(device.id).await.unwrap());
+		assert!(client.get_devices_by_user_id(9).await.unwrap().is_empty());
+	}
+
+	#[tokio::test]
+	async fn test_sync_device() {
+		let client = mock_server::start().await;
+		let device = client
+			.create_device(CreateDevice {
+				name: "macos".to_string(),
+				uuid: "uuid-1".to_string(),
+				user_id: 9,
+			})
+			.await
+			.unwrap();
+		let server_only = client
+			.create_clipboard(CreateClipboard {
+				device_id: device.id,
+				r#type: 0,
+				content: "server".to_string(),
+				path: "".to_string(),
+				hash: "h-server".to_string(),
+				timestamp: 100,
+			})
+			.await
+			.unwrap();
+
+		let sync_device = SyncDevice {
+			start_at: 0,
+			end_at: 200,
+			items: vec![SyncItem {
+				client_id: 1,
+				timestamp: 150,
+				hash: "h-client".to_string(),
+			}],
+		};
+		let result = client.sync_device(sync_device, device.id).await.unwrap();
+		assert_eq!(result.update_client_ids, vec![1]);
+		assert_eq!(result.download_server_ids, vec![server_only.id]);
+	}
+}
@@ -0,0 +1,20 @@
+#[cfg(test)]
+mod tests {
+	use crate::api::common::{ClientError, ClientTrait};
+	use crate::api::tests::grpc_client::mock_server;
+
+	#[tokio::test]
+	async fn test_upload_and_get_file() {
+		let client = mock_server::start().await;
+
+		let path = std::env::temp_dir().join(format!("supercv_grpc_{}.txt", std::process::id()));
+		tokio::fs::write(&path, b"hello grpc").await.unwrap();
+
+		let file = client.upload_file(path.to_str().unwrap()).await.unwrap();
+		assert!(file.uri.ends_with(path.file_name().unwrap().to_str().unwrap()));
+		assert_eq!(client.get_file(&file.uri).await.unwrap(), b"hello grpc");
+
+		assert!(matches!(client.get_file("/file/1/missing").await, Err(ClientError::ApiError { .. })));
+		let _ = tokio::fs::remove_file(&path).await;
+	}
+}
@@ -0,0 +1,311 @@
+// 进程内的 gRPC mock 服务, 数据只保存在内存中
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use tokio::net::TcpListener;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::{BroadcastStream, TcpListenerStream};
+use tokio_stream::{Stream, StreamExt};
+use tonic::transport::Server;
+use tonic::{Request, Response, Status};
+
+use crate::api::grpc::pb;
+use crate::api::grpc::pb::clipboard_service_server::{ClipboardService, ClipboardServiceServer};
+use crate::api::grpc::pb::device_service_server::{DeviceService, DeviceServiceServer};
+use crate::api::grpc::pb::file_service_server::{FileService, FileServiceServer};
+use crate::api::grpc::pb::user_service_server::{UserService, UserServiceServer};
+use crate::api::grpc_client::GrpcClient;
+
+#[derive(Default)]
+struct State {
+	next_id: i32,
+	users: Vec<pb::UserResp>,
+	salts: HashMap<String, pb::UserSalt>, // username -> salt
+	devices: Vec<pb::DeviceResp>,
+	files: HashMap<String, Vec<u8>>,
+	clipboards: Vec<pb::ClipboardResp>,
+}
+
+impl State {
+	fn next_id(&mut self) -> i32 {
+		self.next_id += 1;
+		self.next_id
+	}
+
+	fn device_user_id(&self, device_id: i32) -> Option<i32> {
+		self.devices.iter().find(|d| d.id == device_id).map(|d| d.user_id)
+	}
+}
+
+#[derive(Clone)]
+struct MockServer {
+	state: Arc<Mutex<State>>,
+	created: broadcast::Sender<pb::ClipboardResp>,
+}
+
+// 启动 mock 服务并返回连接好的客户端
+pub async fn start() -> GrpcClient {
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let addr = listener.local_addr().unwrap();
+	let server = MockServer {
+		state: Arc::new(Mutex::new(State::default())),
+		created: broadcast::channel(16).0,
+	};
+	tokio::spawn(
+		Server::builder()
+			.add_service(UserServiceServer::new(server.clone()))
+			.add_service(DeviceServiceServer::new(server.clone()))
+			.add_service(FileServiceServer::new(server.clone()))
+			.add_service(ClipboardServiceServer::new(server))
+			.serve_with_incoming(TcpListenerStream::new(listener)),
+	);
+	GrpcClient::connect(format!("http://{}", addr)).await.unwrap()
+}
+
+#[tonic::async_trait]
+impl UserService for MockServer {
+	async fn register(&self, request: Request<pb::UserRegister>) -> Result<Response<pb::UserResp>, Status> {
+		let req = request.into_inner();
+		let mut state = self.state.lock().unwrap();
+		if state.users.iter().any(|u| u.username == req.username) {
+			return Err(Status::already_exists("username already exists"));
+		}
+		let user = pb::UserResp {
+			id: state.next_id(),
+			username: req.username,
+			email: req.email,
+			password_hash: req.password_hash,
+			encrypted_dek: req.encrypted_dek,
+			created_at: 0,
+			updated_at: 0,
+			token: None,
+		};
+		state.users.push(user.clone());
+		let salt = pb::UserSalt {
+			salt: req.salt,
+			kdf: req.kdf,
+		};
+		state.salts.insert(user.username.clone(), salt);
+		Ok(Response::new(user))
+	}
+
+	async fn login(&self, request: Request<pb::UserLogin>) -> Result<Response<pb::UserResp>, Status> {
+		let req = request.into_inner();
+		let state = self.state.lock().unwrap();
+		state
+			.users
+			.iter()
+			.find(|u| u.username == req.username && u.password_hash == req.password_hash)
+			.cloned()
+			.map(Response::new)
+			.ok_or_else(|| Status::unauthenticated("invalid username or password"))
+	}
+
+	async fn reset(&self, request: Request<pb::UserResetPassword>) -> Result<Response<pb::UserResp>, Status> {
+		let req = request.into_inner();
+		let mut state = self.state.lock().unwrap();
+		let user = state
+			.users
+			.iter_mut()
+			.find(|u| u.username == req.username && u.email == req.email)
+			.ok_or_else(|| Status::not_found("user not found"))?;
+		user.password_hash = req.password_hash;
+		let user = user.clone();
+		let salt = pb::UserSalt {
+			salt: req.salt,
+			kdf: req.kdf,
+		};
+		state.salts.insert(user.username.clone(), salt);
+		Ok(Response::new(user))
+	}
+
+	async fn salt(&self, request: Request<pb::UserSaltQuery>) -> Result<Response<pb::UserSalt>, Status> {
+		let req = request.into_inner();
+		let state = self.state.lock().unwrap();
+		state
+			.salts
+			.get(&req.username)
+			.cloned()
+			.map(Response::new)
+			.ok_or_else(|| Status::not_found("user not found"))
+	}
+
+	async fn upgrade_password(&self, request: Request<pb::UserUpgradePassword>) -> Result<Response<pb::UserResp>, Status> {
+		let req = request.into_inner();
+		let mut state = self.state.lock().unwrap();
+		let user = state
+			.users
+			.iter_mut()
+			.find(|u| u.username == req.username && u.password_hash == req.password_hash)
+			.ok_or_else(|| Status::unauthenticated("invalid username or password"))?;
+		user.password_hash = req.new_password_hash;
+		let user = user.clone();
+		let salt = pb::UserSalt {
+			salt: req.salt,
+			kdf: req.kdf,
+		};
+		state.salts.insert(user.username.clone(), salt);
+		Ok(Response::new(user))
+	}
+}
+
+#[tonic::async_trait]
+impl DeviceService for MockServer {
+	async fn create(&self, request: Request<pb::CreateDevice>) -> Result<Response<pb::DeviceResp>, Status> {
+		let req = request.into_inner();
+		let mut state = self.state.lock().unwrap();
+		let device = pb::DeviceResp {
+			id: state.next_id(),
+			icon: format!("/icons/{}.png", req.uuid),
+			name: req.name,
+			uuid: req.uuid,
+			user_id: req.user_id,
+			created_at: 0,
+			updated_at: 0,
+		};
+		state.devices.push(device.clone());
+		Ok(Response::new(device))
+	}
+
+	async fn update(&self, request: Request<pb::UpdateDevice>) -> Result<Response<pb::DeviceResp>, Status> {
+		let req = request.into_inner();
+		let mut state = self.state.lock().unwrap();
+		let device = state
+			.devices
+			.iter_mut()
+			.find(|d| d.id == req.device_id)
+			.ok_or_else(|| Status::not_found("device not found"))?;
+		if let Some(name) = req.name {
+			device.name = name;
+		}
+		if let Some(icon) = req.icon {
+			device.icon = icon;
+		}
+		if let Some(user_id) = req.user_id {
+			device.user_id = user_id;
+		}
+		Ok(Response::new(device.clone()))
+	}
+
+	async fn delete(&self, request: Request<pb::DeviceId>) -> Result<Response<pb::DeleteDeviceResp>, Status> {
+		let device_id = request.into_inner().device_id;
+		let mut state = self.state.lock().unwrap();
+		let len = state.devices.len();
+		state.devices.retain(|d| d.id != device_id);
+		Ok(Response::new(pb::DeleteDeviceResp {
+			deleted: state.devices.len() < len,
+		}))
+	}
+
+	async fn list_by_user(&self, request: Request<pb::UserId>) -> Result<Response<pb::DeviceList>, Status> {
+		let user_id = request.into_inner().user_id;
+		let state = self.state.lock().unwrap();
+		let devices = state.devices.iter().filter(|d| d.user_id == user_id).cloned().collect();
+		Ok(Response::new(pb::DeviceList { devices }))
+	}
+
+	// 客户端有而服务端没有的记录需要上传, 反之需要下载
+	async fn sync(&self, request: Request<pb::SyncDevice>) -> Result<Response<pb::SyncDeviceResult>, Status> {
+		let req = request.into_inner();
+		let state = self.state.lock().unwrap();
+		let user_id = state.device_user_id(req.device_id).ok_or_else(|| Status::not_found("device not found"))?;
+		let server_items: Vec<&pb::ClipboardResp> = state
+			.clipboards
+			.iter()
+			.filter(|c| state.device_user_id(c.device_id) == Some(user_id))
+			.filter(|c| c.timestamp >= req.start_at as i64 && c.timestamp <= req.end_at as i64)
+			.collect();
+		let update_client_ids = req
+			.items
+			.iter()
+			.filter(|item| !server_items.iter().any(|c| c.hash == item.hash))
+			.map(|item| item.client_id)
+			.collect();
+		let download_server_ids = server_items
+			.iter()
+			.filter(|c| !req.items.iter().any(|item| item.hash == c.hash))
+			.map(|c| c.id)
+			.collect();
+		Ok(Response::new(pb::SyncDeviceResult {
+			update_client_ids,
+			download_server_ids,
+		}))
+	}
+}
+
+#[tonic::async_trait]
+impl FileService for MockServer {
+	async fn upload(&self, request: Request<pb::UploadFile>) -> Result<Response<pb::FileResp>, Status> {
+		let req = request.into_inner();
+		// mock 服务没有登录状态, 只按文件名保存
+		let uri = format!("/file/{}", req.file_name);
+		self.state.lock().unwrap().files.insert(uri.clone(), req.content);
+		Ok(Response::new(pb::FileResp { uri }))
+	}
+
+	async fn get(&self, request: Request<pb::GetFile>) -> Result<Response<pb::FileContent>, Status> {
+		let uri = request.into_inner().uri;
+		let state = self.state.lock().unwrap();
+		let content = state.files.get(&uri).cloned().ok_or_else(|| Status::not_found("file not found"))?;
+		Ok(Response::new(pb::FileContent { content }))
+	}
+}
+
+#[tonic::async_trait]
+impl ClipboardService for MockServer {
+	type WatchStream = Pin<Box<dyn Stream<Item = Result<pb::ClipboardResp, Status>> + Send>>;
+
+	async fn create(&self, request: Request<pb::CreateClipboard>) -> Result<Response<pb::ClipboardResp>, Status> {
+		let req = request.into_inner();
+		let clipboard = {
+			let mut state = self.state.lock().unwrap();
+			let clipboard = pb::ClipboardResp {
+				id: state.next_id(),
+				device_id: req.device_id,
+				r#type: req.r#type,
+				content: req.content,
+				path: req.path,
+				hash: req.hash,
+				timestamp: req.timestamp,
+			};
+			state.clipboards.push(clipboard.clone());
+			clipboard
+		};
+		let _ = self.created.send(clipboard.clone());
+		Ok(Response::new(clipboard))
+	}
+
+	async fn get(&self, request: Request<pb::ClipboardId>) -> Result<Response<pb::ClipboardResp>, Status> {
+		let content_id = request.into_inner().content_id;
+		let state = self.state.lock().unwrap();
+		state
+			.clipboards
+			.iter()
+			.find(|c| c.id == content_id)
+			.cloned()
+			.map(Response::new)
+			.ok_or_else(|| Status::not_found("clipboard not found"))
+	}
+
+	async fn delete(&self, request: Request<pb::DeleteClipboard>) -> Result<Response<pb::DeleteClipboardResp>, Status> {
+		let req = request.into_inner();
+		let mut state = self.state.lock().unwrap();
+		let count = state.clipboards.len();
+		state.clipboards.retain(|c| c.device_id != req.device_id || c.hash != req.hash);
+		Ok(Response::new(pb::DeleteClipboardResp {
+			deleted: state.clipboards.len() < count,
+		}))
+	}
+
+	async fn watch(&self, request: Request<pb::WatchClipboards>) -> Result<Response<Self::WatchStream>, Status> {
+		let req = request.into_inner();
+		let state = self.state.clone();
+		let stream = BroadcastStream::new(self.created.subscribe()).filter_map(move |item| {
+			let clipboard = item.ok()?;
+			let user_id = state.lock().unwrap().device_user_id(clipboard.device_id)?;
+			(user_id == req.user_id && clipboard.device_id != req.exclude_device_id).then_some(Ok(clipboard))
+		});
+		Ok(Response::new(Box::pin(stream)))
+	}
+}
@@ -0,0 +1,7 @@
+mod clipboard;
+mod device;
+mod file;
+mod user;
+
+#[cfg(test)]
+mod mock_server;
@@ -0,0 +1,130 @@
+#[cfg(test)]
+mod tests {
+	use crate::api::api::Api;
+	use crate::api::api_client::ApiClient;
+	use crate::api::common::{ClientError, ClientTrait};
+	use crate::api::models::user::{UserLogin, UserRegister, UserResetPassword, UserSaltQuery};
+	use crate::api::tests::grpc_client::mock_server;
+	use crate::utils::cipher::CryptoHelper;
+	use crate::utils::hash::hash_str;
+	use crate::utils::password::{self, PasswordKdf};
+
+	#[tokio::test]
+	async fn test_user() {
+		let client = mock_server::start().await;
+
+		let register = UserRegister {
+			username: "zeke".to_string(),
+			email: "zeke@example.com".to_string(),
+			password_hash: "hash1".to_string(),
+			encrypted_dek: "dek".to_string(),
+			salt: "".to_string(),
+			kdf: PasswordKdf::Legacy,
+		};
+		let user = client.register_user(register.clone()).await.unwrap();
+		assert_eq!(user.username, "zeke");
+		assert_eq!(user.encrypted_dek, "dek");
+
+		// 重复注册
+		match client.register_user(register).await {
+			Err(ClientError::ApiError { code, .. }) => assert_eq!(code, tonic::Code::AlreadyExists as i32),
+			other => panic!("unexpected result: {:?}", other),
+		}
+
+		let login = UserLogin {
+			username: "zeke".to_string(),
+			password_hash: "hash1".to_string(),
+		};
+		assert_eq!(client.login_user(login.clone()).await.unwrap(), user);
+
+		let reset = UserResetPassword {
+			username: "zeke".to_string(),
+			email: "zeke@example.com".to_string(),
+			password_hash: "hash2".to_string(),
+			salt: "".to_string(),
+			kdf: PasswordKdf::Legacy,
+		};
+		assert_eq!(client.reset_user(reset).await.unwrap().password_hash, "hash2");
+
+		// 旧密码失效
+		match client.login_user(login).await {
+			Err(ClientError::ApiError { code, .. }) => assert_eq!(code, tonic::Code::Unauthenticated as i32),
+			other => panic!("unexpected result: {:?}", other),
+		}
+	}
+
+	#[tokio::test]
+	async fn test_api_register_and_login() {
+		let client = mock_server::start().await;
+		let mut api = Api::in_memory(ApiClient::new(client.clone()));
+
+		api.register_user("zeke".to_string(), "zeke@example.com".to_string(), "password", "super key")
+			.await
+			.unwrap();
+		let user = api.user().unwrap().clone();
+		let salt = client
+			.get_user_salt(UserSaltQuery {
+				username: "zeke".to_string(),
+			})
+			.await
+			.unwrap();
+		assert_eq!(salt.kdf, PasswordKdf::Argon2id);
+		// 服务端只收到 Argon2id 派生的结果
+		let password_hash = password::hash_password("password", PasswordKdf::Argon2id, &salt.salt).unwrap();
+		assert_ne!(password_hash, hash_str("password"));
+		let login = UserLogin {
+			username: "zeke".to_string(),
+			password_hash,
+		};
+		assert_eq!(client.login_user(login).await.unwrap().id, user.id);
+		assert_eq!(CryptoHelper::open_dek("super key", &user.encrypted_dek).unwrap().len(), 32);
+
+		api.logout().await.unwrap();
+		assert!(api.user().is_none());
+		api.login_user("zeke".to_string(), "password").await.unwrap();
+		assert_eq!(api.user().unwrap().id, user.id);
+		match api.login_user("zeke".to_string(), "wrong").await {
+			Err(ClientError::ApiError { code, .. }) => assert_eq!(code, tonic::Code::Unauthenticated as i32),
+			other => panic!("unexpected result: {:?}", other),
+		}
+
+		api.reset_user_password("zeke".to_string(), "zeke@example.com".to_string(), "new password")
+			.await
+			.unwrap();
+		assert!(api.login_user("zeke".to_string(), "password").await.is_err());
+		api.login_user("zeke".to_string(), "new password").await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn test_api_login_migrates_legacy_password() {
+		let client = mock_server::start().await;
+		let register = UserRegister {
+			username: "legacy".to_string(),
+			email: "legacy@example.com".to_string(),
+			password_hash: hash_str("password"),
+			encrypted_dek: "dek".to_string(),
+			salt: "".to_string(),
+			kdf: PasswordKdf::Legacy,
+		};
+		client.register_user(register).await.unwrap();
+
+		let mut api = Api::in_memory(ApiClient::new(client.clone()));
+		api.login_user("legacy".to_string(), "password").await.unwrap();
+
+		// 登录后已换成 Argon2id, 旧的 password_hash 失效
+		let salt = client
+			.get_user_salt(UserSaltQuery {
+				username: "legacy".to_string(),
+			})
+			.await
+			.unwrap();
+		assert_eq!(salt.kdf, PasswordKdf::Argon2id);
+		assert!(!salt.salt.is_empty());
+		let login = UserLogin {
+			username: "legacy".to_string(),
+			password_hash: hash_str("password"),
+		};
+		assert!(client.login_user(login).await.is_err());
+		api.login_user("legacy".to_string(), "password").await.unwrap();
+	}
+}
@@ -0,0 +1,103 @@
+#[cfg(test)]
+mod tests {
+	use crate::api::common::{ClientError, ClientTrait};
+	use crate::api::http_client::HttpClient;
+	use crate::api::models::user::{Token, UserLogin};
+	use mockito::{mock, server_url, Matcher};
+	use serde_json::json;
+
+	fn token(access_token: &str, refresh_token: &str) -> Token {
+		Token {
+			access_token: access_token.to_string(),
+			refresh_token: refresh_token.to_string(),
+			expires_at: 1625567890,
+		}
+	}
+
+	// 刷新后的 token 会写入凭据管理, 测试中不能动到系统里保存的 token
+	fn mock_keyring() {
+		keyring::set_default_credential_builder(keyring::mock::default_credential_builder());
+	}
+
+	fn api_response(data: serde_json::Value) -> String {
+		json!({ "code": 200, "data": data, "error_msg": null }).to_string()
+	}
+
+	fn devices() -> String {
+		api_response(json!([]))
+	}
+
+	#[tokio::test]
+	async fn test_login_sets_authorization_header() {
+		let login_mock = mock("POST", "/user/login")
+			.match_header("authorization", Matcher::Missing)
+			.with_body(api_response(json!({
+				"id": 1, "username": "testuser", "email": "test@example.com", "password_hash": "hash",
+				"encrypted_dek": "dek", "created_at": 0, "updated_at": 0,
+				"token": { "access_token": "a1", "refresh_token": "r1", "expires_at": 1625567890 },
+			})))
+			.create();
+		let devices_mock = mock("GET", "/device/user/1")
+			.match_header("authorization", "Bearer a1")
+			.with_body(devices())
+			.create();
+
+		let client = HttpClient::new(server_url());
+		let login = UserLogin {
+			username: "testuser".to_string(),
+			password_hash: "hash".to_string(),
+		};
+		let user_resp = client.login_user(login).await.unwrap();
+		assert_eq!(user_resp.token, Some(token("a1", "r1")));
+		assert!(client.get_devices_by_user_id(1).await.unwrap().is_empty());
+
+		login_mock.assert();
+		devices_mock.assert();
+	}
+
+	#[tokio::test]
+	async fn test_refresh_token_on_unauthorized() {
+		mock_keyring();
+		let expired_mock = mock("GET", "/device/user/1")
+			.match_header("authorization", "Bearer a1")
+			.with_status(401)
+			.expect(1)
+			.create();
+		let refresh_mock = mock("POST", "/user/refresh")
+			.match_body(Matcher::Json(json!({ "refresh_token": "r1" })))
+			.with_body(api_response(
+				json!({ "access_token": "a2", "refresh_token": "r2", "expires_at": 1625567890 }),
+			))
+			.expect(1)
+			.create();
+		let retry_mock = mock("GET", "/device/user/1")
+			.match_header("authorization", "Bearer a2")
+			.with_body(devices())
+			.expect(1)
+			.create();
+
+		let client = HttpClient::new(server_url()).with_token(Some(token("a1", "r1")));
+		assert!(client.get_devices_by_user_id(1).await.unwrap().is_empty());
+		assert_eq!(client.token().await, Some(token("a2", "r2")));
+
+		expired_mock.assert();
+		refresh_mock.assert();
+		retry_mock.assert();
+	}
+
+	#[tokio::test]
+	async fn test_refresh_token_expired() {
+		mock_keyring();
+		let expired_mock = mock("DELETE", "/device/3").with_status(401).expect(1).create();
+		let refresh_mock = mock("POST", "/user/refresh").with_status(401).expect(1).create();
+
+		let client = HttpClient::new(server_url()).with_token(Some(token("a1", "r1")));
+		let result = client.delete_device(3).await;
+		assert!(matches!(result, Err(ClientError::ApiError { code: 401, .. })));
+		// 需要重新登录, 之后的请求不再携带 token
+		assert_eq!(client.token().await, None);
+
+		expired_mock.assert();
+		refresh_mock.assert();
+	}
+}
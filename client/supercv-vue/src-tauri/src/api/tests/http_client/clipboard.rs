@@ -3,7 +3,7 @@ mod tests {
 	use crate::api::common::ClientTrait;
 	use crate::api::http_client::HttpClient;
 	use crate::api::models::clipboard::{ClipboardResp, CreateClipboard};
-	use crate::utils::hash::{hash_str, hash_vec};
+	use crate::utils::hash::hash_str;
 	use chrono::Utc;
 	use mockito::mock;
 
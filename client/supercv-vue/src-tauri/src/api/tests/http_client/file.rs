@@ -23,13 +23,12 @@ mod tests {
 		let file_content = "Hello, World!";
 		let file_path = create_temp_file(file_content).await;
 		let file_name = "test_temp_file.txt";
-		let user_id = 1;
 
 		let server_url = "http://127.0.0.1:8000";
 		let client = HttpClient::new(server_url.parse().unwrap());
 
 		// Test file upload
-		let upload_result = client.upload_file(user_id, file_path.to_str().unwrap()).await;
+		let upload_result = client.upload_file(file_path.to_str().unwrap()).await;
 		assert!(upload_result.is_ok());
 		let file_resp = upload_result.unwrap();
 		assert!(file_resp.uri.contains(file_name));
@@ -1,5 +1,7 @@
+mod auth;
 mod clipboard;
 mod file;
+mod transfer;
 mod user;
 
 mod device;
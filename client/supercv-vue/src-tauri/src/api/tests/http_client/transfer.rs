@@ -0,0 +1,225 @@
+#[cfg(test)]
+mod tests {
+	use crate::api::common::ClientTrait;
+	use crate::api::http_client::HttpClient;
+	use crate::api::models::file::{ProgressCallback, TransferDirection, TransferProgress};
+	use crate::api::models::user::Token;
+	use crate::db::testing::TempDir;
+	use mockito::{mock, server_url, Matcher};
+	use serde_json::json;
+	use sha2::{Digest, Sha256};
+	use std::sync::{Arc, Mutex};
+
+	fn api_response(data: serde_json::Value) -> String {
+		json!({ "code": 200, "data": data, "error_msg": null }).to_string()
+	}
+
+	fn temp_dir(name: &str) -> TempDir {
+		TempDir::new(&format!("transfer_{}", name))
+	}
+
+	fn collect_progress() -> (ProgressCallback, Arc<Mutex<Vec<TransferProgress>>>) {
+		let progress = Arc::new(Mutex::new(Vec::new()));
+		let collected = progress.clone();
+		(Arc::new(move |p| collected.lock().unwrap().push(p)), progress)
+	}
+
+	#[tokio::test]
+	async fn test_streamed_upload_reports_progress() {
+		let dir = temp_dir("upload");
+		let path = dir.join("small.txt");
+		std::fs::write(&path, "streamed body").unwrap();
+		// 用户由 token 确定, 不出现在 URL 中
+		let upload_mock = mock("POST", "/file")
+			.match_header("authorization", "Bearer a1")
+			.match_body(Matcher::Regex("streamed body".to_string()))
+			.with_body(api_response(json!({ "uri": "/files/5/small.txt" })))
+			.expect(1)
+			.create();
+
+		let (callback, progress) = collect_progress();
+		let token = Token {
+			access_token: "a1".to_string(),
+			refresh_token: "r1".to_string(),
+			expires_at: 0,
+		};
+		let client = HttpClient::new(server_url()).with_progress(callback).with_token(Some(token));
+		let file_resp = client.upload_file(path.to_str().unwrap()).await.unwrap();
+		assert_eq!(file_resp.uri, "/files/5/small.txt");
+		upload_mock.assert();
+
+		let progress = progress.lock().unwrap();
+		let last = progress.last().unwrap();
+		assert_eq!(last.direction, TransferDirection::Upload);
+		assert_eq!((last.transferred, last.total), (13, 13));
+	}
+
+	#[tokio::test]
+	async fn test_chunked_upload_resumes_from_offset() {
+		let dir = temp_dir("chunked");
+		let path = dir.join("large.bin");
+		std::fs::write(&path, "0123456789").unwrap();
+		let hash = format!("{:x}", Sha256::digest(b"0123456789"));
+
+		// 服务端已收到前 4 个字节
+		let init_mock = mock("POST", "/file/upload")
+			.match_body(Matcher::Json(json!({ "file_name": "large.bin", "size": 10, "hash": hash })))
+			.with_body(api_response(json!({ "upload_id": "u1", "offset": 4 })))
+			.expect(1)
+			.create();
+		let chunk_mocks: Vec<_> = [("4", "4567", 8), ("8", "89", 10)]
+			.into_iter()
+			.map(|(offset, body, next)| {
+				mock("PUT", "/file/upload/u1")
+					.match_query(Matcher::UrlEncoded("offset".to_string(), offset.to_string()))
+					.match_body(body)
+					.with_body(api_response(json!({ "upload_id": "u1", "offset": next })))
+					.expect(1)
+					.create()
+			})
+			.collect();
+		let complete_mock = mock("POST", "/file/upload/u1/complete")
+			.match_body(Matcher::Json(json!({ "hash": hash })))
+			.with_body(api_response(json!({ "uri": "/files/6/large.bin" })))
+			.expect(1)
+			.create();
+
+		let (callback, progress) = collect_progress();
+		let client = HttpClient::new(server_url()).with_progress(callback).with_chunk_size(4);
+		let file_resp = client.upload_file(path.to_str().unwrap()).await.unwrap();
+		assert_eq!(file_resp.uri, "/files/6/large.bin");
+		init_mock.assert();
+		for m in chunk_mocks {
+			m.assert();
+		}
+		complete_mock.assert();
+		assert_eq!(progress.lock().unwrap().last().unwrap().transferred, 10);
+	}
+
+	#[tokio::test]
+	async fn test_chunked_upload_falls_back_to_single_request() {
+		let dir = temp_dir("fallback");
+		let path = dir.join("large.txt");
+		std::fs::write(&path, "0123456789").unwrap();
+		// 旧服务端没有分片上传的接口
+		let init_mock = mock("POST", "/file/upload").with_status(422).expect(1).create();
+		let upload_mock = mock("POST", "/file")
+			.match_body(Matcher::Regex("0123456789".to_string()))
+			.with_body(api_response(json!({ "uri": "/files/7/large.txt" })))
+			.expect(1)
+			.create();
+
+		let client = HttpClient::new(server_url()).with_chunk_size(4);
+		let file_resp = client.upload_file(path.to_str().unwrap()).await.unwrap();
+		assert_eq!(file_resp.uri, "/files/7/large.txt");
+		init_mock.assert();
+		upload_mock.assert();
+	}
+
+	#[tokio::test]
+	async fn test_resent_chunk_is_counted_once() {
+		keyring::set_default_credential_builder(keyring::mock::default_credential_builder());
+		let dir = temp_dir("resent");
+		let path = dir.join("resent.bin");
+		std::fs::write(&path, "01234567").unwrap();
+		let init_mock = mock("POST", "/file/upload")
+			.with_body(api_response(json!({ "upload_id": "u2", "offset": 0 })))
+			.expect(1)
+			.create();
+		// 第一个分片先返回 401, 刷新 token 后重发
+		let expired_mock = mock("PUT", "/file/upload/u2")
+			.match_header("authorization", "Bearer a1")
+			.match_query(Matcher::Any)
+			.with_status(401)
+			.expect(1)
+			.create();
+		let refresh_mock = mock("POST", "/user/refresh")
+			.with_body(api_response(json!({ "access_token": "a2", "refresh_token": "r2", "expires_at": 0 })))
+			.expect(1)
+			.create();
+		let chunk_mocks: Vec<_> = [("0", 4), ("4", 8)]
+			.into_iter()
+			.map(|(offset, next)| {
+				mock("PUT", "/file/upload/u2")
+					.match_header("authorization", "Bearer a2")
+					.match_query(Matcher::UrlEncoded("offset".to_string(), offset.to_string()))
+					.with_body(api_response(json!({ "upload_id": "u2", "offset": next })))
+					.expect(1)
+					.create()
+			})
+			.collect();
+		let complete_mock = mock("POST", "/file/upload/u2/complete")
+			.with_body(api_response(json!({ "uri": "/files/8/resent.bin" })))
+			.expect(1)
+			.create();
+
+		let (callback, progress) = collect_progress();
+		let token = Token {
+			access_token: "a1".to_string(),
+			refresh_token: "r1".to_string(),
+			expires_at: 0,
+		};
+		let client = HttpClient::new(server_url())
+			.with_progress(callback)
+			.with_chunk_size(4)
+			.with_token(Some(token));
+		client.upload_file(path.to_str().unwrap()).await.unwrap();
+		init_mock.assert();
+		expired_mock.assert();
+		refresh_mock.assert();
+		for m in chunk_mocks {
+			m.assert();
+		}
+		complete_mock.assert();
+		// 重发的分片计入两次时, 第一个分片后就会报告传输完成
+		let transferred: Vec<u64> = progress.lock().unwrap().iter().map(|p| p.transferred).collect();
+		assert_eq!(transferred, vec![8]);
+	}
+
+	#[tokio::test]
+	async fn test_download_resumes_with_range() {
+		let dir = temp_dir("download");
+		let dest = dir.join("resume.bin");
+		let part = dir.join("resume.bin.part");
+		std::fs::write(&part, "hello ").unwrap();
+		let range_mock = mock("GET", "/files/5/resume.bin")
+			.match_header("range", "bytes=6-")
+			.with_status(206)
+			.with_body("world")
+			.expect(1)
+			.create();
+
+		let (callback, progress) = collect_progress();
+		let client = HttpClient::new(server_url()).with_progress(callback);
+		assert_eq!(client.download_file("/files/5/resume.bin", &dest).await.unwrap(), 11);
+		range_mock.assert();
+		assert_eq!(std::fs::read(&dest).unwrap(), b"hello world");
+		assert!(!part.exists());
+		let last = progress.lock().unwrap().last().cloned().unwrap();
+		assert_eq!(last.direction, TransferDirection::Download);
+		assert_eq!((last.transferred, last.total), (11, 11));
+	}
+
+	#[tokio::test]
+	async fn test_download_restarts_when_range_not_satisfiable() {
+		let dir = temp_dir("restart");
+		let dest = dir.join("changed.bin");
+		std::fs::write(dir.join("changed.bin.part"), "stale partial data").unwrap();
+		let range_mock = mock("GET", "/files/5/changed.bin")
+			.match_header("range", Matcher::Any)
+			.with_status(416)
+			.expect(1)
+			.create();
+		let full_mock = mock("GET", "/files/5/changed.bin")
+			.match_header("range", Matcher::Missing)
+			.with_body("fresh")
+			.expect(1)
+			.create();
+
+		let client = HttpClient::new(server_url());
+		assert_eq!(client.download_file("/files/5/changed.bin", &dest).await.unwrap(), 5);
+		range_mock.assert();
+		full_mock.assert();
+		assert_eq!(std::fs::read(&dest).unwrap(), b"fresh");
+	}
+}
@@ -1,9 +1,13 @@
 #[cfg(test)]
 mod tests {
+	use crate::api::api::Api;
+	use crate::api::api_client::ApiClient;
 	use crate::api::common::ClientTrait;
 	use crate::api::http_client::HttpClient;
 	use crate::api::models::user::{UserLogin, UserRegister, UserResetPassword, UserResp};
-	use mockito::{mock, server_url};
+	use crate::utils::hash::hash_str;
+	use crate::utils::password::{self, PasswordKdf};
+	use mockito::{mock, server_url, Matcher};
 	use rand::Rng;
 
 	fn create_mock_server(method: &str, path: &str, mut body: Option<&str>) -> mockito::Mock {
@@ -42,6 +46,8 @@ mod tests {
 			email: "test@example.com".to_string(),
 			password_hash: "hashedpassword".to_string(),
 			encrypted_dek: "encrypteddek".to_string(),
+			salt: "".to_string(),
+			kdf: PasswordKdf::Legacy,
 		};
 
 		let result = client.register_user(user).await;
@@ -82,6 +88,8 @@ mod tests {
 			username: "testuser".to_string(),
 			email: "test@example.com".to_string(),
 			password_hash: "newhashedpassword".to_string(),
+			salt: "".to_string(),
+			kdf: PasswordKdf::Legacy,
 		};
 
 		let result = client.reset_user(reset).await;
@@ -107,6 +115,8 @@ mod tests {
 			email: format!("{}@test", random_two_number),
 			password_hash: format!("ph_{}", random_two_number),
 			encrypted_dek: format!("ph_{}", random_two_number),
+			salt: "".to_string(),
+			kdf: PasswordKdf::Legacy,
 		};
 
 		let user_login = UserLogin {
@@ -117,6 +127,8 @@ mod tests {
 			username: format!("test_{}", random_two_number),
 			email: format!("{}@test", random_two_number),
 			password_hash: "reset_ph".to_string(),
+			salt: "".to_string(),
+			kdf: PasswordKdf::Legacy,
 		};
 
 		// 注册
@@ -135,6 +147,89 @@ mod tests {
 		assert_eq!(res_reset.unwrap().email, user_reset_password.email);
 	}
 
+	#[tokio::test]
+	async fn test_api_login_migrates_legacy_password() {
+		let salt_mock = mock("POST", "/user/salt")
+			.match_body(Matcher::Json(serde_json::json!({ "username": "testuser" })))
+			.with_body(r#"{"code": 200, "data": {"salt": "", "kdf": "legacy"}, "error_msg": null}"#)
+			.expect(1)
+			.create();
+		let login_mock = mock("POST", "/user/login")
+			.match_body(Matcher::PartialJson(serde_json::json!({ "password_hash": hash_str("password") })))
+			.with_body(
+				r#"{"code": 200, "data": {"id": 1, "username": "testuser", "email": "test@example.com", "password_hash": "somehashvalue",
+				"encrypted_dek": "someencryptedkey", "created_at": 0, "updated_at": 0,
+				"token": {"access_token": "a1", "refresh_token": "r1", "expires_at": 0}}, "error_msg": null}"#,
+			)
+			.expect(1)
+			.create();
+		// 迁移请求需要登录后的 token, 并带上旧的 password_hash 校验
+		let upgrade_mock = mock("POST", "/user/password")
+			.match_header("authorization", "Bearer a1")
+			.match_body(Matcher::PartialJson(
+				serde_json::json!({ "password_hash": hash_str("password"), "kdf": "argon2id" }),
+			))
+			.with_body(
+				r#"{"code": 200, "data": {"id": 1, "username": "testuser", "email": "test@example.com", "password_hash": "new",
+				"encrypted_dek": "someencryptedkey", "created_at": 0, "updated_at": 0}, "error_msg": null}"#,
+			)
+			.expect(1)
+			.create();
+
+		let mut api = Api::in_memory(ApiClient::new(HttpClient::new(server_url())));
+		api.login_user("testuser".to_string(), "password").await.unwrap();
+		assert_eq!(api.user().unwrap().id, 1);
+
+		salt_mock.assert();
+		login_mock.assert();
+		upgrade_mock.assert();
+	}
+
+	#[tokio::test]
+	async fn test_api_register_then_login() {
+		let user_body = r#"{"code": 200, "data": {"id": 2, "username": "newuser", "email": "new@example.com", "password_hash": "h",
+			"encrypted_dek": "someencryptedkey", "created_at": 0, "updated_at": 0,
+			"token": {"access_token": "a2", "refresh_token": "r2", "expires_at": 0}}, "error_msg": null}"#;
+		let register_mock = mock("POST", "/user/register")
+			.match_body(Matcher::PartialJson(serde_json::json!({ "username": "newuser", "kdf": "argon2id" })))
+			.with_body(user_body)
+			.expect(1)
+			.create();
+		let mut api = Api::in_memory(ApiClient::new(HttpClient::new(server_url())));
+		api.register_user("newuser".to_string(), "new@example.com".to_string(), "password", "super_key")
+			.await
+			.unwrap();
+		register_mock.assert();
+
+		let salt = password::gen_salt();
+		let salt_mock = mock("POST", "/user/salt")
+			.with_body(serde_json::json!({ "code": 200, "data": { "salt": salt, "kdf": "argon2id" }, "error_msg": null }).to_string())
+			.expect(1)
+			.create();
+		let password_hash = password::hash_password("password", PasswordKdf::Argon2id, &salt).unwrap();
+		let login_mock = mock("POST", "/user/login")
+			.match_body(Matcher::Json(
+				serde_json::json!({ "username": "newuser", "password_hash": password_hash }),
+			))
+			.with_body(user_body)
+			.expect(1)
+			.create();
+		api.login_user("newuser".to_string(), "password").await.unwrap();
+		assert_eq!(api.user().unwrap().id, 2);
+		salt_mock.assert();
+		login_mock.assert();
+
+		// 已经是 Argon2id 的账户, 服务端返回 Legacy 时拒绝登录, 不发送不加盐的 password_hash
+		let legacy_mock = mock("POST", "/user/salt")
+			.with_body(r#"{"code": 200, "data": {"salt": "", "kdf": "legacy"}, "error_msg": null}"#)
+			.expect(1)
+			.create();
+		let login_mock = mock("POST", "/user/login").expect(0).create();
+		assert!(api.login_user("newuser".to_string(), "password").await.is_err());
+		legacy_mock.assert();
+		login_mock.assert();
+	}
+
 	fn assert_user_fields_match(resp: &UserResp, reg: &UserRegister) {
 		assert_eq!(resp.username, reg.username, "Username mismatch");
 		assert_eq!(resp.email, reg.email, "Email mismatch");
@@ -1 +1,2 @@
-mod http_client;
\ No newline at end of file
+mod http_client;
+mod grpc_client;
\ No newline at end of file
@@ -0,0 +1,452 @@
+// supercv 命令行: 无界面运行监听, 以及在脚本中读写剪贴板历史
+//
+// 与桌面端共用同一个 SQLite 数据库, 输出均为 JSON, 方便配合 jq 使用
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use clipboard_rs::{Clipboard, ClipboardContext};
+use sea_orm::DatabaseConnection;
+use serde::Serialize;
+
+use supercv_vue::clipboard_helper::{parse_paths, ClipboardHelper};
+use supercv_vue::core::history::{self, ConflictPolicy, ExportFormat};
+use supercv_vue::core::pasteboard::ContentType;
+use supercv_vue::core::revision::{self, EntryEdit};
+use supercv_vue::core::stats;
+use supercv_vue::core::trash::{self, DeleteFilter};
+#[cfg(target_os = "linux")]
+use supercv_vue::core::wayland;
+use supercv_vue::db::backup;
+use supercv_vue::db::connection::init_db_connection;
+use supercv_vue::db::crud::host_clipboard::{self, Cursor};
+use supercv_vue::db::entities::host_clipboard::Model;
+use supercv_vue::db::importers::{self, ImportSource};
+use supercv_vue::ipc;
+use supercv_vue::utils::config::CONFIG;
+
+#[derive(Parser)]
+#[command(name = "supercv", version, about = "SuperCV clipboard history")]
+struct Cli {
+    /// Pretty-print JSON output
+    #[arg(long, global = true)]
+    pretty: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the clipboard watcher without the GUI
+    Daemon {
+        /// 0 trace, 1 error, 2 warn, 3 info, 4 debug
+        #[arg(long, default_value_t = 3)]
+        log_level: i32,
+    },
+    /// List recent entries
+    List(Filter),
+    /// Search entries by content
+    Search {
+        query: String,
+        #[command(flatten)]
+        filter: Filter,
+    },
+    /// List one page of entries with the cursor of the next page and the total count
+    Page {
+        /// Only entries matching this text
+        #[arg(long)]
+        query: Option<String>,
+        /// next_cursor of the previous page, as TIMESTAMP:ID
+        #[arg(long, value_parser = parse_cursor)]
+        cursor: Option<Cursor>,
+        #[command(flatten)]
+        filter: Filter,
+    },
+    /// Show one entry
+    Get { id: i32 },
+    /// Put an entry back on the clipboard
+    Copy { id: i32 },
+    /// Pin an entry so it never expires
+    Pin {
+        id: i32,
+        #[arg(long)]
+        unpin: bool,
+    },
+    /// Change the content of a text entry, or the title and note of any entry
+    Edit {
+        id: i32,
+        #[arg(long)]
+        content: Option<String>,
+        #[arg(long)]
+        title: Option<String>,
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// Revert the last edit of an entry
+    Undo { id: i32 },
+    /// Delete entries; the app or daemon purges them and their images after the undo window
+    Delete {
+        #[arg(required = true)]
+        ids: Vec<i32>,
+    },
+    /// Delete all entries, or only those matching the filters; pinned entries are kept by default
+    Clear {
+        /// Can be repeated, e.g. --type text --type file
+        #[arg(long = "type", value_enum)]
+        types: Vec<EntryType>,
+        /// Only entries copied at or after this unix timestamp
+        #[arg(long)]
+        since: Option<i64>,
+        /// Only entries copied before this unix timestamp
+        #[arg(long)]
+        before: Option<i64>,
+        #[arg(long)]
+        include_pinned: bool,
+    },
+    /// Restore entries deleted by `delete` or `clear` within the undo window
+    UndoDelete {
+        /// batch printed by the delete
+        batch: i64,
+    },
+    /// Export all entries, including expired ones
+    Export {
+        /// Write to a file instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        /// Only entries newer than this unix timestamp
+        #[arg(long, default_value_t = 0)]
+        since: i64,
+        /// Only these entries, can be repeated
+        #[arg(long = "id")]
+        ids: Vec<i32>,
+        /// Defaults to the output file extension, or json on stdout. archive bundles images and files
+        #[arg(long, short, value_enum)]
+        format: Option<Format>,
+    },
+    /// Import entries exported by `supercv export` or the app, merging by hash
+    Import {
+        input: PathBuf,
+        /// Defaults to the file extension
+        #[arg(long, short, value_enum)]
+        format: Option<Format>,
+        /// What to do when an entry with the same hash already exists
+        #[arg(long, value_enum, default_value_t = OnConflict::Skip)]
+        on_conflict: OnConflict,
+    },
+    /// Import history from another clipboard manager
+    Migrate {
+        #[arg(value_enum)]
+        from: App,
+        /// Defaults to the app's data file on this system
+        path: Option<PathBuf>,
+        /// Only report what would be imported
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Back up the database and files now, keeping the configured number of backups
+    Backup {
+        /// List existing backups instead, newest first
+        #[arg(long)]
+        list: bool,
+    },
+    /// Replace all entries with a backup; the current data is backed up first
+    Restore {
+        /// Backup name as shown by `supercv backup --list`, or its path
+        backup: String,
+    },
+    /// Show counts per day, hour, type and source, storage usage and the most repeated entries
+    Stats {
+        /// Number of days counted per day and hour
+        #[arg(long, default_value_t = 30)]
+        days: u32,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum App {
+    Copyq,
+    /// Snippets exported from Clipy's snippet editor. Clipy's history (a Realm database) is not supported
+    Clipy,
+    Maccy,
+    Ditto,
+    Gpaste,
+    Klipper,
+}
+
+impl From<App> for ImportSource {
+    fn from(app: App) -> Self {
+        match app {
+            App::Copyq => ImportSource::Copyq,
+            App::Clipy => ImportSource::Clipy,
+            App::Maccy => ImportSource::Maccy,
+            App::Ditto => ImportSource::Ditto,
+            App::Gpaste => ImportSource::Gpaste,
+            App::Klipper => ImportSource::Klipper,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    Jsonl,
+    Csv,
+    Archive,
+}
+
+impl From<Format> for ExportFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Json => ExportFormat::Json,
+            Format::Jsonl => ExportFormat::Jsonl,
+            Format::Csv => ExportFormat::Csv,
+            Format::Archive => ExportFormat::Archive,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OnConflict {
+    /// Keep the local entry
+    Skip,
+    Overwrite,
+    /// Overwrite only if the imported entry is newer
+    Newer,
+}
+
+impl From<OnConflict> for ConflictPolicy {
+    fn from(policy: OnConflict) -> Self {
+        match policy {
+            OnConflict::Skip => ConflictPolicy::Skip,
+            OnConflict::Overwrite => ConflictPolicy::Overwrite,
+            OnConflict::Newer => ConflictPolicy::Newer,
+        }
+    }
+}
+
+#[derive(Args)]
+struct Filter {
+    #[arg(long, short = 'n', default_value_t = 20)]
+    limit: u64,
+    /// Can be repeated, e.g. --type text --type file
+    #[arg(long = "type", value_enum)]
+    types: Vec<EntryType>,
+    /// Source application or process name
+    #[arg(long)]
+    app: Option<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum EntryType {
+    Text,
+    Image,
+    File,
+}
+
+impl EntryType {
+    fn to_i32(self) -> i32 {
+        match self {
+            EntryType::Text => ContentType::Text.to_i32(),
+            EntryType::Image => ContentType::Image.to_i32(),
+            EntryType::File => ContentType::File.to_i32(),
+        }
+    }
+}
+
+impl Filter {
+    fn type_list(&self) -> Option<Vec<i32>> {
+        if self.types.is_empty() {
+            None
+        } else {
+            Some(self.types.iter().map(|t| t.to_i32()).collect())
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = run(cli).await {
+        eprintln!("supercv: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    if let Command::Daemon { log_level } = cli.command {
+        return daemon(log_level).await;
+    }
+
+    let db = init_db_connection(None).await?;
+    match cli.command {
+        Command::Daemon { .. } => unreachable!(),
+        Command::List(filter) => {
+            let entries =
+                host_clipboard::get_clipboards_by_type_list(&db, None, Some(filter.limit), filter.type_list(), filter.app.as_deref()).await?;
+            print_json(&entries, cli.pretty)
+        },
+        Command::Search { query, filter } => {
+            let query = query.to_lowercase();
+            let entries =
+                host_clipboard::get_clipboards_by_type_list(&db, Some(&query), Some(filter.limit), filter.type_list(), filter.app.as_deref()).await?;
+            print_json(&entries, cli.pretty)
+        },
+        Command::Get { id } => print_json(&get_entry(&db, id).await?, cli.pretty),
+        Command::Copy { id } => set_clipboard(get_entry(&db, id).await?),
+        Command::Pin { id, unpin } => print_json(&host_clipboard::set_clipboard_pinned(&db, id, !unpin).await?, cli.pretty),
+        Command::Edit { id, content, title, note } => {
+            print_json(&revision::edit_entry(&db, id, EntryEdit { content, title, note }).await?, cli.pretty)
+        },
+        Command::Undo { id } => print_json(&revision::undo_edit(&db, id).await?, cli.pretty),
+        Command::Page { query, cursor, filter } => {
+            let query = query.map(|query| query.to_lowercase());
+            let page =
+                host_clipboard::get_clipboard_page(&db, query.as_deref(), filter.type_list(), filter.app.as_deref(), cursor, filter.limit).await?;
+            print_json(&page, cli.pretty)
+        },
+        Command::Delete { ids } => print_json(&trash::delete_entries(&db, ids).await?, cli.pretty),
+        Command::Clear {
+            types,
+            since,
+            before,
+            include_pinned,
+        } => {
+            let filter = DeleteFilter {
+                type_list: (!types.is_empty()).then(|| types.iter().map(|t| t.to_i32()).collect()),
+                start: since,
+                end: before,
+                include_pinned,
+            };
+            print_json(&trash::delete_by_filter(&db, filter).await?, cli.pretty)
+        },
+        Command::UndoDelete { batch } => print_json(&trash::undo_delete(&db, batch).await?, cli.pretty),
+        Command::Export { output, since, ids, format } => {
+            let mut entries = if ids.is_empty() {
+                host_clipboard::get_clipboard_entries_by_gt_timestamp(&db, since).await?
+            } else {
+                host_clipboard::get_clipboard_entries_by_id_list(&db, Some(ids)).await?
+            };
+            entries.retain(|e| e.timestamp > since);
+            let format = format
+                .map(ExportFormat::from)
+                .or_else(|| output.as_deref().and_then(ExportFormat::from_path));
+            match (output, format.unwrap_or(ExportFormat::Json)) {
+                // JSON 保持原来的输出, 带上 id
+                (Some(path), ExportFormat::Json) => {
+                    std::fs::write(&path, to_json(&entries, cli.pretty)?)?;
+                    eprintln!("exported {} entries to {}", entries.len(), path.display());
+                    Ok(())
+                },
+                (None, ExportFormat::Json) => print_json(&entries, cli.pretty),
+                (Some(path), format) => {
+                    let report = history::export_entries(entries, format, &path).await?;
+                    eprintln!("exported {} entries and {} files to {}", report.entries, report.files, path.display());
+                    Ok(())
+                },
+                (None, format) => Ok(history::write_records(&entries, format, std::io::stdout().lock())?),
+            }
+        },
+        Command::Import { input, format, on_conflict } => {
+            let files_dir = CONFIG.read().unwrap().files_path.join("imported");
+            let report = history::import_entries(&db, &input, format.map(ExportFormat::from), on_conflict.into(), &files_dir).await?;
+            print_json(&report, cli.pretty)
+        },
+        Command::Migrate { from, path, dry_run } => {
+            let files_dir = CONFIG.read().unwrap().files_path.join("imported");
+            let report = importers::import_history(&db, from.into(), path.as_deref(), dry_run, &files_dir).await?;
+            print_json(&report, cli.pretty)
+        },
+        Command::Backup { list: true } => print_json(&backup::list()?, cli.pretty),
+        Command::Backup { list: false } => print_json(&backup::backup_now(&db).await?, cli.pretty),
+        Command::Restore { backup } => print_json(&backup::restore(&db, &backup).await?, cli.pretty),
+        Command::Stats { days } => print_json(&stats::get_stats(&db, days).await?, cli.pretty),
+    }
+}
+
+async fn daemon(log_level: i32) -> Result<(), Box<dyn Error>> {
+    let clipboard_helper = Arc::new(ClipboardHelper::new(Some(log_level), Some(2)).await?);
+    // 无界面时通过环境变量提供 super_key
+    if let Ok(super_key) = std::env::var("SUPERCV_SUPER_KEY") {
+        if let Err(e) = clipboard_helper.start_sync(&super_key).await {
+            log::error!("Failed to start sync: {}", e);
+        }
+    }
+    ipc::start_server(clipboard_helper.clone());
+    log::info!("supercv daemon started, press Ctrl+C to stop");
+    tokio::signal::ctrl_c().await?;
+    clipboard_helper.shutdown().await;
+    log::info!("supercv daemon stopped");
+    Ok(())
+}
+
+fn parse_cursor(value: &str) -> Result<Cursor, String> {
+    let (timestamp, id) = value.split_once(':').ok_or("expected TIMESTAMP:ID")?;
+    Ok(Cursor {
+        timestamp: timestamp.parse().map_err(|e| format!("invalid timestamp: {}", e))?,
+        id: id.parse().map_err(|e| format!("invalid id: {}", e))?,
+    })
+}
+
+async fn get_entry(db: &DatabaseConnection, id: i32) -> Result<Model, Box<dyn Error>> {
+    host_clipboard::get_clipboard_entry(db, id)
+        .await?
+        .ok_or_else(|| format!("entry {} not found", id).into())
+}
+
+fn set_clipboard(entry: Model) -> Result<(), Box<dyn Error>> {
+    let is_text = entry.r#type == ContentType::Text.to_i32();
+
+    // wl-clipboard-rs 会在后台进程中继续提供内容
+    #[cfg(target_os = "linux")]
+    if wayland::is_wayland_session() {
+        return if is_text {
+            wayland::set_text(entry.content)
+        } else if entry.r#type == ContentType::Image.to_i32() {
+            wayland::set_image(parse_paths(entry.path))
+        } else {
+            wayland::set_files(parse_paths(entry.path))
+        };
+    }
+
+    let ctx = ClipboardContext::new().map_err(|e| e.to_string())?;
+    if is_text {
+        ctx.set_text(entry.content).map_err(|e| e.to_string())?;
+    } else {
+        ctx.set_files(parse_paths(entry.path)).map_err(|e| e.to_string())?;
+    }
+
+    #[cfg(target_os = "linux")]
+    wait_for_selection_loss()?;
+    Ok(())
+}
+
+// X11 下剪贴板内容由当前进程提供, 进程退出后就会丢失, 等到被其他程序替换后再退出
+#[cfg(target_os = "linux")]
+fn wait_for_selection_loss() -> Result<(), Box<dyn Error>> {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::ConnectionExt;
+
+    let (conn, _) = x11rb::rust_connection::RustConnection::connect(None)?;
+    let clipboard = conn.intern_atom(false, b"CLIPBOARD")?.reply()?.atom;
+    let owner = conn.get_selection_owner(clipboard)?.reply()?.owner;
+    eprintln!("serving clipboard until it is replaced, press Ctrl+C to stop");
+    while conn.get_selection_owner(clipboard)?.reply()?.owner == owner {
+        std::thread::sleep(std::time::Duration::from_millis(200));
+    }
+    conn.flush()?;
+    Ok(())
+}
+
+fn to_json<T: Serialize>(value: &T, pretty: bool) -> Result<String, serde_json::Error> {
+    if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    }
+}
+
+fn print_json<T: Serialize>(value: &T, pretty: bool) -> Result<(), Box<dyn Error>> {
+    println!("{}", to_json(value, pretty)?);
+    Ok(())
+}
@@ -1,107 +1,402 @@
 use clipboard_rs::{Clipboard, ClipboardContent, ClipboardContext, ClipboardWatcher, ClipboardWatcherContext, WatcherShutdown};
-use log::{debug, error};
+use log::{debug, error, info, warn};
 use sea_orm::DatabaseConnection;
 use serde_json::Value;
-use std::io;
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 use tauri::Manager;
-use tokio::sync::Mutex;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc::Sender;
+use tokio::task::JoinHandle;
 
-use crate::core::clipboard::ClipboardHandle;
-use crate::db::connection::init_db_connection;
+use crate::api::models::user::{Token, User};
+use crate::core::capture::{self, CaptureState};
+use crate::core::clipboard::{self, CaptureMetricsSnapshot, ClipboardHandle, LastHash, CAPTURE_METRICS};
+use crate::core::events::{self, ClipboardEvent};
+use crate::core::history::{self, ConflictPolicy, ExportFormat, ExportReport, ImportReport};
+#[cfg(target_os = "linux")]
+use crate::core::pasteboard::ContentSource;
+use crate::core::pasteboard::{ContentType, PasteboardContent};
+#[cfg(target_os = "linux")]
+use crate::core::primary::{PrimarySelection, PrimaryWatcher, PrimaryWatcherShutdown};
+use crate::core::revision::{self, EntryEdit};
+use crate::core::stats::{self, ClipboardStats, Timeline};
+use crate::core::trash::{self, DeleteFilter, DeleteReport};
+#[cfg(target_os = "linux")]
+use crate::core::wayland::{self, WaylandWatcher, WaylandWatcherShutdown};
+use crate::db::backup::{self, BackupInfo, RestoreReport};
 use crate::db::crud;
+use crate::db::crud::host_clipboard::{ClipboardPage, Cursor};
+use crate::db::entities::clipboard_revision::Model as Revision;
 use crate::db::entities::host_clipboard::Model;
+use crate::db::importers::{self, ImportSource, MigrationReport};
+use crate::db::recovery::{self, Recovery, RecoveryReport};
+use crate::error::{AppError, AppResult};
 use crate::time_it;
 use crate::utils::config::{UserConfig, CONFIG};
+use crate::utils::hash::hash_str;
 use crate::utils::{config, logger};
 
+// 退出时最多等待多久让队列中的内容写入数据库
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
 pub struct ClipboardHelper {
-    db: Arc<Mutex<DatabaseConnection>>,
-    ctx: ClipboardContext,
-    #[allow(dead_code)]
-    watcher_shutdown: WatcherShutdown,
+    db: DatabaseConnection,
+    ctx: Option<ClipboardContext>,
+    // 监听和写入剪贴板都使用 data-control 协议
+    #[cfg(target_os = "linux")]
+    wayland: bool,
+    #[cfg(target_os = "linux")]
+    primary: Option<PrimarySelection>,
+    // 退出时取出并停止
+    capture: std::sync::Mutex<Option<CaptureShutdown>>,
+    recovery: Option<RecoveryReport>,
+}
+
+// 先停止 watcher, 采集任务处理完已收到的事件后结束, 写入任务随之写完剩余的内容
+struct CaptureShutdown {
+    watcher: Option<WatcherShutdown>,
+    #[cfg(target_os = "linux")]
+    wayland: Option<WaylandWatcherShutdown>,
+    #[cfg(target_os = "linux")]
+    primary: Option<PrimaryWatcherShutdown>,
+    writer: JoinHandle<()>,
+}
+
+impl CaptureShutdown {
+    async fn stop(self) {
+        if let Some(shutdown) = self.watcher {
+            shutdown.stop();
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(shutdown) = self.wayland {
+            shutdown.stop();
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(shutdown) = self.primary {
+            shutdown.stop();
+        }
+        match tokio::time::timeout(SHUTDOWN_TIMEOUT, self.writer).await {
+            Ok(Ok(())) => info!("Clipboard capture stopped"),
+            Ok(Err(e)) => error!("Clipboard writer task failed: {}", e),
+            Err(_) => warn!("Timed out waiting for pending clipboard entries"),
+        }
+    }
 }
 
 impl ClipboardHelper {
-    pub async fn new(log_level: Option<i32>, sql_level: Option<i32>) -> Self {
+    pub async fn new(log_level: Option<i32>, sql_level: Option<i32>) -> AppResult<Self> {
         logger::init_logger(log_level, sql_level);
-        // 初始化数据库连接
-        let db_connection = init_db_connection(None).await.expect("Failed to connect to database");
-        let db = Arc::new(Mutex::new(db_connection));
+        // 初始化数据库连接, 数据库损坏时自动恢复
+        let (db, recovery) = recovery::open_database().await?;
+        // 内存数据库不参与定时备份, 避免轮换掉之前的备份
+        if !recovery.as_ref().is_some_and(|r| r.recovery == Recovery::InMemory) {
+            backup::start(db.clone());
+        }
+        // 清除过了撤销窗口的已删除记录
+        trash::start(db.clone());
+
+        // 所有采集来源共用一个写入任务
+        let (entries, writer) = clipboard::spawn_writer(db.clone());
+
+        let last_hash = LastHash::default();
+
+        // X11 PRIMARY selection, 纯 Wayland 下不可用
+        #[cfg(target_os = "linux")]
+        let (primary, primary_shutdown) = Self::start_primary_watcher(entries.clone(), last_hash.clone());
 
         // 创建 ClipboardHandle
-        let clipboard_manager = ClipboardHandle::new(db.clone());
+        #[allow(unused_mut)]
+        let mut clipboard_manager = ClipboardHandle::new(entries).with_last_hash(last_hash);
+        #[cfg(target_os = "linux")]
+        clipboard_manager.set_primary_selection(primary.clone());
+        let notifier = clipboard_manager.spawn();
+        let ctx = ClipboardContext::new()
+            .map_err(|e| error!("Failed to create clipboard context: {}", e))
+            .ok();
+        let mut capture = CaptureShutdown {
+            watcher: None,
+            #[cfg(target_os = "linux")]
+            wayland: None,
+            #[cfg(target_os = "linux")]
+            primary: primary_shutdown,
+            writer,
+        };
 
-        let mut watcher = ClipboardWatcherContext::new().unwrap();
-        let watcher_shutdown = watcher.add_handler(clipboard_manager).get_shutdown_channel();
-        // 在新的任务中启动 watcher
-        let _ = tokio::spawn(async move {
-            watcher.start_watch();
-        });
+        // Wayland 会话优先使用 data-control 协议, 合成器不支持时回退到 X11 (XWayland)
+        #[cfg(target_os = "linux")]
+        if wayland::is_wayland_session() {
+            match WaylandWatcher::new() {
+                Ok(mut watcher) => {
+                    capture.wayland = Some(watcher.add_handler(notifier).get_shutdown_channel());
+                    tokio::task::spawn_blocking(move || watcher.start_watch());
+                    return Ok(Self {
+                        db,
+                        ctx,
+                        wayland: true,
+                        primary,
+                        capture: std::sync::Mutex::new(Some(capture)),
+                        recovery,
+                    });
+                },
+                Err(e) => warn!("Wayland clipboard unavailable, fallback to X11: {}", e),
+            }
+        }
 
-        Self {
+        match ClipboardWatcherContext::new() {
+            Ok(mut watcher) => {
+                capture.watcher = Some(watcher.add_handler(notifier).get_shutdown_channel());
+                // start_watch 会一直阻塞到 stop
+                tokio::task::spawn_blocking(move || watcher.start_watch());
+            },
+            Err(e) => error!("Failed to start clipboard watcher, clipboard history is disabled: {}", e),
+        }
+
+        Ok(Self {
             db,
-            ctx: ClipboardContext::new().unwrap(),
-            watcher_shutdown,
-            // watcher_handle,
+            ctx,
+            #[cfg(target_os = "linux")]
+            wayland: false,
+            #[cfg(target_os = "linux")]
+            primary,
+            capture: std::sync::Mutex::new(Some(capture)),
+            recovery,
+        })
+    }
+
+    // 退出前调用: 停止同步, 定时备份和清除任务, 停止监听并等待队列中的内容写入数据库
+    pub async fn shutdown(&self) {
+        crate::api::sync::stop();
+        backup::stop();
+        trash::stop();
+        let capture = self.capture.lock().unwrap().take();
+        if let Some(capture) = capture {
+            capture.stop().await;
         }
     }
 
-    async fn get_clipboards(&self, num: u64, type_list: Option<Vec<i32>>) -> Result<Vec<Model>, Box<dyn std::error::Error>> {
-        let db_guard = self.db.lock().await;
-        let all_entries = time_it!(async { crud::host_clipboard::get_clipboards_by_type_list(&db_guard, None, Some(num), type_list) }).await?;
+    // 同步数据端到端加密, 需要用户提供 super_key 解开 DEK
+    pub async fn start_sync(&self, super_key: &str) -> AppResult<()> {
+        Ok(crate::api::sync::start(self.db.clone(), super_key).await?)
+    }
+
+    // 停止同步并清除本地缓存的用户和 token
+    pub async fn logout(&self) -> AppResult<()> {
+        crate::api::sync::stop();
+        User::clear().await?;
+        Ok(Token::clear().await?)
+    }
+
+    // 启动时数据库出现问题并被恢复的情况, 需要通知用户
+    pub fn recovery(&self) -> Option<&RecoveryReport> {
+        self.recovery.as_ref()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn start_primary_watcher(entries: Sender<PasteboardContent>, last_hash: LastHash) -> (Option<PrimarySelection>, Option<PrimaryWatcherShutdown>) {
+        let mut watcher = match PrimaryWatcher::new() {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                warn!("Primary selection unavailable: {}", e);
+                return (None, None);
+            },
+        };
+        let primary_notifier = ClipboardHandle::new(entries)
+            .with_source(ContentSource::Primary)
+            .with_last_hash(last_hash)
+            .spawn();
+        let shutdown = watcher.add_handler(primary_notifier).get_shutdown_channel();
+        let primary = watcher.get_selection();
+        tokio::task::spawn_blocking(move || watcher.start_watch());
+        (Some(primary), Some(shutdown))
+    }
+
+    pub(crate) async fn get_clipboards(&self, num: u64, type_list: Option<Vec<i32>>, app: Option<&str>) -> AppResult<Vec<Model>> {
+        let all_entries = time_it!(async crud::host_clipboard::get_clipboards_by_type_list(&self.db, None, Some(num), type_list, app)).await?;
         Ok(all_entries)
     }
 
-    async fn search_clipboards(&self, query: &str, num: u64, type_list: Option<Vec<i32>>) -> Result<Vec<Model>, Box<dyn std::error::Error>> {
-        let db_guard = self.db.lock().await;
-        let all_entries =
-            time_it!(async { crud::host_clipboard::get_clipboards_by_type_list(&db_guard, Some(query), Some(num), type_list,) }).await?;
+    pub(crate) async fn search_clipboards(&self, query: &str, num: u64, type_list: Option<Vec<i32>>, app: Option<&str>) -> AppResult<Vec<Model>> {
+        let all_entries = time_it!(async crud::host_clipboard::get_clipboards_by_type_list(&self.db, Some(query), Some(num), type_list, app)).await?;
         Ok(all_entries)
     }
 
-    pub async fn set(&self, items: Vec<Model>) -> Result<(), String> {
+    pub(crate) async fn get_clipboard_page(
+        &self,
+        query: Option<&str>,
+        type_list: Option<Vec<i32>>,
+        app: Option<&str>,
+        cursor: Option<Cursor>,
+        limit: u64,
+    ) -> AppResult<ClipboardPage> {
+        let query = query.filter(|query| !query.is_empty());
+        Ok(crud::host_clipboard::get_clipboard_page(&self.db, query, type_list, app, cursor, limit).await?)
+    }
+
+    pub(crate) async fn get_stats(&self, days: u32) -> AppResult<ClipboardStats> {
+        Ok(stats::get_stats(&self.db, days).await?)
+    }
+
+    pub(crate) async fn get_timeline(
+        &self,
+        query: Option<&str>,
+        type_list: Option<Vec<i32>>,
+        app: Option<&str>,
+        cursor: Option<Cursor>,
+        limit: u64,
+    ) -> AppResult<Timeline> {
+        let query = query.filter(|query| !query.is_empty());
+        Ok(stats::get_timeline(&self.db, query, type_list, app, cursor, limit).await?)
+    }
+
+    pub(crate) async fn edit(&self, id: i32, edit: EntryEdit) -> AppResult<Model> {
+        revision::edit_entry(&self.db, id, edit).await
+    }
+
+    pub(crate) async fn undo_edit(&self, id: i32) -> AppResult<Model> {
+        revision::undo_edit(&self.db, id).await
+    }
+
+    pub(crate) async fn get_revisions(&self, id: i32) -> AppResult<Vec<Revision>> {
+        revision::list_revisions(&self.db, id).await
+    }
+
+    pub(crate) async fn delete(&self, ids: Vec<i32>) -> AppResult<DeleteReport> {
+        trash::delete_entries(&self.db, ids).await
+    }
+
+    pub(crate) async fn delete_by_filter(&self, filter: DeleteFilter) -> AppResult<DeleteReport> {
+        trash::delete_by_filter(&self.db, filter).await
+    }
+
+    pub(crate) async fn undo_delete(&self, batch: i64) -> AppResult<u64> {
+        trash::undo_delete(&self.db, batch).await
+    }
+
+    pub(crate) async fn get_source_apps(&self) -> AppResult<Vec<String>> {
+        Ok(crud::host_clipboard::get_source_apps(&self.db).await?)
+    }
+
+    // ids 为空时导出全部记录, format 为空时按扩展名推断
+    pub(crate) async fn export(&self, ids: Option<Vec<i32>>, format: Option<ExportFormat>, path: &Path) -> AppResult<ExportReport> {
+        let format = format
+            .or_else(|| ExportFormat::from_path(path))
+            .ok_or_else(|| AppError::InvalidInput(format!("Unknown export format: {}", path.display())))?;
+        let entries = match ids {
+            Some(ids) => crud::host_clipboard::get_clipboard_entries_by_id_list(&self.db, Some(ids)).await?,
+            None => crud::host_clipboard::get_all_clipboard_entries(&self.db).await?,
+        };
+        history::export_entries(entries, format, path).await
+    }
+
+    pub(crate) async fn import(&self, path: &Path, format: Option<ExportFormat>, policy: ConflictPolicy) -> AppResult<ImportReport> {
+        let files_dir = CONFIG.read().unwrap().files_path.join("imported");
+        history::import_entries(&self.db, path, format, policy, &files_dir).await
+    }
+
+    // 从其他剪贴板管理器迁移, path 为空时使用该应用默认的数据文件
+    pub(crate) async fn migrate(&self, source: ImportSource, path: Option<&Path>, dry_run: bool) -> AppResult<MigrationReport> {
+        let files_dir = CONFIG.read().unwrap().files_path.join("imported");
+        importers::import_history(&self.db, source, path, dry_run, &files_dir).await
+    }
+
+    pub(crate) async fn backup_now(&self) -> AppResult<BackupInfo> {
+        backup::backup_now(&self.db).await
+    }
+
+    pub(crate) async fn restore_backup(&self, backup: &str) -> AppResult<RestoreReport> {
+        backup::restore(&self.db, backup).await
+    }
+
+    // 外部工具写入一条文本记录, 暂停采集时拒绝
+    pub(crate) async fn add_text(&self, text: String, app: Option<String>) -> AppResult<Model> {
+        if !capture::is_capturing() {
+            return Err(AppError::CapturePaused);
+        }
+        let hash = hash_str(&text);
+        let mut item = PasteboardContent::new(text, ContentType::Text, hash, None);
+        item.app.app_name = app.unwrap_or_default();
+        let entry = crud::host_clipboard::add_clipboard_entry(&self.db, item).await?;
+        events::publish(ClipboardEvent::Captured(entry.clone()));
+        Ok(entry)
+    }
+
+    pub async fn set(&self, items: Vec<Model>) -> AppResult<()> {
         let first_type = items.first().map(|item| item.r#type);
 
         // 确保所有项目具有相同的类型
         if !items.iter().all(|item| Some(item.r#type) == first_type) {
-            return Err("All items must have the same type".into());
+            return Err(AppError::InvalidInput("All items must have the same type".to_string()));
+        }
+
+        // 来自 PRIMARY 的文本同时写回 PRIMARY, 方便直接中键粘贴
+        #[cfg(target_os = "linux")]
+        if let Some(primary) = self.primary.as_ref() {
+            if first_type == Some(0) && items.iter().all(|item| item.source == ContentSource::Primary.to_i32()) {
+                let text = items.iter().map(|item| item.content.as_str()).collect::<Vec<_>>().join("\n");
+                if let Err(e) = primary.set_text(text) {
+                    error!("Error setting primary selection: {}", e);
+                }
+            }
         }
 
         // Determine clipboard content based on the type
         let clipboard_content: Vec<ClipboardContent> = match first_type {
             Some(0) => items.into_iter().map(|item| ClipboardContent::Text(item.content)).collect(),
-            Some(1) | Some(2) => {
-                let paths: Vec<String> = items
-                    .into_iter()
-                    .flat_map(|item| {
-                        if item.path.starts_with('[') && item.path.ends_with(']') {
-                            // 尝试解析 JSON 数组
-                            match serde_json::from_str::<Value>(&item.path) {
-                                Ok(Value::Array(arr)) => arr.into_iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect::<Vec<String>>(),
-                                _ => vec![item.path], // 如果解析失败，将原始字符串作为单个元素
-                            }
-                        } else {
-                            // 如果不是 JSON 数组格式，就直接使用
-                            vec![item.path]
-                        }
-                    })
-                    .collect();
-                return self.ctx.set_files(paths).map_err(|e| {
-                    error!("Error setting files: {}", e);
-                    e.to_string()
-                });
+            Some(1) => {
+                let paths: Vec<String> = items.into_iter().flat_map(|item| parse_paths(item.path)).collect();
+                // Wayland 下同时提供图片内容, 可以直接粘贴到图片编辑器等程序
+                #[cfg(target_os = "linux")]
+                if self.use_wayland() {
+                    return wayland::set_image(paths).map_err(AppError::clipboard);
+                }
+                return self.set_files(paths);
             },
-            _ => return Err("Invalid type".into()),
+            Some(2) => {
+                let paths: Vec<String> = items.into_iter().flat_map(|item| parse_paths(item.path)).collect();
+                return self.set_files(paths);
+            },
+            _ => return Err(AppError::InvalidInput(format!("Invalid type {:?}", first_type))),
         };
 
         // Set clipboard content
-        self.ctx.set(clipboard_content).map_err(|e| {
-            error!("Error setting clipboard: {}", e);
-            e.to_string()
-        })
+        #[cfg(target_os = "linux")]
+        if self.use_wayland() {
+            let text = clipboard_content
+                .into_iter()
+                .filter_map(|content| match content {
+                    ClipboardContent::Text(text) => Some(text),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            return wayland::set_text(text).map_err(AppError::clipboard);
+        }
+
+        Ok(self.clipboard_ctx()?.set(clipboard_content)?)
+    }
+
+    fn set_files(&self, paths: Vec<String>) -> AppResult<()> {
+        #[cfg(target_os = "linux")]
+        if self.use_wayland() {
+            return wayland::set_files(paths).map_err(AppError::clipboard);
+        }
+
+        Ok(self.clipboard_ctx()?.set_files(paths)?)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn use_wayland(&self) -> bool {
+        self.wayland
+    }
+
+    fn clipboard_ctx(&self) -> AppResult<&ClipboardContext> {
+        self.ctx
+            .as_ref()
+            .ok_or_else(|| AppError::Unavailable("Clipboard is unavailable".to_string()))
     }
-    async fn set_clipboard(&self, clipboard: Model) -> Result<(), String> {
+    async fn set_clipboard(&self, clipboard: Model) -> AppResult<()> {
         self.set(vec![clipboard]).await
     }
 
@@ -109,23 +404,51 @@ impl ClipboardHelper {
         CONFIG.read().unwrap().user_config.clone()
     }
 
-    pub async fn set_user_config(user_config: UserConfig) -> io::Result<()> {
-        config::update(user_config).await
+    pub async fn set_user_config(user_config: UserConfig) -> AppResult<()> {
+        Ok(config::update(user_config).await?)
+    }
+}
+// 同步时的文件传输进度转发给前端
+pub async fn watch_transfer_progress(app: tauri::AppHandle) {
+    let mut receiver = events::subscribe();
+    loop {
+        match receiver.recv().await {
+            Ok(ClipboardEvent::TransferProgress(progress)) => {
+                if let Err(e) = app.emit_all("transfer-progress", progress) {
+                    error!("Failed to emit transfer progress: {}", e);
+                }
+            },
+            Ok(_) | Err(RecvError::Lagged(_)) => {},
+            Err(RecvError::Closed) => break,
+        }
     }
 }
+
+// 图片/文件记录的 path 可能是单个路径, 也可能是 JSON 数组
+pub fn parse_paths(path: String) -> Vec<String> {
+    if path.starts_with('[') && path.ends_with(']') {
+        // 尝试解析 JSON 数组
+        match serde_json::from_str::<Value>(&path) {
+            Ok(Value::Array(arr)) => arr.into_iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect::<Vec<String>>(),
+            _ => vec![path], // 如果解析失败，将原始字符串作为单个元素
+        }
+    } else {
+        // 如果不是 JSON 数组格式，就直接使用
+        vec![path]
+    }
+}
+
 #[tauri::command]
 pub async fn rs_invoke_get_clipboards(
     state: tauri::State<'_, Arc<ClipboardHelper>>,
     num: u64,
     type_list: Option<Vec<i32>>,
-) -> Result<Vec<Model>, String> {
-    match state.get_clipboards(num, type_list).await {
-        Ok(clipboards) => Ok(clipboards),
-        Err(e) => {
-            error!("rs_invoke_get_clipboards err: {:?}", e);
-            Err(format!("Failed to get clipboards: {}", e))
-        },
-    }
+    app: Option<String>,
+) -> AppResult<Vec<Model>> {
+    state.get_clipboards(num, type_list, app.as_deref()).await.map_err(|e| {
+        error!("rs_invoke_get_clipboards err: {}", e);
+        e
+    })
 }
 
 #[tauri::command]
@@ -134,52 +457,273 @@ pub async fn rs_invoke_search_clipboards(
     query: &str,
     num: u64,
     type_list: Option<Vec<i32>>,
-) -> Result<Vec<Model>, String> {
-    match state.search_clipboards(query, num, type_list).await {
-        Ok(clipboards) => Ok(clipboards),
-        Err(e) => {
-            error!("rs_invoke_search_clipboards err: {:?}", e);
-            Err(format!("Failed to search clipboards: {}", e))
-        },
-    }
+    app: Option<String>,
+) -> AppResult<Vec<Model>> {
+    state.search_clipboards(query, num, type_list, app.as_deref()).await.map_err(|e| {
+        error!("rs_invoke_search_clipboards err: {}", e);
+        e
+    })
 }
 
 #[tauri::command]
-pub async fn rs_invoke_set_clipboards(state: tauri::State<'_, Arc<ClipboardHelper>>, item: Model) -> Result<bool, String> {
-    match state.set_clipboard(item).await {
-        Ok(_) => Ok(true),
-        Err(e) => {
-            error!("rs_invoke_set_clipboards err: {:?}", e);
-            Err(format!("Failed to set clipboard: {}", e))
-        },
-    }
+pub async fn rs_invoke_get_clipboard_page(
+    state: tauri::State<'_, Arc<ClipboardHelper>>,
+    query: Option<String>,
+    type_list: Option<Vec<i32>>,
+    app: Option<String>,
+    cursor: Option<Cursor>,
+    limit: u64,
+) -> AppResult<ClipboardPage> {
+    state
+        .get_clipboard_page(query.as_deref(), type_list, app.as_deref(), cursor, limit)
+        .await
+        .map_err(|e| {
+            error!("rs_invoke_get_clipboard_page err: {}", e);
+            e
+        })
 }
 
+// days 为按天统计的天数, 默认最近 30 天
 #[tauri::command]
-pub async fn rs_invoke_get_user_config(_: tauri::State<'_, Arc<ClipboardHelper>>) -> Result<UserConfig, String> {
-    match ClipboardHelper::get_user_config().await {
-        config => Ok(config),
-    }
+pub async fn rs_invoke_get_stats(state: tauri::State<'_, Arc<ClipboardHelper>>, days: Option<u32>) -> AppResult<ClipboardStats> {
+    state.get_stats(days.unwrap_or(30)).await.map_err(|e| {
+        error!("rs_invoke_get_stats err: {}", e);
+        e
+    })
 }
 
 #[tauri::command]
-pub async fn rs_invoke_set_user_config(_: tauri::State<'_, Arc<ClipboardHelper>>, user_config: UserConfig) -> Result<bool, String> {
-    match ClipboardHelper::set_user_config(user_config).await {
-        Ok(()) => Ok(true),
-        Err(e) => {
-            error!("rs_invoke_set_config err: {:?}", e);
-            Err(format!("Failed to set config: {}", e))
-        },
-    }
+pub async fn rs_invoke_get_timeline(
+    state: tauri::State<'_, Arc<ClipboardHelper>>,
+    query: Option<String>,
+    type_list: Option<Vec<i32>>,
+    app: Option<String>,
+    cursor: Option<Cursor>,
+    limit: u64,
+) -> AppResult<Timeline> {
+    state
+        .get_timeline(query.as_deref(), type_list, app.as_deref(), cursor, limit)
+        .await
+        .map_err(|e| {
+            error!("rs_invoke_get_timeline err: {}", e);
+            e
+        })
 }
 
+// 文本记录可以修改内容, 任意记录可以修改标题和备注
 #[tauri::command]
-pub fn rs_invoke_open_settings(window: tauri::Window) -> Result<(), String> {
-    if let Some(settings_window) = window.get_window("settings") {
-        settings_window.show().map_err(|e| e.to_string())?;
-        settings_window.set_focus().map_err(|e| e.to_string())?;
-    } else {
-        return Err("Settings window not found".into());
-    }
+pub async fn rs_invoke_edit_clipboard(state: tauri::State<'_, Arc<ClipboardHelper>>, id: i32, edit: EntryEdit) -> AppResult<Model> {
+    state.edit(id, edit).await.map_err(|e| {
+        error!("rs_invoke_edit_clipboard err: {}", e);
+        e
+    })
+}
+
+#[tauri::command]
+pub async fn rs_invoke_undo_clipboard_edit(state: tauri::State<'_, Arc<ClipboardHelper>>, id: i32) -> AppResult<Model> {
+    state.undo_edit(id).await.map_err(|e| {
+        error!("rs_invoke_undo_clipboard_edit err: {}", e);
+        e
+    })
+}
+
+#[tauri::command]
+pub async fn rs_invoke_get_clipboard_revisions(state: tauri::State<'_, Arc<ClipboardHelper>>, id: i32) -> AppResult<Vec<Revision>> {
+    state.get_revisions(id).await.map_err(|e| {
+        error!("rs_invoke_get_clipboard_revisions err: {}", e);
+        e
+    })
+}
+
+// 删除后可以在 undo_until 之前用返回的 batch 撤销
+#[tauri::command]
+pub async fn rs_invoke_delete_clipboard(state: tauri::State<'_, Arc<ClipboardHelper>>, id: i32) -> AppResult<DeleteReport> {
+    state.delete(vec![id]).await.map_err(|e| {
+        error!("rs_invoke_delete_clipboard err: {}", e);
+        e
+    })
+}
+
+#[tauri::command]
+pub async fn rs_invoke_delete_clipboards(state: tauri::State<'_, Arc<ClipboardHelper>>, ids: Vec<i32>) -> AppResult<DeleteReport> {
+    state.delete(ids).await.map_err(|e| {
+        error!("rs_invoke_delete_clipboards err: {}", e);
+        e
+    })
+}
+
+#[tauri::command]
+pub async fn rs_invoke_delete_clipboards_by_filter(state: tauri::State<'_, Arc<ClipboardHelper>>, filter: DeleteFilter) -> AppResult<DeleteReport> {
+    state.delete_by_filter(filter).await.map_err(|e| {
+        error!("rs_invoke_delete_clipboards_by_filter err: {}", e);
+        e
+    })
+}
+
+// 清空历史记录, 默认保留置顶的记录
+#[tauri::command]
+pub async fn rs_invoke_clear_clipboards(state: tauri::State<'_, Arc<ClipboardHelper>>, include_pinned: Option<bool>) -> AppResult<DeleteReport> {
+    let filter = DeleteFilter {
+        include_pinned: include_pinned.unwrap_or(false),
+        ..Default::default()
+    };
+    state.delete_by_filter(filter).await.map_err(|e| {
+        error!("rs_invoke_clear_clipboards err: {}", e);
+        e
+    })
+}
+
+#[tauri::command]
+pub async fn rs_invoke_undo_delete(state: tauri::State<'_, Arc<ClipboardHelper>>, batch: i64) -> AppResult<u64> {
+    state.undo_delete(batch).await.map_err(|e| {
+        error!("rs_invoke_undo_delete err: {}", e);
+        e
+    })
+}
+
+#[tauri::command]
+pub async fn rs_invoke_get_source_apps(state: tauri::State<'_, Arc<ClipboardHelper>>) -> AppResult<Vec<String>> {
+    state.get_source_apps().await.map_err(|e| {
+        error!("rs_invoke_get_source_apps err: {}", e);
+        e
+    })
+}
+
+#[tauri::command]
+pub async fn rs_invoke_export_clipboards(
+    state: tauri::State<'_, Arc<ClipboardHelper>>,
+    path: String,
+    format: Option<ExportFormat>,
+    ids: Option<Vec<i32>>,
+) -> AppResult<ExportReport> {
+    state.export(ids, format, Path::new(&path)).await.map_err(|e| {
+        error!("rs_invoke_export_clipboards err: {}", e);
+        e
+    })
+}
+
+#[tauri::command]
+pub async fn rs_invoke_import_clipboards(
+    state: tauri::State<'_, Arc<ClipboardHelper>>,
+    path: String,
+    format: Option<ExportFormat>,
+    on_conflict: Option<ConflictPolicy>,
+) -> AppResult<ImportReport> {
+    state
+        .import(Path::new(&path), format, on_conflict.unwrap_or_default())
+        .await
+        .map_err(|e| {
+            error!("rs_invoke_import_clipboards err: {}", e);
+            e
+        })
+}
+
+#[tauri::command]
+pub async fn rs_invoke_import_from_app(
+    state: tauri::State<'_, Arc<ClipboardHelper>>,
+    source: ImportSource,
+    path: Option<String>,
+    dry_run: bool,
+) -> AppResult<MigrationReport> {
+    state.migrate(source, path.as_deref().map(Path::new), dry_run).await.map_err(|e| {
+        error!("rs_invoke_import_from_app err: {}", e);
+        e
+    })
+}
+
+#[tauri::command]
+pub async fn rs_invoke_backup_now(state: tauri::State<'_, Arc<ClipboardHelper>>) -> AppResult<BackupInfo> {
+    state.backup_now().await.map_err(|e| {
+        error!("rs_invoke_backup_now err: {}", e);
+        e
+    })
+}
+
+#[tauri::command]
+pub async fn rs_invoke_list_backups() -> AppResult<Vec<BackupInfo>> {
+    backup::list().map_err(|e| {
+        error!("rs_invoke_list_backups err: {}", e);
+        e
+    })
+}
+
+#[tauri::command]
+pub async fn rs_invoke_restore_backup(state: tauri::State<'_, Arc<ClipboardHelper>>, backup: String) -> AppResult<RestoreReport> {
+    state.restore_backup(&backup).await.map_err(|e| {
+        error!("rs_invoke_restore_backup err: {}", e);
+        e
+    })
+}
+
+#[tauri::command]
+pub async fn rs_invoke_get_capture_metrics(_: tauri::State<'_, Arc<ClipboardHelper>>) -> AppResult<CaptureMetricsSnapshot> {
+    Ok(CAPTURE_METRICS.snapshot())
+}
+
+#[tauri::command]
+pub async fn rs_invoke_get_capture_state() -> AppResult<CaptureState> {
+    Ok(capture::get_capture_state())
+}
+
+// minutes 为空时一直暂停, 直到手动恢复. 定时暂停为 1 分钟到 24 小时
+#[tauri::command]
+pub async fn rs_invoke_pause_capture(minutes: Option<i64>) -> AppResult<CaptureState> {
+    capture::pause(minutes).map_err(|e| {
+        error!("rs_invoke_pause_capture err: {}", e);
+        e
+    })
+}
+
+#[tauri::command]
+pub async fn rs_invoke_resume_capture() -> AppResult<CaptureState> {
+    Ok(capture::resume())
+}
+
+#[tauri::command]
+pub async fn rs_invoke_start_sync(state: tauri::State<'_, Arc<ClipboardHelper>>, super_key: String) -> AppResult<()> {
+    state.start_sync(&super_key).await.map_err(|e| {
+        error!("rs_invoke_start_sync err: {}", e);
+        e
+    })
+}
+
+#[tauri::command]
+pub async fn rs_invoke_logout(state: tauri::State<'_, Arc<ClipboardHelper>>) -> AppResult<()> {
+    state.logout().await.map_err(|e| {
+        error!("rs_invoke_logout err: {}", e);
+        e
+    })
+}
+
+#[tauri::command]
+pub async fn rs_invoke_set_clipboards(state: tauri::State<'_, Arc<ClipboardHelper>>, item: Model) -> AppResult<bool> {
+    state.set_clipboard(item).await.map_err(|e| {
+        error!("rs_invoke_set_clipboards err: {}", e);
+        e
+    })?;
+    Ok(true)
+}
+
+#[tauri::command]
+pub async fn rs_invoke_get_user_config(_: tauri::State<'_, Arc<ClipboardHelper>>) -> AppResult<UserConfig> {
+    Ok(ClipboardHelper::get_user_config().await)
+}
+
+#[tauri::command]
+pub async fn rs_invoke_set_user_config(_: tauri::State<'_, Arc<ClipboardHelper>>, user_config: UserConfig) -> AppResult<bool> {
+    ClipboardHelper::set_user_config(user_config).await.map_err(|e| {
+        error!("rs_invoke_set_config err: {}", e);
+        e
+    })?;
+    Ok(true)
+}
+
+#[tauri::command]
+pub fn rs_invoke_open_settings(window: tauri::Window) -> AppResult<()> {
+    let settings_window = window
+        .get_window("settings")
+        .ok_or_else(|| AppError::NotFound("settings window".to_string()))?;
+    settings_window.show()?;
+    settings_window.set_focus()?;
     Ok(())
 }
@@ -0,0 +1,137 @@
+// 采集状态: 暂停期间所有剪贴板变化都被忽略, 不会写入数据库和文件
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::events::{self, ClipboardEvent};
+use crate::error::{AppError, AppResult};
+use crate::utils::time::get_current_timestamp;
+
+static CAPTURE_STATE: Mutex<CaptureState> = Mutex::new(CaptureState::Active);
+
+// 定时暂停最长 24 小时, 更长时间应使用不限时的暂停
+pub const MAX_PAUSE_MINUTES: i64 = 24 * 60;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum CaptureState {
+    Active,
+    Paused,
+    PausedUntil { until: i64 }, // 到期时间戳, 到期后自动恢复
+}
+
+impl CaptureState {
+    // 已到期的 PausedUntil 视为 Active
+    fn at(self, now: i64) -> Self {
+        match self {
+            CaptureState::PausedUntil { until } if until <= now => CaptureState::Active,
+            state => state,
+        }
+    }
+
+    fn toggled(self) -> Self {
+        match self {
+            CaptureState::Active => CaptureState::Paused,
+            CaptureState::Paused | CaptureState::PausedUntil { .. } => CaptureState::Active,
+        }
+    }
+}
+
+// 定时暂停到期时在这里切换为 Active, 并发布状态变化
+pub fn get_capture_state() -> CaptureState {
+    let (previous, current) = {
+        let mut state = CAPTURE_STATE.lock().unwrap();
+        let previous = *state;
+        *state = state.at(get_current_timestamp());
+        (previous, *state)
+    };
+    if previous != current {
+        events::publish(ClipboardEvent::CaptureStateChanged(current));
+    }
+    current
+}
+
+pub fn is_capturing() -> bool {
+    get_capture_state() == CaptureState::Active
+}
+
+// minutes 为 None 时一直暂停, 直到手动恢复. 定时暂停到期后发布恢复事件, 订阅者不需要自己计时
+pub fn pause(minutes: Option<i64>) -> AppResult<CaptureState> {
+    let state = match minutes {
+        Some(minutes) => CaptureState::PausedUntil {
+            until: get_current_timestamp() + validate_minutes(minutes)? * 60,
+        },
+        None => CaptureState::Paused,
+    };
+    if let (CaptureState::PausedUntil { until }, Ok(runtime)) = (state, tokio::runtime::Handle::try_current()) {
+        runtime.spawn(async move {
+            let secs = (until - get_current_timestamp()).max(0) as u64;
+            tokio::time::sleep(Duration::from_secs(secs)).await;
+            // 期间状态可能已被修改, 只有仍是这次暂停时才会切换
+            get_capture_state();
+        });
+    }
+    Ok(set_capture_state(state))
+}
+
+fn validate_minutes(minutes: i64) -> AppResult<i64> {
+    if !(1..=MAX_PAUSE_MINUTES).contains(&minutes) {
+        return Err(AppError::InvalidInput(format!(
+            "Pause minutes must be between 1 and {}, got {}",
+            MAX_PAUSE_MINUTES, minutes
+        )));
+    }
+    Ok(minutes)
+}
+
+pub fn resume() -> CaptureState {
+    set_capture_state(CaptureState::Active)
+}
+
+pub fn toggle() -> CaptureState {
+    let state = get_capture_state().toggled();
+    set_capture_state(state)
+}
+
+fn set_capture_state(state: CaptureState) -> CaptureState {
+    *CAPTURE_STATE.lock().unwrap() = state;
+    events::publish(ClipboardEvent::CaptureStateChanged(state));
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paused_until_expires() {
+        let state = CaptureState::PausedUntil { until: 100 };
+        assert_eq!(state.at(99), state);
+        assert_eq!(state.at(100), CaptureState::Active);
+        assert_eq!(CaptureState::Paused.at(i64::MAX), CaptureState::Paused);
+    }
+
+    #[test]
+    fn test_toggle() {
+        assert_eq!(CaptureState::Active.toggled(), CaptureState::Paused);
+        assert_eq!(CaptureState::Paused.toggled(), CaptureState::Active);
+        assert_eq!(CaptureState::PausedUntil { until: 100 }.toggled(), CaptureState::Active);
+    }
+
+    #[test]
+    fn test_validate_minutes() {
+        assert_eq!(validate_minutes(1).unwrap(), 1);
+        assert_eq!(validate_minutes(MAX_PAUSE_MINUTES).unwrap(), MAX_PAUSE_MINUTES);
+        for minutes in [0, -1, MAX_PAUSE_MINUTES + 1, i64::MAX, i64::MIN] {
+            assert!(matches!(validate_minutes(minutes), Err(AppError::InvalidInput(_))));
+        }
+    }
+
+    #[test]
+    fn test_serialize_state() {
+        let json = serde_json::to_string(&CaptureState::PausedUntil { until: 100 }).unwrap();
+        assert_eq!(json, r#"{"state":"paused_until","until":100}"#);
+        assert_eq!(serde_json::to_string(&CaptureState::Active).unwrap(), r#"{"state":"active"}"#);
+    }
+}
@@ -1,79 +1,308 @@
-use std::sync::mpsc::{self, Receiver, Sender};
-use std::sync::Arc;
-use std::thread::JoinHandle;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+#[cfg(target_os = "linux")]
+use clipboard_rs::{common::RustImage, RustImageData};
 use clipboard_rs::{Clipboard, ClipboardContext, ClipboardHandler};
-use log::{debug, error};
+use log::{debug, error, warn};
 use sea_orm::DatabaseConnection;
-use tokio::runtime::Runtime;
-use tokio::sync::Mutex;
+use serde::Serialize;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::task::JoinHandle;
 
-use crate::core::pasteboard::PasteboardContent;
-use crate::db::crud::host_clipboard::add_clipboard_entry;
+use crate::core::capture;
+use crate::core::events::{self, ClipboardEvent};
+#[cfg(target_os = "linux")]
+use crate::core::pasteboard::SourceApp;
+use crate::core::pasteboard::{ContentSource, ContentType, PasteboardContent};
+#[cfg(target_os = "linux")]
+use crate::core::primary::{self, PrimaryHandler, PrimarySelection};
+#[cfg(target_os = "linux")]
+use crate::core::wayland::{WaylandClipboardHandler, WaylandContent};
+use crate::db::crud::host_clipboard::add_clipboard_entries;
 use crate::time_it;
+use crate::utils::config::CONFIG;
+#[cfg(target_os = "linux")]
+use crate::utils::x11_window::SourceAppResolver;
+
+pub static CAPTURE_METRICS: CaptureMetrics = CaptureMetrics::new();
+
+// 采集计数, 进程启动后累计
+pub struct CaptureMetrics {
+    notified: AtomicU64,  // 收到的变化通知
+    coalesced: AtomicU64, // 合并掉的通知
+    dropped: AtomicU64,   // 队列已满被丢弃的事件或内容
+    captured: AtomicU64,  // 写入数据库的条数
+    batches: AtomicU64,   // 写入事务数
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct CaptureMetricsSnapshot {
+    pub notified: u64,
+    pub coalesced: u64,
+    pub dropped: u64,
+    pub captured: u64,
+    pub batches: u64,
+}
+
+impl CaptureMetrics {
+    const fn new() -> Self {
+        Self {
+            notified: AtomicU64::new(0),
+            coalesced: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            captured: AtomicU64::new(0),
+            batches: AtomicU64::new(0),
+        }
+    }
+
+    pub fn snapshot(&self) -> CaptureMetricsSnapshot {
+        CaptureMetricsSnapshot {
+            notified: self.notified.load(Ordering::Relaxed),
+            coalesced: self.coalesced.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            captured: self.captured.load(Ordering::Relaxed),
+            batches: self.batches.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// 监听线程只转发事件, 读取剪贴板在采集任务中完成
+pub enum CaptureEvent {
+    Clipboard, // 需要重新读取剪贴板
+    #[cfg(target_os = "linux")]
+    Primary(String),
+    #[cfg(target_os = "linux")]
+    Wayland(WaylandContent),
+}
+
+#[derive(Clone)]
+pub struct ClipboardNotifier {
+    sender: Sender<CaptureEvent>,
+}
+
+impl ClipboardNotifier {
+    fn notify(&self, event: CaptureEvent) {
+        CAPTURE_METRICS.notified.fetch_add(1, Ordering::Relaxed);
+        if !capture::is_capturing() {
+            return;
+        }
+        match self.sender.try_send(event) {
+            Ok(()) => {},
+            // 队列中已有待读取的事件, 本次通知等同于被合并
+            Err(TrySendError::Full(CaptureEvent::Clipboard)) => {
+                CAPTURE_METRICS.coalesced.fetch_add(1, Ordering::Relaxed);
+            },
+            Err(TrySendError::Full(_)) => {
+                CAPTURE_METRICS.dropped.fetch_add(1, Ordering::Relaxed);
+            },
+            Err(TrySendError::Closed(_)) => error!("clipboard capture task exited"),
+        }
+    }
+}
+
+impl ClipboardHandler for ClipboardNotifier {
+    fn on_clipboard_change(&mut self) {
+        self.notify(CaptureEvent::Clipboard);
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl PrimaryHandler for ClipboardNotifier {
+    fn on_primary_change(&mut self, text: String) {
+        self.notify(CaptureEvent::Primary(text));
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl WaylandClipboardHandler for ClipboardNotifier {
+    fn on_wayland_change(&mut self, content: WaylandContent) {
+        self.notify(CaptureEvent::Wayland(content));
+    }
+}
+
+// 最近一次采集内容的 hash. CLIPBOARD 和 PRIMARY 的采集任务共用, 先选中再复制同一段文本时只记录一次
+#[derive(Clone, Default)]
+pub struct LastHash(Arc<Mutex<String>>);
+
+impl LastHash {
+    pub fn is(&self, hash: &str) -> bool {
+        *self.0.lock().unwrap() == hash
+    }
+
+    pub fn set(&self, hash: &str) {
+        *self.0.lock().unwrap() = hash.to_string();
+    }
+}
 
 pub struct ClipboardHandle {
-    #[allow(dead_code)]
-    db: Arc<Mutex<DatabaseConnection>>,
-    ctx: ClipboardContext,
-    pub(crate) last_hash: String,
+    // 纯 Wayland 下没有 X11, 创建会失败, 此时由 wayland 模块负责读取内容
+    ctx: Option<ClipboardContext>,
+    pub(crate) last_hash: LastHash,
+    source: ContentSource,
+    // CLIPBOARD -> PRIMARY 同步时使用
+    #[cfg(target_os = "linux")]
+    primary: Option<PrimarySelection>,
+    // 查询来源应用, 纯 Wayland 下为 None
+    #[cfg(target_os = "linux")]
+    source_resolver: Option<SourceAppResolver>,
     sender: Sender<PasteboardContent>,
-    #[allow(dead_code)]
-    receiver_handle: JoinHandle<()>,
-    #[allow(dead_code)]
-    runtime: Arc<Runtime>,
 }
 
-impl ClipboardHandle {
-    pub fn new(db: Arc<Mutex<DatabaseConnection>>) -> Self {
-        let ctx = ClipboardContext::new().unwrap();
-        let (sender, receiver) = mpsc::channel();
-        let runtime = Arc::new(Runtime::new().unwrap());
+// 所有采集来源共用一个写入任务, 积压的内容合并到一个事务中写入.
+// 所有 sender 释放后, 写完队列中剩余的内容再结束
+pub fn spawn_writer(db: DatabaseConnection) -> (Sender<PasteboardContent>, JoinHandle<()>) {
+    let queue_size = CONFIG.read().unwrap().user_config.capture_config.queue_size;
+    let (sender, receiver) = mpsc::channel(queue_size.max(1));
+    (sender, tokio::spawn(write_entries(receiver, db)))
+}
 
-        let db_clone = db.clone();
-        let runtime_clone = runtime.clone();
-        let receiver_handle = std::thread::spawn(move || {
-            Self::process_receiver(receiver, db_clone, runtime_clone);
-        });
+async fn write_entries(mut receiver: Receiver<PasteboardContent>, db: DatabaseConnection) {
+    while let Some(content) = receiver.recv().await {
+        let batch_size = CONFIG.read().unwrap().user_config.capture_config.batch_size.max(1);
+        let mut batch = vec![content];
+        while batch.len() < batch_size {
+            match receiver.try_recv() {
+                Ok(content) => batch.push(content),
+                Err(_) => break,
+            }
+        }
+        let count = batch.len() as u64;
+        match time_it!(async add_clipboard_entries(&db, batch)).await {
+            Ok(entries) => {
+                CAPTURE_METRICS.captured.fetch_add(count, Ordering::Relaxed);
+                CAPTURE_METRICS.batches.fetch_add(1, Ordering::Relaxed);
+                debug!("capture metrics: {:?}", CAPTURE_METRICS.snapshot());
+                for entry in entries {
+                    events::publish(ClipboardEvent::Captured(entry));
+                }
+            },
+            Err(e) => error!("Failed to add clipboard entries: {}", e),
+        }
+    }
+}
 
+impl ClipboardHandle {
+    pub fn new(sender: Sender<PasteboardContent>) -> Self {
+        let ctx = ClipboardContext::new()
+            .map_err(|e| error!("Failed to create clipboard context: {}", e))
+            .ok();
         ClipboardHandle {
             ctx,
-            db,
             sender,
-            last_hash: "".to_string(),
-            receiver_handle,
-            runtime,
+            last_hash: LastHash::default(),
+            source: ContentSource::Clipboard,
+            #[cfg(target_os = "linux")]
+            primary: None,
+            #[cfg(target_os = "linux")]
+            source_resolver: SourceAppResolver::new()
+                .map_err(|e| debug!("Source app resolver unavailable: {}", e))
+                .ok(),
         }
     }
-    fn process_receiver(receiver: Receiver<PasteboardContent>, db: Arc<Mutex<DatabaseConnection>>, runtime: Arc<Runtime>) {
-        while let Ok(content) = receiver.recv() {
-            // debug!("Received clipboard content: {:?}", content);
-            runtime.block_on(async {
-                Self::add_clipboard_entry(&db, content).await;
-            });
+
+    pub fn with_source(mut self, source: ContentSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn with_last_hash(mut self, last_hash: LastHash) -> Self {
+        self.last_hash = last_hash;
+        self
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn set_primary_selection(&mut self, primary: Option<PrimarySelection>) {
+        self.primary = primary;
+    }
+
+    // 启动采集任务, 返回交给 watcher 的 handler. 所有 handler 释放后任务结束
+    pub fn spawn(self) -> ClipboardNotifier {
+        let queue_size = CONFIG.read().unwrap().user_config.capture_config.queue_size;
+        let (sender, receiver) = mpsc::channel(queue_size.max(1));
+        tokio::spawn(self.run(receiver));
+        ClipboardNotifier { sender }
+    }
+
+    async fn run(mut self, mut receiver: Receiver<CaptureEvent>) {
+        while let Some(mut event) = receiver.recv().await {
+            let capture_config = CONFIG.read().unwrap().user_config.capture_config.clone();
+            let debounce = Duration::from_millis(capture_config.debounce_ms);
+            let deadline = Instant::now() + Duration::from_millis(capture_config.max_wait_ms);
+
+            // debounce: 静默 debounce_ms 之后才读取, 期间只保留最新的事件
+            loop {
+                let now = Instant::now();
+                if now >= deadline {
+                    break;
+                }
+                match tokio::time::timeout(debounce.min(deadline - now), receiver.recv()).await {
+                    Ok(Some(next)) => {
+                        CAPTURE_METRICS.coalesced.fetch_add(1, Ordering::Relaxed);
+                        event = next;
+                    },
+                    Ok(None) | Err(_) => break,
+                }
+            }
+            // 读取剪贴板是阻塞调用
+            self = match tokio::task::spawn_blocking(move || {
+                self.handle_event(event);
+                self
+            })
+            .await
+            {
+                Ok(handle) => handle,
+                Err(e) => {
+                    error!("clipboard capture task failed: {}", e);
+                    return;
+                },
+            };
         }
     }
 
-    async fn add_clipboard_entry(db: &Arc<Mutex<DatabaseConnection>>, content: PasteboardContent) {
-        let db_guard = db.lock().await;
-        time_it!(async add_clipboard_entry(&db_guard, content)).await.unwrap();
+    fn handle_event(&mut self, event: CaptureEvent) {
+        // debounce 期间可能已被暂停
+        if !capture::is_capturing() {
+            debug!("capture paused, ignore clipboard change");
+            return;
+        }
+        match event {
+            CaptureEvent::Clipboard => self.read_clipboard(),
+            #[cfg(target_os = "linux")]
+            CaptureEvent::Primary(text) => self.handle_primary(text),
+            #[cfg(target_os = "linux")]
+            CaptureEvent::Wayland(content) => self.handle_wayland(content),
+        }
     }
-}
 
-impl ClipboardHandler for ClipboardHandle {
-    fn on_clipboard_change(&mut self) {
-        let mut content = None;
+    fn push(&self, mut content: PasteboardContent) {
+        content.source = self.source;
+        #[cfg(target_os = "linux")]
+        {
+            content.app = self.source_app();
+        }
+        match self.sender.try_send(content) {
+            Ok(()) => {},
+            Err(TrySendError::Full(_)) => {
+                CAPTURE_METRICS.dropped.fetch_add(1, Ordering::Relaxed);
+                warn!("clipboard write queue is full, entry dropped");
+            },
+            Err(TrySendError::Closed(_)) => error!("clipboard writer task exited"),
+        }
+    }
+
+    fn read_clipboard(&mut self) {
+        let Some(ctx) = self.ctx.as_ref() else {
+            return;
+        };
 
-        let mut have_files = false;
         // if let Ok(img) = self.ctx.get_image() {
         //     content = self.new_img_content(&img);
         // }
-        match self.ctx.get_files() {
-            Ok(file_urls) if !file_urls.is_empty() => {
-                have_files = true;
-                content = self.new_file_content(file_urls);
-            },
-            Ok(_) => {},
+        let file_urls = match ctx.get_files() {
+            Ok(file_urls) => file_urls,
             Err(e) => {
                 #[cfg(target_os = "windows")]
                 {}
@@ -85,24 +314,93 @@ impl ClipboardHandler for ClipboardHandle {
                         error!("Error getting files from clipboard: {}", err_text);
                     }
                 }
+                vec![]
             },
         };
 
-        if !have_files && content.is_none() {
-            if let Ok(img) = self.ctx.get_image() {
-                content = self.new_img_content(&img);
-            } else if let Ok(text) = self.ctx.get_text() {
-                content = self.new_text_content(text);
+        let content = if !file_urls.is_empty() {
+            self.new_file_content(file_urls)
+        } else if let Ok(img) = ctx.get_image() {
+            self.new_img_content(&img)
+        } else if let Ok(text) = ctx.get_text() {
+            self.new_text_content(text)
+        } else {
+            None
+        };
+        // 将content push
+        if let Some(content) = content {
+            #[cfg(target_os = "linux")]
+            self.sync_to_primary(&content);
+            self.push(content);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl ClipboardHandle {
+    fn source_app(&self) -> SourceApp {
+        let Some(resolver) = self.source_resolver.as_ref() else {
+            return SourceApp::default();
+        };
+        let selection: &[u8] = match self.source {
+            ContentSource::Clipboard => b"CLIPBOARD",
+            ContentSource::Primary => b"PRIMARY",
+        };
+        resolver.resolve(selection).unwrap_or_else(|e| {
+            debug!("Failed to resolve source app: {}", e);
+            SourceApp::default()
+        })
+    }
+
+    fn sync_to_primary(&self, content: &PasteboardContent) {
+        let Some(primary) = self.primary.as_ref() else {
+            return;
+        };
+        if content.r#type != ContentType::Text || !CONFIG.read().unwrap().user_config.primary_config.sync.to_primary() {
+            return;
+        }
+        if let Err(e) = primary.set_text(content.text_content.clone()) {
+            error!("Failed to sync clipboard to primary: {}", e);
+        }
+    }
+
+    fn handle_primary(&mut self, text: String) {
+        let primary_config = CONFIG.read().unwrap().user_config.primary_config.clone();
+        if primary_config.sync.to_clipboard() {
+            if let Some(ctx) = self.ctx.as_ref() {
+                if let Err(e) = ctx.set_text(text.clone()) {
+                    error!("Failed to sync primary to clipboard: {}", e);
+                }
             }
         }
-        // 将content push
+        if !primary_config.capture {
+            return;
+        }
+        if let Some(hash) = primary::filter_selection(&text, &self.last_hash) {
+            self.last_hash.set(&hash);
+            self.push(PasteboardContent::new(text, ContentType::Text, hash, None));
+        }
+    }
+
+    fn handle_wayland(&mut self, content: WaylandContent) {
+        let content = match content {
+            WaylandContent::Files(file_urls) => self.new_file_content(file_urls),
+            WaylandContent::Image(bytes) => match RustImageData::from_bytes(&bytes) {
+                Ok(img) => self.new_img_content(&img),
+                Err(e) => {
+                    error!("Failed to decode wayland clipboard image: {}", e);
+                    None
+                },
+            },
+            WaylandContent::Text(text) => self.new_text_content(text),
+        };
         if let Some(content) = content {
-            let _ = self.sender.send(content);
+            self.push(content);
         }
     }
 }
 
-pub(crate) fn string_is_large(input: &String) -> bool {
+pub(crate) fn string_is_large(input: &str) -> bool {
     const LARGE_SIZE: usize = 250000;
     let input_len = input.len();
     debug!("get_sting_length: {}", input_len);
@@ -204,3 +502,45 @@ pub(crate) fn string_is_large(input: &String) -> bool {
 //         }
 //     }
 // }
+
+#[cfg(test)]
+mod notifier_tests {
+    use super::*;
+    use crate::db::crud::host_clipboard::count_clipboard_entries;
+    use crate::db::testing::temp_db;
+    use crate::utils::hash::hash_str;
+
+    #[test]
+    fn test_notifier_coalesces_when_queue_full() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let notifier = ClipboardNotifier { sender };
+        let before = CAPTURE_METRICS.snapshot();
+
+        for _ in 0..3 {
+            notifier.notify(CaptureEvent::Clipboard);
+        }
+
+        let after = CAPTURE_METRICS.snapshot();
+        assert!(after.notified - before.notified >= 3);
+        assert!(after.coalesced - before.coalesced >= 2);
+        assert!(matches!(receiver.try_recv(), Ok(CaptureEvent::Clipboard)));
+        assert!(receiver.try_recv().is_err());
+    }
+
+    // 所有 sender 释放后, 写入任务先写完队列中的内容再结束
+    #[tokio::test]
+    async fn test_writer_drains_queue_on_shutdown() {
+        let (db, _dir) = temp_db("writer").await;
+
+        let (sender, writer) = spawn_writer(db.clone());
+        for i in 0..5 {
+            let text = format!("pending {}", i);
+            sender
+                .try_send(PasteboardContent::new(text.clone(), ContentType::Text, hash_str(&text), None))
+                .unwrap();
+        }
+        drop(sender);
+        writer.await.unwrap();
+        assert_eq!(count_clipboard_entries(&db).await.unwrap(), 5);
+    }
+}
@@ -0,0 +1,41 @@
+// 进程内事件总线, 托盘和 IPC 订阅者通过它获取新记录, 采集状态和同步传输进度的变化
+use lazy_static::lazy_static;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::api::models::file::TransferProgress;
+use crate::core::capture::CaptureState;
+use crate::db::entities::host_clipboard::Model;
+
+const EVENT_CAPACITY: usize = 256;
+
+lazy_static! {
+    static ref EVENTS: broadcast::Sender<ClipboardEvent> = broadcast::channel(EVENT_CAPACITY).0;
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum ClipboardEvent {
+    Captured(Model),
+    CaptureStateChanged(CaptureState),
+    TransferProgress(TransferProgress),
+}
+
+impl ClipboardEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ClipboardEvent::Captured(_) => "captured",
+            ClipboardEvent::CaptureStateChanged(_) => "capture_state_changed",
+            ClipboardEvent::TransferProgress(_) => "transfer_progress",
+        }
+    }
+}
+
+// 没有订阅者时直接丢弃
+pub fn publish(event: ClipboardEvent) {
+    let _ = EVENTS.send(event);
+}
+
+pub fn subscribe() -> broadcast::Receiver<ClipboardEvent> {
+    EVENTS.subscribe()
+}
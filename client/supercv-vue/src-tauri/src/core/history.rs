@@ -0,0 +1,528 @@
+// 剪贴板历史的导入导出: JSON, JSON Lines, CSV, 以及带上图片和文件的 .supercv 压缩包
+//
+// 压缩包结构:
+//   manifest.json          { version, exported_at, platform, entries: [{ ...记录, files: [...] }] }
+//   files/<i>/<j>/<name>   第 i 条记录引用的第 j 个文件
+//
+// 导入时按 hash 合并, 压缩包中的文件解压到本机的文件目录并改写记录的 path,
+// 因此可以恢复其他系统上导出的压缩包
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Seek, Write};
+use std::path::Path;
+
+use log::warn;
+use sea_orm::{DatabaseConnection, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use zip::result::ZipError;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::clipboard_helper::parse_paths;
+use crate::core::pasteboard::ContentType;
+use crate::db::crud::host_clipboard;
+use crate::db::entities::host_clipboard::{default_copy_count, Model};
+use crate::error::{AppError, AppResult};
+use crate::utils::time::get_current_timestamp;
+
+const MANIFEST_NAME: &str = "manifest.json";
+const ARCHIVE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Json, // 与 supercv export 默认输出相同的 JSON 数组
+    Jsonl,
+    Csv,
+    Archive,
+}
+
+impl ExportFormat {
+    // 按扩展名推断格式
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "json" => Some(ExportFormat::Json),
+            "jsonl" | "ndjson" => Some(ExportFormat::Jsonl),
+            "csv" => Some(ExportFormat::Csv),
+            "supercv" | "zip" => Some(ExportFormat::Archive),
+            _ => None,
+        }
+    }
+}
+
+// 导入的记录与本地记录 hash 相同时的处理方式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    #[default]
+    Skip,
+    Overwrite,
+    Newer, // 导入的记录更新时才覆盖
+}
+
+// 导出的记录, 不带本机的 id. 字段都是标量, CSV 也能直接使用
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExportRecord {
+    pub r#type: i32,
+    pub content: String,
+    #[serde(default)]
+    pub path: String,
+    pub timestamp: i64,
+    pub hash: String,
+    #[serde(default)]
+    pub source: i32,
+    #[serde(default)]
+    pub app_name: String,
+    #[serde(default)]
+    pub process_name: String,
+    #[serde(default)]
+    pub window_title: String,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default = "default_copy_count")]
+    pub copy_count: i32,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub note: String,
+}
+
+impl From<Model> for ExportRecord {
+    fn from(m: Model) -> Self {
+        ExportRecord {
+            r#type: m.r#type,
+            content: m.content,
+            path: m.path,
+            timestamp: m.timestamp,
+            hash: m.hash,
+            source: m.source,
+            app_name: m.app_name,
+            process_name: m.process_name,
+            window_title: m.window_title,
+            pinned: m.pinned,
+            copy_count: m.copy_count,
+            title: m.title,
+            note: m.note,
+        }
+    }
+}
+
+impl ExportRecord {
+    fn into_model(self) -> Model {
+        Model {
+            id: 0,
+            r#type: self.r#type,
+            path: self.path,
+            content: self.content,
+            timestamp: self.timestamp,
+            hash: self.hash,
+            source: self.source,
+            app_name: self.app_name,
+            process_name: self.process_name,
+            window_title: self.window_title,
+            pinned: self.pinned,
+            copy_count: self.copy_count,
+            title: self.title,
+            note: self.note,
+            deleted_at: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    exported_at: i64,
+    platform: String,
+    entries: Vec<ArchiveEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ArchiveEntry {
+    #[serde(flatten)]
+    record: ExportRecord,
+    // 压缩包内的文件名, 与 path 中的路径一一对应
+    #[serde(default)]
+    files: Vec<String>,
+}
+
+// 待写入的记录和本地 hash 相同的记录 id
+type Accepted = (ArchiveEntry, Option<i32>);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ExportReport {
+    pub entries: usize,
+    pub files: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ImportReport {
+    pub imported: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    pub files: usize,
+}
+
+// 将记录写入 dest, 压缩包中会带上图片和文件
+pub async fn export_entries(entries: Vec<Model>, format: ExportFormat, dest: &Path) -> AppResult<ExportReport> {
+    let dest = dest.to_path_buf();
+    blocking(move || {
+        let file = File::create(&dest)?;
+        match format {
+            ExportFormat::Archive => write_archive(&entries, file),
+            _ => {
+                write_records(&entries, format, BufWriter::new(file))?;
+                Ok(ExportReport {
+                    entries: entries.len(),
+                    files: 0,
+                })
+            },
+        }
+    })
+    .await
+}
+
+// 以文本格式写出记录, 压缩包需要可以 seek 的文件, 使用 export_entries
+pub fn write_records<W: Write>(entries: &[Model], format: ExportFormat, mut writer: W) -> AppResult<()> {
+    let records = entries.iter().cloned().map(ExportRecord::from);
+    match format {
+        ExportFormat::Json => serde_json::to_writer_pretty(&mut writer, &records.collect::<Vec<_>>())?,
+        ExportFormat::Jsonl => {
+            for record in records {
+                serde_json::to_writer(&mut writer, &record)?;
+                writer.write_all(b"\n")?;
+            }
+        },
+        ExportFormat::Csv => {
+            let mut csv_writer = csv::Writer::from_writer(&mut writer);
+            for record in records {
+                csv_writer.serialize(record).map_err(csv_error)?;
+            }
+            csv_writer.flush()?;
+        },
+        ExportFormat::Archive => return Err(AppError::InvalidInput("Archive can only be written to a file".to_string())),
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_archive<W: Write + Seek>(entries: &[Model], writer: W) -> AppResult<ExportReport> {
+    let mut zip = ZipWriter::new(writer);
+    // 图片和文件可能超过 4 GiB, 需要 ZIP64
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated).large_file(true);
+    let mut report = ExportReport {
+        entries: entries.len(),
+        files: 0,
+    };
+    let mut manifest_entries = Vec::with_capacity(entries.len());
+
+    for (i, entry) in entries.iter().enumerate() {
+        let mut files = Vec::new();
+        if entry.r#type != ContentType::Text.to_i32() {
+            for (j, path) in parse_paths(entry.path.clone()).iter().enumerate() {
+                // 目录和已经删除的文件不打包, 一个都没有打包时导入后保留原路径
+                if !Path::new(path).is_file() {
+                    warn!("Skip exporting {}: not a file", path);
+                    continue;
+                }
+                let name = format!("files/{}/{}/{}", i, j, file_name(path));
+                zip.start_file(name.as_str(), options).map_err(zip_error)?;
+                io::copy(&mut File::open(path)?, &mut zip)?;
+                files.push(name);
+            }
+        }
+        report.files += files.len();
+        manifest_entries.push(ArchiveEntry {
+            record: entry.clone().into(),
+            files,
+        });
+    }
+
+    let manifest = Manifest {
+        version: ARCHIVE_VERSION,
+        exported_at: get_current_timestamp(),
+        platform: std::env::consts::OS.to_string(),
+        entries: manifest_entries,
+    };
+    zip.start_file(MANIFEST_NAME, options).map_err(zip_error)?;
+    serde_json::to_writer(&mut zip, &manifest)?;
+    zip.finish().map_err(zip_error)?;
+    Ok(report)
+}
+
+// 从 src 导入记录, format 为空时按扩展名推断. 压缩包中的文件解压到 files_dir 下
+pub async fn import_entries(
+    db: &DatabaseConnection,
+    src: &Path,
+    format: Option<ExportFormat>,
+    policy: ConflictPolicy,
+    files_dir: &Path,
+) -> AppResult<ImportReport> {
+    let format = format
+        .or_else(|| ExportFormat::from_path(src))
+        .ok_or_else(|| AppError::InvalidInput(format!("Unknown import format: {}", src.display())))?;
+    let path = src.to_path_buf();
+    let entries = blocking(move || read_entries(&path, format)).await?;
+
+    let mut report = ImportReport::default();
+    let mut accepted = Vec::new();
+    let mut seen = HashSet::new();
+    for entry in entries {
+        // 同一文件中重复的 hash 只导入第一条
+        if !seen.insert(entry.record.hash.clone()) {
+            report.skipped += 1;
+            continue;
+        }
+        // 已删除等待清除的记录同样占用 hash, 导入时覆盖并恢复
+        let existing = host_clipboard::get_clipboard_entry_by_hash_including_deleted(db, &entry.record.hash).await?;
+        let accept = match (&existing, policy) {
+            (None, _) => true,
+            (Some(local), _) if local.deleted_at.is_some() => true,
+            (Some(_), ConflictPolicy::Overwrite) => true,
+            (Some(local), ConflictPolicy::Newer) => entry.record.timestamp > local.timestamp,
+            (Some(_), ConflictPolicy::Skip) => false,
+        };
+        if accept {
+            accepted.push((entry, existing.map(|e| e.id)));
+        } else {
+            report.skipped += 1;
+        }
+    }
+
+    // 只解压需要写入的记录引用的文件
+    if format == ExportFormat::Archive {
+        let path = src.to_path_buf();
+        // 同一秒内的两次导入不能解压到同一个目录
+        let dir = files_dir.join(format!("{}_{}", get_current_timestamp(), uuid::Uuid::new_v4().simple()));
+        let (extracted, files) = blocking(move || extract_files(&path, &dir, accepted)).await?;
+        accepted = extracted;
+        report.files = files;
+    }
+
+    let txn = db.begin().await?;
+    for (entry, existing) in accepted {
+        host_clipboard::import_clipboard_entry(&txn, entry.record.into_model(), existing).await?;
+        if existing.is_some() {
+            report.updated += 1;
+        } else {
+            report.imported += 1;
+        }
+    }
+    txn.commit().await?;
+    Ok(report)
+}
+
+fn read_entries(src: &Path, format: ExportFormat) -> AppResult<Vec<ArchiveEntry>> {
+    let reader = BufReader::new(File::open(src)?);
+    let records: Vec<ExportRecord> = match format {
+        ExportFormat::Json => serde_json::from_reader(reader).map_err(json_error)?,
+        ExportFormat::Jsonl => {
+            let mut records = Vec::new();
+            for line in reader.lines() {
+                let line = line?;
+                if !line.trim().is_empty() {
+                    records.push(serde_json::from_str(&line).map_err(json_error)?);
+                }
+            }
+            records
+        },
+        ExportFormat::Csv => csv::Reader::from_reader(reader)
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .map_err(csv_error)?,
+        ExportFormat::Archive => {
+            let mut archive = ZipArchive::new(reader).map_err(zip_error)?;
+            let manifest: Manifest = serde_json::from_reader(archive.by_name(MANIFEST_NAME).map_err(zip_error)?).map_err(json_error)?;
+            if manifest.version > ARCHIVE_VERSION {
+                return Err(AppError::InvalidInput(format!("Unsupported archive version {}", manifest.version)));
+            }
+            return Ok(manifest.entries);
+        },
+    };
+    Ok(records.into_iter().map(|record| ArchiveEntry { record, files: vec![] }).collect())
+}
+
+// 解压记录引用的文件并改写 path: 图片为单个路径, 文件为 JSON 数组
+fn extract_files(src: &Path, dir: &Path, entries: Vec<Accepted>) -> AppResult<(Vec<Accepted>, usize)> {
+    let mut archive = ZipArchive::new(BufReader::new(File::open(src)?)).map_err(zip_error)?;
+    let mut count = 0;
+    let mut result = Vec::with_capacity(entries.len());
+    for (mut entry, existing) in entries {
+        let mut paths = Vec::new();
+        for name in &entry.files {
+            let mut file = archive.by_name(name).map_err(zip_error)?;
+            // 拒绝 ../ 和绝对路径
+            let target = match file.enclosed_name() {
+                Some(name) => dir.join(name),
+                None => return Err(AppError::InvalidInput(format!("Invalid file name in archive: {}", name))),
+            };
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            io::copy(&mut file, &mut File::create(&target)?)?;
+            paths.push(target.to_string_lossy().to_string());
+        }
+        if !paths.is_empty() {
+            count += paths.len();
+            entry.record.path = if entry.record.r#type == ContentType::Image.to_i32() {
+                paths.remove(0)
+            } else {
+                serde_json::to_string(&paths)?
+            };
+        }
+        result.push((entry, existing));
+    }
+    Ok((result, count))
+}
+
+// 路径可能来自其他系统, 按两种分隔符处理, 保证结果不含目录
+pub(crate) fn file_name(path: &str) -> String {
+    match path.rsplit(['/', '\\']).next() {
+        Some(name) if !name.is_empty() && name != "." && name != ".." => name.to_string(),
+        _ => "file".to_string(),
+    }
+}
+
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> AppResult<T> + Send + 'static) -> AppResult<T> {
+    tokio::task::spawn_blocking(f).await.map_err(|e| AppError::Internal(e.to_string()))?
+}
+
+fn json_error(e: serde_json::Error) -> AppError {
+    AppError::InvalidInput(format!("Invalid json: {}", e))
+}
+
+fn csv_error(e: csv::Error) -> AppError {
+    AppError::InvalidInput(format!("Invalid csv: {}", e))
+}
+
+fn zip_error(e: ZipError) -> AppError {
+    match e {
+        ZipError::Io(e) => AppError::Io(e),
+        e => AppError::InvalidInput(format!("Invalid archive: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::connection::init_db_connection;
+    use crate::db::testing::temp_db;
+    use crate::utils::hash::{hash_str, hash_vec};
+
+    fn record(r#type: ContentType, content: &str, path: &str, hash: String, timestamp: i64) -> Model {
+        ExportRecord {
+            r#type: r#type.to_i32(),
+            content: content.to_string(),
+            path: path.to_string(),
+            timestamp,
+            hash,
+            source: 0,
+            app_name: "Gnome-terminal".to_string(),
+            process_name: "gnome-terminal-".to_string(),
+            window_title: "a, \"quoted\" title".to_string(),
+            pinned: true,
+            copy_count: 1,
+            title: String::new(),
+            note: String::new(),
+        }
+        .into_model()
+    }
+
+    // 与 temp_db 同一目录下的其他数据库
+    async fn open_db(dir: &Path, name: &str) -> DatabaseConnection {
+        init_db_connection(dir.join(name).to_str()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_text_formats_roundtrip() {
+        let (db, dir) = temp_db("history_text").await;
+        let text = "line 1\nline 2, with comma";
+        host_clipboard::import_clipboard_entry(&db, record(ContentType::Text, text, "", hash_str(text), 100), None)
+            .await
+            .unwrap();
+        let entries = host_clipboard::get_all_clipboard_entries(&db).await.unwrap();
+
+        for (format, file) in [
+            (ExportFormat::Jsonl, "out.jsonl"),
+            (ExportFormat::Csv, "out.csv"),
+            (ExportFormat::Json, "out.json"),
+        ] {
+            let report = export_entries(entries.clone(), format, &dir.join(file)).await.unwrap();
+            assert_eq!(report.entries, 1);
+
+            let target = open_db(&dir, &format!("{}.sqlite", file)).await;
+            let report = import_entries(&target, &dir.join(file), None, ConflictPolicy::Skip, &dir).await.unwrap();
+            assert_eq!(report.imported, 1);
+            let imported = host_clipboard::get_all_clipboard_entries(&target).await.unwrap();
+            assert_eq!(ExportRecord::from(imported[0].clone()), ExportRecord::from(entries[0].clone()));
+
+            // 再次导入时 hash 冲突
+            let report = import_entries(&target, &dir.join(file), None, ConflictPolicy::Skip, &dir).await.unwrap();
+            assert_eq!(report.skipped, 1);
+            let report = import_entries(&target, &dir.join(file), None, ConflictPolicy::Newer, &dir).await.unwrap();
+            assert_eq!(report.skipped, 1);
+            let report = import_entries(&target, &dir.join(file), None, ConflictPolicy::Overwrite, &dir)
+                .await
+                .unwrap();
+            assert_eq!(report.updated, 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_archive_rewrites_paths() {
+        let (db, dir) = temp_db("history_archive").await;
+        let image = dir.join("image.png");
+        fs::write(&image, b"png bytes").unwrap();
+        let doc = dir.join("doc.txt");
+        fs::write(&doc, b"doc").unwrap();
+        let image_path = image.to_string_lossy().to_string();
+        let files_path = serde_json::to_string(&[doc.to_string_lossy(), dir.join("missing.txt").to_string_lossy()]).unwrap();
+        host_clipboard::import_clipboard_entry(&db, record(ContentType::Image, "", &image_path, hash_vec(b"png bytes"), 100), None)
+            .await
+            .unwrap();
+        host_clipboard::import_clipboard_entry(&db, record(ContentType::File, "doc.txt", &files_path, hash_str(&files_path), 200), None)
+            .await
+            .unwrap();
+
+        let archive = dir.join("history.supercv");
+        let entries = host_clipboard::get_all_clipboard_entries(&db).await.unwrap();
+        let report = export_entries(entries, ExportFormat::Archive, &archive).await.unwrap();
+        assert_eq!(report, ExportReport { entries: 2, files: 2 });
+
+        let target = open_db(&dir, "dst.sqlite").await;
+        let files_dir = dir.join("imported");
+        let report = import_entries(&target, &archive, None, ConflictPolicy::Skip, &files_dir).await.unwrap();
+        assert_eq!(report.imported, 2);
+        assert_eq!(report.files, 2);
+
+        let imported = host_clipboard::get_all_clipboard_entries(&target).await.unwrap();
+        assert!(imported[0].path.starts_with(files_dir.to_str().unwrap()));
+        assert_eq!(fs::read(&imported[0].path).unwrap(), b"png bytes");
+        let paths = parse_paths(imported[1].path.clone());
+        assert_eq!(paths.len(), 1);
+        assert_eq!(fs::read(&paths[0]).unwrap(), b"doc");
+        // hash 保持不变, 以便之后再次合并
+        assert_eq!(imported[1].hash, hash_str(&files_path));
+
+        // 紧接着再次导入时解压到另一个目录, 不覆盖上一次的文件
+        let again = open_db(&dir, "again.sqlite").await;
+        import_entries(&again, &archive, None, ConflictPolicy::Skip, &files_dir).await.unwrap();
+        let reimported = host_clipboard::get_all_clipboard_entries(&again).await.unwrap();
+        assert_ne!(Path::new(&reimported[0].path).parent(), Path::new(&imported[0].path).parent());
+        assert_eq!(fs::read(&imported[0].path).unwrap(), b"png bytes");
+    }
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(file_name("C:\\Users\\me\\a.png"), "a.png");
+        assert_eq!(file_name("/home/me/b.txt"), "b.txt");
+        assert_eq!(file_name("/home/me/.."), "file");
+    }
+
+    #[test]
+    fn test_missing_copy_count() {
+        let json = r#"{"id": 1, "type": 0, "path": "", "content": "old", "timestamp": 0, "hash": "h"}"#;
+        assert_eq!(serde_json::from_str::<Model>(json).unwrap().copy_count, 1);
+        assert_eq!(serde_json::from_str::<ExportRecord>(json).unwrap().copy_count, 1);
+    }
+}
@@ -1,2 +1,12 @@
 pub mod pasteboard;
+pub mod capture;
 pub mod clipboard;
+pub mod events;
+pub mod history;
+pub mod revision;
+pub mod stats;
+pub mod trash;
+#[cfg(target_os = "linux")]
+pub mod primary;
+#[cfg(target_os = "linux")]
+pub mod wayland;
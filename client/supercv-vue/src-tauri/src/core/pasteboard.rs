@@ -1,18 +1,18 @@
 extern crate chrono;
 use std::cmp::PartialEq;
-use std::io;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 
 use chrono::offset::FixedOffset;
 use chrono::{DateTime, Datelike};
 use clipboard_rs::common::RustImage;
 use clipboard_rs::RustImageData;
-use log::debug;
+use log::{debug, error};
 use serde_json::json;
 #[cfg(target_os = "linux")]
 use url::Url;
 
 use crate::core::clipboard::ClipboardHandle;
+use crate::error::{AppError, AppResult};
 use crate::utils;
 use crate::utils::config::CONFIG;
 use crate::utils::file::{format_size, get_file_size};
@@ -34,6 +34,15 @@ impl ContentType {
             ContentType::File => 2,
         }
     }
+
+    pub fn from_i32(value: i32) -> Option<Self> {
+        match value {
+            0 => Some(ContentType::Text),
+            1 => Some(ContentType::Image),
+            2 => Some(ContentType::File),
+            _ => None,
+        }
+    }
 }
 
 impl PartialEq for ContentType {
@@ -42,6 +51,30 @@ impl PartialEq for ContentType {
     }
 }
 
+// 内容来自哪个 selection
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContentSource {
+    Clipboard,
+    Primary, // X11 PRIMARY
+}
+
+impl ContentSource {
+    pub fn to_i32(&self) -> i32 {
+        match self {
+            ContentSource::Clipboard => 0,
+            ContentSource::Primary => 1,
+        }
+    }
+}
+
+// 复制时的来源应用, 取不到时为空字符串
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceApp {
+    pub app_name: String,     // 应用名, X11 下为 WM_CLASS 的 class 部分
+    pub process_name: String, // 进程名
+    pub window_title: String, // 窗口标题
+}
+
 #[derive(Debug)]
 pub struct PasteboardContent {
     pub text_content: String, // 索引内容
@@ -49,6 +82,8 @@ pub struct PasteboardContent {
     pub hash: String,         // content or text_content hash
     pub path: String,         // 路径
     pub date_time: DateTime<FixedOffset>,
+    pub source: ContentSource, // 来源 selection
+    pub app: SourceApp,        // 来源应用
 }
 
 impl PasteboardContent {
@@ -60,6 +95,8 @@ impl PasteboardContent {
             hash,
             path: path.unwrap_or_default(),
             date_time: get_current_date_time(),
+            source: ContentSource::Clipboard,
+            app: SourceApp::default(),
         }
     }
 }
@@ -74,8 +111,8 @@ impl ClipboardHandle {
         if self.check_hash(&hash) {
             return None;
         }
-        self.last_hash = hash.clone();
-        return Some(PasteboardContent::new(text_content, ContentType::Text, hash, None));
+        self.last_hash.set(&hash);
+        Some(PasteboardContent::new(text_content, ContentType::Text, hash, None))
     }
 
     pub(crate) fn new_file_content(&mut self, file_urls: Vec<String>) -> Option<PasteboardContent> {
@@ -86,15 +123,7 @@ impl ClipboardHandle {
         }
 
         let (text_content, content_type, path) = if file_urls.len() == 1 {
-            #[cfg(target_os = "linux")]
-            let path = {
-                let url = Url::parse(&file_urls[0]).expect("Invalid URL");
-                let decoded_path = urlencoding::decode(url.path()).expect("UTF-8");
-                PathBuf::from(decoded_path.as_ref())
-            };
-
-            #[cfg(any(target_os = "macos", target_os = "windows"))]
-            let path = PathBuf::from(&file_urls[0]);
+            let path = url_to_path(&file_urls[0])?;
 
             // let path = url.path();
             let file_name = path.file_name()?.to_str()?;
@@ -124,20 +153,7 @@ impl ClipboardHandle {
                 )
             }
         } else {
-            let paths: Vec<PathBuf> = file_urls
-                .iter()
-                .map(|url| {
-                    #[cfg(any(target_os = "macos", target_os = "windows"))]
-                    return PathBuf::from(url);
-
-                    #[cfg(target_os = "linux")]
-                    {
-                        let url = Url::parse(url).expect("Invalid URL");
-                        let decoded_path = urlencoding::decode(url.path()).expect("UTF-8");
-                        return PathBuf::from(decoded_path.as_ref());
-                    }
-                })
-                .collect();
+            let paths: Vec<PathBuf> = file_urls.iter().filter_map(|url| url_to_path(url)).collect();
             let file_names: String = paths
                 .iter()
                 .filter_map(|path| path.file_name().and_then(|name| name.to_str()))
@@ -155,7 +171,7 @@ impl ClipboardHandle {
         if self.check_hash(&hash) {
             return None;
         }
-        self.last_hash = hash.clone();
+        self.last_hash.set(&hash);
         Some(PasteboardContent::new(text_content, content_type, hash, Some(path)))
     }
 
@@ -166,40 +182,65 @@ impl ClipboardHandle {
         let hash = hash_vec(img.get_bytes());
         // let hash = "test".to_string();
 
-        let path = get_local_path("png").unwrap();
-        img.save_to_path(&path).unwrap();
         if self.check_hash(&hash) {
             return None;
         }
-        self.last_hash = hash.clone();
+        // 保存失败时丢弃这张图片, 不影响之后的采集
+        let path = match save_image(img) {
+            Ok(path) => path,
+            Err(e) => {
+                error!("Failed to save image: {}", e);
+                return None;
+            },
+        };
+        self.last_hash.set(&hash);
         Some(PasteboardContent::new(text_content, ContentType::Image, hash, Some(path)))
     }
 
     fn check_hash(&self, hash: &str) -> bool {
-        return if self.last_hash == *hash {
+        if self.last_hash.is(hash) {
             debug!("check_hash true");
             true
         } else {
             false
-        };
+        }
     }
 }
 
-fn get_local_path(suffix: &str) -> Result<String, io::Error> {
+// 剪贴板中的文件 URL 转为本地路径, 无法解析时记录日志并跳过
+fn url_to_path(url: &str) -> Option<PathBuf> {
+    #[cfg(target_os = "linux")]
+    {
+        let parsed = Url::parse(url).map_err(|e| error!("Invalid file url {}: {}", url, e)).ok()?;
+        let decoded_path = urlencoding::decode(parsed.path())
+            .map_err(|e| error!("Invalid file url {}: {}", url, e))
+            .ok()?;
+        Some(PathBuf::from(decoded_path.as_ref()))
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    Some(PathBuf::from(url))
+}
+
+fn save_image(img: &RustImageData) -> AppResult<String> {
+    let path = get_local_path("png")?;
+    img.save_to_path(&path)?;
+    Ok(path)
+}
+
+fn get_local_path(suffix: &str) -> AppResult<String> {
     let date_time = get_current_date_time();
     let root_file_path = CONFIG
         .read()
         .unwrap()
         .files_path
-        .join(format!("{}{}{}", date_time.year(), date_time.month(), date_time.day()))
-        .to_str()
-        .unwrap()
-        .to_string();
+        .join(format!("{}{}{}", date_time.year(), date_time.month(), date_time.day()));
     // 判断root_file_path 是否存在 不存在则递归创建
-    if !Path::new(&root_file_path).exists() {
-        std::fs::create_dir_all(&root_file_path).unwrap_or_else(|e| {
-            panic!("Failed to create directories: {}", e);
-        });
+    if !root_file_path.exists() {
+        std::fs::create_dir_all(&root_file_path)?;
     }
-    Ok(format!("{}/{}.{}", root_file_path, date_time.timestamp(), suffix))
+    let path = root_file_path.join(format!("{}.{}", date_time.timestamp(), suffix));
+    path.to_str()
+        .map(str::to_string)
+        .ok_or_else(|| AppError::InvalidInput(format!("Non UTF-8 path {}", path.display())))
 }
@@ -0,0 +1,352 @@
+// X11 PRIMARY selection 监听与写入
+//
+// clipboard-rs 只处理 CLIPBOARD, 这里用 XFixes 监听 PRIMARY 的 owner 变化,
+// 静默 debounce_ms 之后再读取, 避免拖选时产生大量记录.
+// 同时持有一个隐藏窗口, 需要写入 PRIMARY 时由它成为 owner 并响应 SelectionRequest.
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use log::{debug, error, info};
+use x11rb::connection::Connection;
+use x11rb::protocol::xfixes::{ConnectionExt as _, SelectionEventMask};
+use x11rb::protocol::xproto::{
+    Atom, AtomEnum, ClientMessageEvent, ConnectionExt as _, CreateWindowAux, EventMask, PropMode, SelectionNotifyEvent, SelectionRequestEvent,
+    Window, WindowClass, SELECTION_NOTIFY_EVENT,
+};
+use x11rb::protocol::Event;
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as _;
+use x11rb::{CURRENT_TIME, NONE};
+
+use crate::core::clipboard::{string_is_large, LastHash};
+use crate::utils::config::CONFIG;
+use crate::utils::hash::hash_str;
+
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+// ClientMessage data[0]
+const WAKE_SET: u32 = 0;
+const WAKE_STOP: u32 = 1;
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        PRIMARY,
+        TARGETS,
+        UTF8_STRING,
+        STRING,
+        TEXT,
+        INCR,
+        SUPERCV_PRIMARY,
+        SUPERCV_WAKE,
+    }
+}
+
+pub trait PrimaryHandler {
+    fn on_primary_change(&mut self, text: String);
+}
+
+// 跨线程写入 PRIMARY, 实际的 owner 切换在监听线程中完成
+#[derive(Clone)]
+pub struct PrimarySelection {
+    conn: Arc<RustConnection>,
+    window: Window,
+    wake_atom: Atom,
+    pending: Arc<Mutex<Option<String>>>,
+}
+
+impl PrimarySelection {
+    pub fn set_text(&self, text: String) -> Result<(), Box<dyn Error>> {
+        *self.pending.lock().unwrap() = Some(text);
+        wake(&self.conn, self.window, self.wake_atom, WAKE_SET)
+    }
+}
+
+pub struct PrimaryWatcherShutdown {
+    conn: Arc<RustConnection>,
+    window: Window,
+    wake_atom: Atom,
+    running: Arc<AtomicBool>,
+}
+
+impl PrimaryWatcherShutdown {
+    pub fn stop(self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Err(e) = wake(&self.conn, self.window, self.wake_atom, WAKE_STOP) {
+            error!("Failed to wake up primary watcher: {}", e);
+        }
+    }
+}
+
+pub struct PrimaryWatcher<T: PrimaryHandler> {
+    conn: Arc<RustConnection>,
+    window: Window,
+    atoms: Atoms,
+    handlers: Vec<T>,
+    pending: Arc<Mutex<Option<String>>>,
+    running: Arc<AtomicBool>,
+    // 我们作为 owner 时提供的内容
+    owned_text: Option<String>,
+    changed_at: Option<Instant>,
+}
+
+impl<T: PrimaryHandler> PrimaryWatcher<T> {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        let (conn, screen_num) = RustConnection::connect(None)?;
+        let conn = Arc::new(conn);
+        let screen = &conn.setup().roots[screen_num];
+        let atoms = Atoms::new(conn.as_ref())?.reply()?;
+        conn.xfixes_query_version(5, 0)?.reply()?;
+
+        let window = conn.generate_id()?;
+        conn.create_window(
+            x11rb::COPY_DEPTH_FROM_PARENT,
+            window,
+            screen.root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            screen.root_visual,
+            &CreateWindowAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+        )?;
+        conn.xfixes_select_selection_input(
+            window,
+            atoms.PRIMARY,
+            SelectionEventMask::SET_SELECTION_OWNER | SelectionEventMask::SELECTION_WINDOW_DESTROY | SelectionEventMask::SELECTION_CLIENT_CLOSE,
+        )?;
+        conn.flush()?;
+        info!("primary selection watcher started");
+
+        Ok(Self {
+            conn,
+            window,
+            atoms,
+            handlers: Vec::new(),
+            pending: Arc::new(Mutex::new(None)),
+            running: Arc::new(AtomicBool::new(true)),
+            owned_text: None,
+            changed_at: None,
+        })
+    }
+
+    pub fn add_handler(&mut self, handler: T) -> &mut Self {
+        self.handlers.push(handler);
+        self
+    }
+
+    pub fn get_selection(&self) -> PrimarySelection {
+        PrimarySelection {
+            conn: self.conn.clone(),
+            window: self.window,
+            wake_atom: self.atoms.SUPERCV_WAKE,
+            pending: self.pending.clone(),
+        }
+    }
+
+    pub fn get_shutdown_channel(&self) -> PrimaryWatcherShutdown {
+        PrimaryWatcherShutdown {
+            conn: self.conn.clone(),
+            window: self.window,
+            wake_atom: self.atoms.SUPERCV_WAKE,
+            running: self.running.clone(),
+        }
+    }
+
+    pub fn start_watch(&mut self) {
+        while self.running.load(Ordering::SeqCst) {
+            let event = match self.conn.wait_for_event() {
+                Ok(event) => event,
+                Err(e) => {
+                    error!("primary watcher connection error: {}", e);
+                    break;
+                },
+            };
+            self.handle_event(event);
+
+            // debounce: 直到 selection 静默 debounce_ms 才读取
+            while let Some(changed_at) = self.changed_at {
+                let debounce = Duration::from_millis(CONFIG.read().unwrap().user_config.primary_config.debounce_ms);
+                if changed_at.elapsed() >= debounce {
+                    self.changed_at = None;
+                    self.read_primary();
+                    break;
+                }
+                std::thread::sleep(POLL_INTERVAL);
+                if let Err(e) = self.poll_events() {
+                    error!("primary watcher connection error: {}", e);
+                    return;
+                }
+            }
+        }
+        let _ = self.conn.destroy_window(self.window);
+        let _ = self.conn.flush();
+    }
+
+    fn poll_events(&mut self) -> Result<(), Box<dyn Error>> {
+        while let Some(event) = self.conn.poll_for_event()? {
+            self.handle_event(event);
+        }
+        Ok(())
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        match event {
+            // 自己成为 owner 时不记录
+            Event::XfixesSelectionNotify(ev) if ev.selection == self.atoms.PRIMARY && ev.owner != self.window && ev.owner != NONE => {
+                self.changed_at = Some(Instant::now());
+            },
+            Event::SelectionRequest(req) => {
+                if let Err(e) = self.serve_request(&req) {
+                    error!("Failed to serve primary selection request: {}", e);
+                }
+            },
+            Event::SelectionClear(ev) if ev.selection == self.atoms.PRIMARY => {
+                self.owned_text = None;
+            },
+            Event::ClientMessage(ev) if ev.type_ == self.atoms.SUPERCV_WAKE && ev.data.as_data32()[0] == WAKE_SET => {
+                let text = self.pending.lock().unwrap().take();
+                if let Some(text) = text {
+                    if let Err(e) = self.take_ownership(text) {
+                        error!("Failed to set primary selection: {}", e);
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn take_ownership(&mut self, text: String) -> Result<(), Box<dyn Error>> {
+        self.conn.set_selection_owner(self.window, self.atoms.PRIMARY, CURRENT_TIME)?;
+        let owner = self.conn.get_selection_owner(self.atoms.PRIMARY)?.reply()?.owner;
+        if owner != self.window {
+            return Err("another client owns the primary selection".into());
+        }
+        self.owned_text = Some(text);
+        Ok(())
+    }
+
+    fn serve_request(&self, req: &SelectionRequestEvent) -> Result<(), Box<dyn Error>> {
+        let mut property = req.property;
+        match self.owned_text.as_ref() {
+            Some(_) if req.target == self.atoms.TARGETS => {
+                let targets = [self.atoms.TARGETS, self.atoms.UTF8_STRING, self.atoms.STRING, self.atoms.TEXT];
+                self.conn
+                    .change_property32(PropMode::REPLACE, req.requestor, req.property, AtomEnum::ATOM, &targets)?;
+            },
+            Some(text) if [self.atoms.UTF8_STRING, self.atoms.STRING, self.atoms.TEXT].contains(&req.target) => {
+                self.conn
+                    .change_property8(PropMode::REPLACE, req.requestor, req.property, req.target, text.as_bytes())?;
+            },
+            _ => property = NONE,
+        }
+        let notify = SelectionNotifyEvent {
+            response_type: SELECTION_NOTIFY_EVENT,
+            sequence: 0,
+            time: req.time,
+            requestor: req.requestor,
+            selection: req.selection,
+            target: req.target,
+            property,
+        };
+        self.conn.send_event(false, req.requestor, EventMask::NO_EVENT, notify)?;
+        self.conn.flush()?;
+        Ok(())
+    }
+
+    fn read_primary(&mut self) {
+        match self.convert_primary() {
+            Ok(Some(text)) if !text.trim().is_empty() => {
+                for handler in self.handlers.iter_mut() {
+                    handler.on_primary_change(text.clone());
+                }
+            },
+            Ok(_) => {},
+            Err(e) => error!("Failed to read primary selection: {}", e),
+        }
+    }
+
+    fn convert_primary(&mut self) -> Result<Option<String>, Box<dyn Error>> {
+        let atoms = self.atoms;
+        self.conn
+            .convert_selection(self.window, atoms.PRIMARY, atoms.UTF8_STRING, atoms.SUPERCV_PRIMARY, CURRENT_TIME)?;
+        self.conn.flush()?;
+
+        let deadline = Instant::now() + READ_TIMEOUT;
+        loop {
+            match self.conn.poll_for_event()? {
+                Some(Event::SelectionNotify(ev)) if ev.requestor == self.window && ev.selection == atoms.PRIMARY => {
+                    if ev.property == NONE {
+                        return Ok(None);
+                    }
+                    break;
+                },
+                Some(event) => self.handle_event(event),
+                None if Instant::now() >= deadline => return Err("timeout waiting for primary selection".into()),
+                None => std::thread::sleep(POLL_INTERVAL),
+            }
+        }
+
+        let reply = self
+            .conn
+            .get_property(true, self.window, atoms.SUPERCV_PRIMARY, AtomEnum::ANY, 0, u32::MAX / 4)?
+            .reply()?;
+        if reply.type_ == atoms.INCR {
+            // 超大的选区走 INCR 分段传输, 历史记录里也不需要
+            debug!("skip INCR primary selection");
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8_lossy(&reply.value).into_owned()))
+    }
+}
+
+// 选区内容是否需要记录, 需要时返回内容的 hash. 空白或过长的内容, 以及与最近一次采集 (包括 CLIPBOARD) 相同的内容不记录
+pub(crate) fn filter_selection(text: &str, last_hash: &LastHash) -> Option<String> {
+    if text.trim().is_empty() || string_is_large(text) {
+        return None;
+    }
+    let hash = hash_str(text);
+    if last_hash.is(&hash) {
+        debug!("primary selection is the same as the last captured content");
+        return None;
+    }
+    Some(hash)
+}
+
+fn wake(conn: &RustConnection, window: Window, wake_atom: Atom, action: u32) -> Result<(), Box<dyn Error>> {
+    let event = ClientMessageEvent::new(32, window, wake_atom, [action, 0, 0, 0, 0]);
+    conn.send_event(false, window, EventMask::NO_EVENT, event)?;
+    conn.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_selection() {
+        let last_hash = LastHash::default();
+        assert_eq!(filter_selection("", &last_hash), None);
+        assert_eq!(filter_selection(" \n\t", &last_hash), None);
+        assert_eq!(filter_selection(&"a".repeat(250001), &last_hash), None);
+        assert_eq!(filter_selection("selected", &last_hash), Some(hash_str("selected")));
+
+        // CLIPBOARD 已经记录了相同的内容
+        last_hash.set(&hash_str("copied"));
+        assert_eq!(filter_selection("copied", &last_hash), None);
+        assert_eq!(filter_selection("copied again", &last_hash), Some(hash_str("copied again")));
+    }
+
+    #[test]
+    fn test_last_hash_is_shared() {
+        let clipboard = LastHash::default();
+        let primary = clipboard.clone();
+        primary.set(&hash_str("selected"));
+        assert!(clipboard.is(&hash_str("selected")));
+        assert!(filter_selection("selected", &clipboard).is_none());
+    }
+}
@@ -0,0 +1,168 @@
+// 记录的编辑和撤销
+//
+// 文本记录可以修改内容, 任意记录都可以修改标题和备注. 每次编辑前把原来的内容存入 clipboard_revision,
+// 撤销时取出最近的一个版本恢复. 修改内容后重新计算 hash, 与其他记录相同时拒绝修改
+use sea_orm::{ConnectionTrait, DatabaseConnection, TransactionTrait};
+use serde::Deserialize;
+
+use crate::core::pasteboard::ContentType;
+use crate::db::crud::{clipboard_revision, host_clipboard};
+use crate::db::entities::clipboard_revision::Model as Revision;
+use crate::db::entities::host_clipboard::Model;
+use crate::error::{AppError, AppResult};
+use crate::utils::hash::hash_str;
+use crate::utils::time::get_current_timestamp;
+
+// 每条记录保留的版本数
+const MAX_REVISIONS: u64 = 20;
+
+// 为 None 的字段保持不变
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct EntryEdit {
+    pub content: Option<String>,
+    pub title: Option<String>,
+    pub note: Option<String>,
+}
+
+pub async fn edit_entry(db: &DatabaseConnection, id: i32, edit: EntryEdit) -> AppResult<Model> {
+    let txn = db.begin().await?;
+    let entry = get_entry(&txn, id).await?;
+
+    let (content, hash) = match edit.content {
+        Some(content) if content != entry.content => {
+            if entry.r#type != ContentType::Text.to_i32() {
+                return Err(AppError::InvalidInput(format!(
+                    "Only text entries can be edited, entry {} is not text",
+                    id
+                )));
+            }
+            if content.is_empty() {
+                return Err(AppError::InvalidInput("Content can not be empty".to_string()));
+            }
+            let hash = hash_str(&content);
+            (content, hash)
+        },
+        _ => (entry.content.clone(), entry.hash.clone()),
+    };
+    let title = edit.title.unwrap_or_else(|| entry.title.clone());
+    let note = edit.note.unwrap_or_else(|| entry.note.clone());
+    // 没有变化时不产生新版本
+    if content == entry.content && title == entry.title && note == entry.note {
+        return Ok(entry);
+    }
+
+    ensure_hash_available(&txn, &hash, id).await?;
+    clipboard_revision::add_revision(&txn, &entry, get_current_timestamp()).await?;
+    clipboard_revision::prune_revisions(&txn, id, MAX_REVISIONS).await?;
+    let entry = host_clipboard::update_clipboard_entry(&txn, id, content, hash, title, note).await?;
+    txn.commit().await?;
+    Ok(entry)
+}
+
+// 恢复到最近一次编辑之前, 可以连续撤销
+pub async fn undo_edit(db: &DatabaseConnection, id: i32) -> AppResult<Model> {
+    let txn = db.begin().await?;
+    get_entry(&txn, id).await?;
+    let revision = clipboard_revision::get_latest_revision(&txn, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("revision of clipboard entry {}", id)))?;
+
+    ensure_hash_available(&txn, &revision.hash, id).await?;
+    clipboard_revision::delete_revision(&txn, revision.id).await?;
+    let entry = host_clipboard::update_clipboard_entry(&txn, id, revision.content, revision.hash, revision.title, revision.note).await?;
+    txn.commit().await?;
+    Ok(entry)
+}
+
+pub async fn list_revisions(db: &DatabaseConnection, id: i32) -> AppResult<Vec<Revision>> {
+    Ok(clipboard_revision::get_revisions(db, id).await?)
+}
+
+async fn get_entry<C: ConnectionTrait>(db: &C, id: i32) -> AppResult<Model> {
+    host_clipboard::get_clipboard_entry(db, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("clipboard entry {}", id)))
+}
+
+// hash 有唯一索引, 与其他记录相同时需要用户先处理那条记录.
+// 已删除等待清除的记录直接清除, 该次删除撤销时不再恢复这条记录
+async fn ensure_hash_available<C: ConnectionTrait>(db: &C, hash: &str, id: i32) -> AppResult<()> {
+    match host_clipboard::get_clipboard_entry_by_hash_including_deleted(db, hash).await? {
+        Some(other) if other.id != id && other.deleted_at.is_some() => {
+            host_clipboard::delete_clipboard_entries(db, vec![other.id]).await?;
+            Ok(())
+        },
+        Some(other) if other.id != id => Err(AppError::InvalidInput(format!("Entry {} already has the same content", other.id))),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::pasteboard::PasteboardContent;
+    use crate::db::testing::temp_db;
+
+    async fn add(db: &DatabaseConnection, text: &str, content_type: ContentType) -> Model {
+        let item = PasteboardContent::new(text.to_string(), content_type, hash_str(text), None);
+        host_clipboard::add_clipboard_entry(db, item).await.unwrap()
+    }
+
+    fn edit(content: Option<&str>, title: Option<&str>) -> EntryEdit {
+        EntryEdit {
+            content: content.map(str::to_string),
+            title: title.map(str::to_string),
+            note: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_edit_and_undo() {
+        let (db, _dir) = temp_db("revision").await;
+
+        let entry = add(&db, "draft", ContentType::Text).await;
+        let other = add(&db, "other", ContentType::Text).await;
+        let image = add(&db, "image.png", ContentType::Image).await;
+
+        let edited = edit_entry(&db, entry.id, edit(Some("final"), Some("greeting"))).await.unwrap();
+        assert_eq!((edited.content.as_str(), edited.title.as_str()), ("final", "greeting"));
+        assert_eq!(edited.hash, hash_str("final"));
+        assert_eq!(edited.timestamp, entry.timestamp);
+        let edited = edit_entry(&db, entry.id, edit(None, Some("renamed"))).await.unwrap();
+        assert_eq!((edited.content.as_str(), edited.title.as_str()), ("final", "renamed"));
+        assert_eq!(list_revisions(&db, entry.id).await.unwrap().len(), 2);
+
+        // 与其他记录的内容相同, 或者修改非文本记录的内容时拒绝
+        assert!(matches!(
+            edit_entry(&db, entry.id, edit(Some("other"), None)).await,
+            Err(AppError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            edit_entry(&db, image.id, edit(Some("text"), None)).await,
+            Err(AppError::InvalidInput(_))
+        ));
+
+        // 已删除的记录不占用 hash
+        let deleted = add(&db, "deleted", ContentType::Text).await;
+        host_clipboard::soft_delete_clipboard_entries(&db, vec![deleted.id], 1).await.unwrap();
+        let edited = edit_entry(&db, other.id, edit(Some("deleted"), None)).await.unwrap();
+        assert_eq!(edited.hash, hash_str("deleted"));
+        let undone = undo_edit(&db, other.id).await.unwrap();
+        assert_eq!(undone.content, "other");
+        assert_eq!(
+            edit_entry(&db, image.id, edit(None, Some("screenshot"))).await.unwrap().title,
+            "screenshot"
+        );
+
+        let undone = undo_edit(&db, entry.id).await.unwrap();
+        assert_eq!((undone.content.as_str(), undone.title.as_str()), ("final", "greeting"));
+        let undone = undo_edit(&db, entry.id).await.unwrap();
+        assert_eq!((undone.content.as_str(), undone.title.as_str(), undone.hash), ("draft", "", entry.hash));
+        assert!(matches!(undo_edit(&db, entry.id).await, Err(AppError::NotFound(_))));
+
+        // 删除记录时一起删除版本
+        edit_entry(&db, other.id, edit(Some("changed"), None)).await.unwrap();
+        host_clipboard::delete_clipboard_entries(&db, vec![other.id]).await.unwrap();
+        assert!(list_revisions(&db, other.id).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,331 @@
+// 剪贴板记录的统计和按天分组的时间线, 用于统计页面和调整过期时间
+//
+// 统计包括已过期但还没有被清理的记录, 不包括已删除的记录 (存储大小除外). 按天和小时分组时使用每条记录当时的本地时区偏移,
+// 跨夏令时切换的记录也归到正确的日期和小时
+use std::collections::HashMap;
+use std::fs;
+
+use chrono::{Local, NaiveDate, Offset, TimeZone, Utc};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, DbErr, QueryResult, Statement, Value};
+use serde::Serialize;
+
+use crate::db::crud::host_clipboard::{self, Cursor};
+use crate::db::entities::host_clipboard::Model;
+use crate::utils::config::CONFIG;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+const TOP_REPEATED: u64 = 10;
+const PREVIEW_CHARS: usize = 100;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DayCount {
+    pub date: String, // 本地日期, 如 2024-08-10
+    pub count: u64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TypeStats {
+    pub r#type: i32,
+    pub count: u64,
+    pub expired: u64, // 按当前的过期配置已过期的记录, 不含置顶
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SourceCount {
+    pub source: i32,
+    pub count: u64,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct StorageStats {
+    pub text_bytes: u64,     // 数据库中记录内容的大小
+    pub image_bytes: u64,    // 图片文件的大小
+    pub database_bytes: u64, // 数据库文件的大小, 包括 WAL
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RepeatedEntry {
+    pub id: i32,
+    pub r#type: i32,
+    pub content: String, // 只保留开头部分
+    pub copy_count: i32,
+    pub timestamp: i64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ClipboardStats {
+    pub total: u64,
+    pub per_day: Vec<DayCount>, // 最近 days 天, 没有记录的日期为 0
+    pub per_hour: Vec<u64>,     // 最近 days 天中每个小时 (0-23) 的记录数
+    pub per_type: Vec<TypeStats>,
+    pub per_source: Vec<SourceCount>,
+    pub storage: StorageStats,
+    pub top_repeated: Vec<RepeatedEntry>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TimelineDay {
+    pub date: String,
+    pub entries: Vec<Model>,
+}
+
+// 同一天的记录可能分在相邻的两页, 前端按 date 合并
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Timeline {
+    pub days: Vec<TimelineDay>,
+    pub next_cursor: Option<Cursor>,
+    pub total: u64,
+}
+
+pub async fn get_stats(db: &DatabaseConnection, days: u32) -> Result<ClipboardStats, DbErr> {
+    let today = Local::now().date_naive();
+    let first_day = today - chrono::Duration::days(days.max(1) as i64 - 1);
+    let since = local_midnight(first_day);
+
+    // SQLite 的 localtime 与 chrono 的 Local 一样使用系统时区, 按每条记录的时间戳换算偏移
+    let rows = query(
+        db,
+        "SELECT strftime('%Y-%m-%d', timestamp, 'unixepoch', 'localtime') AS day, COUNT(*) AS count \
+         FROM host_clipboard WHERE deleted_at IS NULL AND timestamp >= ? GROUP BY day",
+        [since.into()],
+    )
+    .await?;
+    let counts = rows
+        .iter()
+        .map(|row| Ok((row.try_get::<String>("", "day")?, row.try_get::<i64>("", "count")? as u64)))
+        .collect::<Result<HashMap<_, _>, DbErr>>()?;
+    let per_day = first_day
+        .iter_days()
+        .take_while(|day| *day <= today)
+        .map(|day| {
+            let date = day.format("%Y-%m-%d").to_string();
+            let count = counts.get(&date).copied().unwrap_or(0);
+            DayCount { date, count }
+        })
+        .collect();
+
+    let mut per_hour = vec![0; 24];
+    let rows = query(
+        db,
+        "SELECT CAST(strftime('%H', timestamp, 'unixepoch', 'localtime') AS INTEGER) AS hour, COUNT(*) AS count \
+         FROM host_clipboard WHERE deleted_at IS NULL AND timestamp >= ? GROUP BY hour",
+        [since.into()],
+    )
+    .await?;
+    for row in rows {
+        let hour = row.try_get::<i64>("", "hour")?;
+        if let Some(count) = per_hour.get_mut(hour as usize) {
+            *count = row.try_get::<i64>("", "count")? as u64;
+        }
+    }
+
+    let (text_ts, img_ts, file_ts) = CONFIG.read().unwrap().get_expired_ts();
+    let per_type = query(
+        db,
+        "SELECT type, COUNT(*) AS count, \
+         SUM(CASE WHEN pinned = 0 AND timestamp <= (CASE type WHEN 0 THEN ? WHEN 1 THEN ? ELSE ? END) THEN 1 ELSE 0 END) AS expired \
+         FROM host_clipboard WHERE deleted_at IS NULL GROUP BY type ORDER BY type",
+        [text_ts.into(), img_ts.into(), file_ts.into()],
+    )
+    .await?
+    .into_iter()
+    .map(|row| {
+        Ok(TypeStats {
+            r#type: row.try_get("", "type")?,
+            count: row.try_get::<i64>("", "count")? as u64,
+            expired: row.try_get::<i64>("", "expired")? as u64,
+        })
+    })
+    .collect::<Result<Vec<_>, DbErr>>()?;
+
+    let per_source = query(
+        db,
+        "SELECT source, COUNT(*) AS count FROM host_clipboard WHERE deleted_at IS NULL GROUP BY source ORDER BY source",
+        [],
+    )
+    .await?
+    .into_iter()
+    .map(|row| {
+        Ok(SourceCount {
+            source: row.try_get("", "source")?,
+            count: row.try_get::<i64>("", "count")? as u64,
+        })
+    })
+    .collect::<Result<Vec<_>, DbErr>>()?;
+
+    let top_repeated = query(
+        db,
+        "SELECT id, type, content, copy_count, timestamp FROM host_clipboard WHERE deleted_at IS NULL AND copy_count > 1 \
+         ORDER BY copy_count DESC, timestamp DESC LIMIT ?",
+        [TOP_REPEATED.into()],
+    )
+    .await?
+    .into_iter()
+    .map(|row| {
+        Ok(RepeatedEntry {
+            id: row.try_get("", "id")?,
+            r#type: row.try_get("", "type")?,
+            content: row.try_get::<String>("", "content")?.chars().take(PREVIEW_CHARS).collect(),
+            copy_count: row.try_get("", "copy_count")?,
+            timestamp: row.try_get("", "timestamp")?,
+        })
+    })
+    .collect::<Result<Vec<_>, DbErr>>()?;
+
+    Ok(ClipboardStats {
+        total: per_type.iter().map(|t| t.count).sum(),
+        per_day,
+        per_hour,
+        per_type,
+        per_source,
+        storage: get_storage(db).await?,
+        top_repeated,
+    })
+}
+
+async fn get_storage(db: &DatabaseConnection) -> Result<StorageStats, DbErr> {
+    let rows = query(
+        db,
+        "SELECT COALESCE(SUM(LENGTH(CAST(content AS BLOB))), 0) AS bytes FROM host_clipboard",
+        [],
+    )
+    .await?;
+    let text_bytes = match rows.first() {
+        Some(row) => row.try_get::<i64>("", "bytes")? as u64,
+        None => 0,
+    };
+
+    // 图片文件可能已被删除, 不存在的文件不计入
+    let mut image_bytes = 0;
+    for row in query(db, "SELECT path FROM host_clipboard WHERE type = 1", []).await? {
+        let path: String = row.try_get("", "path")?;
+        image_bytes += fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    }
+
+    // 内存数据库时文件不存在, 为 0
+    let db_file = CONFIG.read().unwrap().db_path.join("db.sqlite");
+    let database_bytes = ["", "-wal"]
+        .iter()
+        .map(|suffix| {
+            let mut path = db_file.clone().into_os_string();
+            path.push(suffix);
+            fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+        })
+        .sum();
+
+    Ok(StorageStats {
+        text_bytes,
+        image_bytes,
+        database_bytes,
+    })
+}
+
+// 与 get_clipboard_page 相同的分页, 每页的记录按本地日期分组
+pub async fn get_timeline(
+    db: &DatabaseConnection,
+    text: Option<&str>,
+    type_list: Option<Vec<i32>>,
+    app: Option<&str>,
+    cursor: Option<Cursor>,
+    limit: u64,
+) -> Result<Timeline, DbErr> {
+    let page = host_clipboard::get_clipboard_page(db, text, type_list, app, cursor, limit).await?;
+    let mut days: Vec<TimelineDay> = Vec::new();
+    for entry in page.entries {
+        let date = local_date(entry.timestamp);
+        match days.last_mut() {
+            Some(day) if day.date == date => day.entries.push(entry),
+            _ => days.push(TimelineDay { date, entries: vec![entry] }),
+        }
+    }
+    Ok(Timeline {
+        days,
+        next_cursor: page.next_cursor,
+        total: page.total,
+    })
+}
+
+async fn query<const N: usize>(db: &DatabaseConnection, sql: &str, values: [Value; N]) -> Result<Vec<QueryResult>, DbErr> {
+    db.query_all(Statement::from_sql_and_values(DbBackend::Sqlite, sql, values)).await
+}
+
+// timestamp 所在时刻的本地时区偏移, 夏令时前后不同
+fn local_offset(timestamp: i64) -> i64 {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map_or(0, |datetime| datetime.offset().fix().local_minus_utc() as i64)
+}
+
+fn local_date(timestamp: i64) -> String {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|datetime| datetime.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+// 本地日期开始的时间戳. 夏令时在午夜切换时当天没有 00:00, 按切换前的偏移换算
+fn local_midnight(date: NaiveDate) -> i64 {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+    match Local.from_local_datetime(&midnight).earliest() {
+        Some(datetime) => datetime.timestamp(),
+        None => {
+            let utc = Utc.from_utc_datetime(&midnight).timestamp();
+            utc - local_offset(utc - SECONDS_PER_DAY)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::pasteboard::{ContentType, PasteboardContent};
+    use crate::db::testing::temp_db;
+    use crate::utils::hash::hash_str;
+
+    async fn add(db: &DatabaseConnection, text: &str, seconds_ago: i64) {
+        let mut item = PasteboardContent::new(text.to_string(), ContentType::Text, hash_str(text), None);
+        item.date_time = Local::now().fixed_offset() - chrono::Duration::seconds(seconds_ago);
+        host_clipboard::add_clipboard_entry(db, item).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stats_and_timeline() {
+        let (db, _dir) = temp_db("stats").await;
+
+        add(&db, "two days ago", 2 * SECONDS_PER_DAY).await;
+        add(&db, "repeated", 10).await;
+        add(&db, "repeated", 5).await;
+        add(&db, "repeated", 0).await;
+        add(&db, "once", 0).await;
+
+        let stats = get_stats(&db, 7).await.unwrap();
+        assert_eq!(stats.total, 3);
+        assert_eq!(stats.per_day.len(), 7);
+        assert_eq!(stats.per_day.last().unwrap().date, local_date(Local::now().timestamp()));
+        assert_eq!(stats.per_day.iter().map(|d| d.count).sum::<u64>(), 3);
+        assert_eq!(stats.per_hour.iter().sum::<u64>(), 3);
+        assert_eq!(
+            stats.per_type,
+            vec![TypeStats {
+                r#type: 0,
+                count: 3,
+                expired: 0
+            }]
+        );
+        assert_eq!(stats.per_source, vec![SourceCount { source: 0, count: 3 }]);
+        assert_eq!(stats.storage.text_bytes, ("two days ago".len() + "repeated".len() + "once".len()) as u64);
+        assert_eq!(stats.top_repeated.len(), 1);
+        assert_eq!(
+            (stats.top_repeated[0].content.as_str(), stats.top_repeated[0].copy_count),
+            ("repeated", 3)
+        );
+
+        let timeline = get_timeline(&db, None, None, None, None, 10).await.unwrap();
+        assert_eq!(timeline.total, 3);
+        assert_eq!(timeline.days.len(), 2);
+        assert_eq!(timeline.days[0].entries.len(), 2);
+        assert_eq!(timeline.days[1].entries[0].content, "two days ago");
+    }
+}
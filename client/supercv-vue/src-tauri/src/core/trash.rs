@@ -0,0 +1,231 @@
+// 删除记录
+//
+// 删除时只标记 deleted_at (毫秒), 列表, 搜索, 导出和同步都不再包括这些记录. UNDO_WINDOW 内可以按删除时返回的
+// batch 撤销, 之后由后台任务把记录和对应的图片文件一起清除. 文件类型记录引用的是用户自己的文件, 不会被删除.
+// 开启同步时, 清除的记录通过 outbox 在服务端删除, 避免下次同步时又被下载回来
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+
+use chrono::Utc;
+use lazy_static::lazy_static;
+use log::{error, info, warn};
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+
+use crate::core::pasteboard::ContentType;
+use crate::db::crud::{host_clipboard, outbox};
+use crate::error::{AppError, AppResult};
+use crate::utils::config::CONFIG;
+
+pub const UNDO_WINDOW: Duration = Duration::from_secs(30);
+const PURGE_INTERVAL: Duration = Duration::from_secs(10);
+
+// 上一次删除的 batch, 保证同一毫秒内的两次删除可以分别撤销
+static LAST_BATCH: AtomicI64 = AtomicI64::new(0);
+
+lazy_static! {
+    static ref PURGE_TASK: std::sync::Mutex<Option<JoinHandle<()>>> = std::sync::Mutex::new(None);
+}
+
+// 条件都为空时删除全部记录
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DeleteFilter {
+    pub type_list: Option<Vec<i32>>,
+    pub start: Option<i64>, // 包括 start
+    pub end: Option<i64>,   // 不包括 end
+    #[serde(default)]
+    pub include_pinned: bool, // 默认保留置顶的记录
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DeleteReport {
+    pub deleted: u64,
+    pub batch: i64,      // 撤销时使用
+    pub undo_until: i64, // 毫秒, 之后无法撤销
+}
+
+// 删除指定的记录, 置顶的记录也会被删除
+pub async fn delete_entries(db: &DatabaseConnection, ids: Vec<i32>) -> AppResult<DeleteReport> {
+    let batch = next_batch();
+    let deleted = host_clipboard::soft_delete_clipboard_entries(db, ids, batch).await?;
+    Ok(report(deleted, batch))
+}
+
+pub async fn delete_by_filter(db: &DatabaseConnection, filter: DeleteFilter) -> AppResult<DeleteReport> {
+    let batch = next_batch();
+    let deleted =
+        host_clipboard::soft_delete_clipboards_by_filter(db, filter.type_list, filter.start, filter.end, filter.include_pinned, batch).await?;
+    Ok(report(deleted, batch))
+}
+
+// 返回恢复的条数, 已经被清除时返回 NotFound
+pub async fn undo_delete(db: &DatabaseConnection, batch: i64) -> AppResult<u64> {
+    match host_clipboard::restore_deleted_clipboard_entries(db, batch).await? {
+        0 => Err(AppError::NotFound(format!("deleted entries {}", batch))),
+        restored => Ok(restored),
+    }
+}
+
+// 清除撤销窗口之前删除的记录和图片文件, 返回清除的条数
+pub async fn purge(db: &DatabaseConnection, now: i64) -> AppResult<u64> {
+    let before = now - UNDO_WINDOW.as_millis() as i64;
+    let entries = host_clipboard::purge_deleted_clipboard_entries(db, before).await?;
+    if CONFIG.read().unwrap().user_config.sync_config.enabled {
+        for entry in &entries {
+            outbox::enqueue_outbox_delete(db, &entry.hash, now / 1000).await?;
+        }
+    }
+
+    // 只删除 files_path 下的图片, 只处理实际清除的记录
+    let files_path = CONFIG.read().unwrap().files_path.clone();
+    for entry in entries.iter().filter(|entry| entry.r#type == ContentType::Image.to_i32()) {
+        let path = Path::new(&entry.path);
+        if !path.starts_with(&files_path) {
+            continue;
+        }
+        if let Err(e) = fs::remove_file(path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove image {}: {}", path.display(), e);
+            }
+        }
+    }
+    Ok(entries.len() as u64)
+}
+
+// 定期清除过了撤销窗口的记录, 已在运行时重新启动. 上次运行中未清除的记录在启动后清除
+pub fn start(db: DatabaseConnection) {
+    let task = tokio::spawn(async move {
+        loop {
+            match purge(&db, now_millis()).await {
+                Ok(0) => {},
+                Ok(purged) => info!("Purged {} deleted entries", purged),
+                Err(e) => error!("Failed to purge deleted entries: {}", e),
+            }
+            tokio::time::sleep(PURGE_INTERVAL).await;
+        }
+    });
+    if let Some(previous) = PURGE_TASK.lock().unwrap().replace(task) {
+        previous.abort();
+    }
+}
+
+pub fn stop() {
+    if let Some(task) = PURGE_TASK.lock().unwrap().take() {
+        task.abort();
+    }
+}
+
+fn report(deleted: u64, batch: i64) -> DeleteReport {
+    DeleteReport {
+        deleted,
+        batch,
+        undo_until: batch + UNDO_WINDOW.as_millis() as i64,
+    }
+}
+
+fn now_millis() -> i64 {
+    Utc::now().timestamp_millis()
+}
+
+fn next_batch() -> i64 {
+    let now = now_millis();
+    let previous = LAST_BATCH.fetch_max(now, Ordering::SeqCst);
+    if previous < now {
+        now
+    } else {
+        LAST_BATCH.fetch_add(1, Ordering::SeqCst) + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::history::{self, ExportFormat};
+    use crate::core::pasteboard::PasteboardContent;
+    use crate::db::testing::temp_db;
+    use crate::utils::hash::hash_str;
+
+    async fn add(db: &DatabaseConnection, text: &str, content_type: ContentType, path: Option<String>) -> i32 {
+        let item = PasteboardContent::new(text.to_string(), content_type, hash_str(text), path);
+        host_clipboard::add_clipboard_entry(db, item).await.unwrap().id
+    }
+
+    async fn visible(db: &DatabaseConnection) -> Vec<String> {
+        let entries = host_clipboard::get_clipboards_by_type_list(db, None, None, None, None).await.unwrap();
+        let mut contents: Vec<String> = entries.into_iter().map(|entry| entry.content).collect();
+        contents.sort();
+        contents
+    }
+
+    #[tokio::test]
+    async fn test_delete_undo_and_purge() {
+        let (db, _dir) = temp_db("trash").await;
+
+        let image_path = CONFIG
+            .read()
+            .unwrap()
+            .files_path
+            .join(format!("supercv_test_trash_{}.png", std::process::id()));
+        fs::create_dir_all(image_path.parent().unwrap()).unwrap();
+        fs::write(&image_path, b"png").unwrap();
+        let a = add(&db, "a", ContentType::Text, None).await;
+        add(&db, "b", ContentType::Text, None).await;
+        let image = add(&db, "image", ContentType::Image, Some(image_path.to_string_lossy().to_string())).await;
+        let pinned = add(&db, "pinned", ContentType::Text, None).await;
+        host_clipboard::set_clipboard_pinned(&db, pinned, true).await.unwrap();
+
+        let first = delete_entries(&db, vec![a]).await.unwrap();
+        assert_eq!(first.deleted, 1);
+        assert_eq!(visible(&db).await, vec!["b", "image", "pinned"]);
+
+        // 清空时保留置顶的记录, 每次删除可以分别撤销
+        let second = delete_by_filter(&db, DeleteFilter::default()).await.unwrap();
+        assert_eq!(second.deleted, 2);
+        assert_ne!(first.batch, second.batch);
+        assert_eq!(visible(&db).await, vec!["pinned"]);
+        assert_eq!(undo_delete(&db, first.batch).await.unwrap(), 1);
+        assert_eq!(visible(&db).await, vec!["a", "pinned"]);
+
+        // 撤销窗口内不清除
+        assert_eq!(purge(&db, now_millis()).await.unwrap(), 0);
+        assert!(image_path.exists());
+        let later = second.undo_until + 1;
+        assert_eq!(purge(&db, later).await.unwrap(), 2);
+        assert!(!image_path.exists());
+        assert!(host_clipboard::get_clipboard_entry_by_hash_including_deleted(&db, &hash_str("image"))
+            .await
+            .unwrap()
+            .is_none());
+        assert!(matches!(undo_delete(&db, second.batch).await, Err(AppError::NotFound(_))));
+
+        // 再次复制已删除的内容时恢复
+        delete_entries(&db, vec![a]).await.unwrap();
+        add(&db, "a", ContentType::Text, None).await;
+        assert_eq!(visible(&db).await, vec!["a", "pinned"]);
+        assert!(host_clipboard::get_clipboard_entry(&db, image).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_deleted_entries_are_not_exported() {
+        let (db, _dir) = temp_db("trash_export").await;
+
+        add(&db, "kept entry", ContentType::Text, None).await;
+        let removed = add(&db, "removed entry", ContentType::Text, None).await;
+        delete_entries(&db, vec![removed]).await.unwrap();
+        assert!(host_clipboard::get_clipboard_entry(&db, removed).await.unwrap().is_none());
+        assert!(host_clipboard::get_clipboard_entry_by_hash(&db, &hash_str("removed entry"))
+            .await
+            .unwrap()
+            .is_none());
+
+        let entries = host_clipboard::get_all_clipboard_entries(&db).await.unwrap();
+        let mut exported = Vec::new();
+        history::write_records(&entries, ExportFormat::Jsonl, &mut exported).unwrap();
+        let exported = String::from_utf8(exported).unwrap();
+        assert!(exported.contains("kept entry"));
+        assert!(!exported.contains("removed entry"));
+    }
+}
@@ -0,0 +1,536 @@
+// Wayland 剪切板支持
+//
+// 纯 Wayland 会话下没有 X11, clipboard-rs 无法工作. 这里通过 ext-data-control (优先)
+// 或 wlr-data-control 协议直接监听 selection 变化; 合成器两者都不支持时 `WaylandWatcher::new`
+// 返回错误, 由调用方降级处理而不是 panic.
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::io::{self, Cursor, Read};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::{debug, error, info, warn};
+use url::Url;
+use wayland_client::backend::ObjectId;
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::wl_callback::WlCallback;
+use wayland_client::protocol::wl_registry::WlRegistry;
+use wayland_client::protocol::wl_seat::WlSeat;
+use wayland_client::{event_created_child, Connection, Dispatch, EventQueue, Proxy, QueueHandle};
+use wayland_protocols::ext::data_control::v1::client::ext_data_control_device_v1::{self, ExtDataControlDeviceV1};
+use wayland_protocols::ext::data_control::v1::client::ext_data_control_manager_v1::ExtDataControlManagerV1;
+use wayland_protocols::ext::data_control::v1::client::ext_data_control_offer_v1::{self, ExtDataControlOfferV1};
+use wayland_protocols_wlr::data_control::v1::client::zwlr_data_control_device_v1::{self, ZwlrDataControlDeviceV1};
+use wayland_protocols_wlr::data_control::v1::client::zwlr_data_control_manager_v1::ZwlrDataControlManagerV1;
+use wayland_protocols_wlr::data_control::v1::client::zwlr_data_control_offer_v1::{self, ZwlrDataControlOfferV1};
+use wl_clipboard_rs::copy::{MimeSource, MimeType, Options, Source};
+
+const URI_LIST_MIME: &str = "text/uri-list";
+const PNG_MIME: &str = "image/png";
+// 按优先级排列, 前面的能保证是 UTF-8
+const TEXT_MIMES: [&str; 5] = ["text/plain;charset=utf-8", "UTF8_STRING", "text/plain", "STRING", "TEXT"];
+// 读取 offer 的超时时间, 提供内容的程序卡住时放弃这次内容, 避免阻塞监听线程
+const READ_TIMEOUT: Duration = Duration::from_secs(3);
+
+pub fn is_wayland_session() -> bool {
+    env::var_os("WAYLAND_DISPLAY").is_some() || env::var("XDG_SESSION_TYPE").map(|t| t == "wayland").unwrap_or(false)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WaylandContent {
+    Files(Vec<String>), // file:// url, 与 clipboard-rs 在 linux 下 get_files 的格式一致
+    Image(Vec<u8>),     // png
+    Text(String),
+}
+
+pub trait WaylandClipboardHandler {
+    fn on_wayland_change(&mut self, content: WaylandContent);
+}
+
+#[derive(Clone, PartialEq)]
+enum Offer {
+    Ext(ExtDataControlOfferV1),
+    Zwlr(ZwlrDataControlOfferV1),
+}
+
+impl Offer {
+    fn id(&self) -> ObjectId {
+        match self {
+            Offer::Ext(offer) => offer.id(),
+            Offer::Zwlr(offer) => offer.id(),
+        }
+    }
+
+    fn receive(&self, mime_type: String, fd: BorrowedFd) {
+        match self {
+            Offer::Ext(offer) => offer.receive(mime_type, fd),
+            Offer::Zwlr(offer) => offer.receive(mime_type, fd),
+        }
+    }
+
+    fn destroy(&self) {
+        match self {
+            Offer::Ext(offer) => offer.destroy(),
+            Offer::Zwlr(offer) => offer.destroy(),
+        }
+    }
+}
+
+enum Device {
+    Ext(ExtDataControlDeviceV1),
+    Zwlr(ZwlrDataControlDeviceV1),
+}
+
+impl Device {
+    fn destroy(&self) {
+        match self {
+            Device::Ext(device) => device.destroy(),
+            Device::Zwlr(device) => device.destroy(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct WatchState {
+    mime_types: HashMap<ObjectId, Vec<String>>,
+    selection: Option<Offer>,
+    changed: bool,
+    finished: bool,
+}
+
+impl WatchState {
+    fn set_selection(&mut self, offer: Option<Offer>) {
+        if let Some(old) = self.selection.take() {
+            self.mime_types.remove(&old.id());
+            old.destroy();
+        }
+        self.changed = offer.is_some();
+        self.selection = offer;
+    }
+
+    // 只关心 CLIPBOARD, primary selection 的 offer 直接释放
+    fn drop_offer(&mut self, offer: Option<Offer>) {
+        if let Some(offer) = offer {
+            if self.selection.as_ref() != Some(&offer) {
+                self.mime_types.remove(&offer.id());
+                offer.destroy();
+            }
+        }
+    }
+
+    fn add_mime_type(&mut self, offer: ObjectId, mime_type: String) {
+        self.mime_types.entry(offer).or_default().push(mime_type);
+    }
+}
+
+pub struct WaylandWatcherShutdown {
+    conn: Connection,
+    qh: QueueHandle<WatchState>,
+    running: Arc<AtomicBool>,
+}
+
+impl WaylandWatcherShutdown {
+    pub fn stop(self) {
+        self.running.store(false, Ordering::SeqCst);
+        // 发一个 sync 请求唤醒阻塞在 blocking_dispatch 的监听线程
+        self.conn.display().sync(&self.qh, ());
+        if let Err(e) = self.conn.flush() {
+            error!("Failed to wake up wayland watcher: {}", e);
+        }
+    }
+}
+
+pub struct WaylandWatcher<T: WaylandClipboardHandler> {
+    conn: Connection,
+    queue: EventQueue<WatchState>,
+    state: WatchState,
+    device: Device,
+    handlers: Vec<T>,
+    running: Arc<AtomicBool>,
+}
+
+impl<T: WaylandClipboardHandler> WaylandWatcher<T> {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        let conn = Connection::connect_to_env()?;
+        let (globals, mut queue) = registry_queue_init::<WatchState>(&conn)?;
+        let qh = queue.handle();
+        let seat: WlSeat = globals.bind(&qh, 1..=7, ())?;
+
+        let device = if let Ok(manager) = globals.bind::<ExtDataControlManagerV1, _, _>(&qh, 1..=1, ()) {
+            info!("wayland clipboard: using ext-data-control");
+            Device::Ext(manager.get_data_device(&seat, &qh, ()))
+        } else if let Ok(manager) = globals.bind::<ZwlrDataControlManagerV1, _, _>(&qh, 1..=2, ()) {
+            info!("wayland clipboard: using wlr-data-control");
+            Device::Zwlr(manager.get_data_device(&seat, &qh, ()))
+        } else {
+            return Err("compositor supports neither ext-data-control nor wlr-data-control".into());
+        };
+
+        // 创建 device 后合成器会立即发送当前的 selection, 这里先消费掉, 与 X11 下只监听后续变化保持一致
+        let mut state = WatchState::default();
+        queue.roundtrip(&mut state)?;
+        if state.finished {
+            return Err("wayland data-control device was finished by the compositor".into());
+        }
+        state.changed = false;
+
+        Ok(Self {
+            conn,
+            queue,
+            state,
+            device,
+            handlers: Vec::new(),
+            running: Arc::new(AtomicBool::new(true)),
+        })
+    }
+
+    pub fn add_handler(&mut self, handler: T) -> &mut Self {
+        self.handlers.push(handler);
+        self
+    }
+
+    pub fn get_shutdown_channel(&self) -> WaylandWatcherShutdown {
+        WaylandWatcherShutdown {
+            conn: self.conn.clone(),
+            qh: self.queue.handle(),
+            running: self.running.clone(),
+        }
+    }
+
+    pub fn start_watch(&mut self) {
+        while self.running.load(Ordering::SeqCst) {
+            if let Err(e) = self.queue.blocking_dispatch(&mut self.state) {
+                error!("wayland clipboard dispatch failed: {}", e);
+                break;
+            }
+            if self.state.finished {
+                warn!("wayland data-control device finished, stop watching");
+                break;
+            }
+            if !std::mem::take(&mut self.state.changed) {
+                continue;
+            }
+            let Some(offer) = self.state.selection.clone() else {
+                continue;
+            };
+            let mime_types = self.state.mime_types.get(&offer.id()).cloned().unwrap_or_default();
+            match self.read_offer(&offer, &mime_types) {
+                Ok(Some(content)) => {
+                    for handler in self.handlers.iter_mut() {
+                        handler.on_wayland_change(content.clone());
+                    }
+                },
+                Ok(None) => debug!("wayland clipboard: no supported mime type in {:?}", mime_types),
+                Err(e) => error!("Failed to read wayland clipboard: {}", e),
+            }
+        }
+        self.device.destroy();
+        let _ = self.conn.flush();
+    }
+
+    fn read_offer(&self, offer: &Offer, mime_types: &[String]) -> io::Result<Option<WaylandContent>> {
+        let Some(mime_type) = pick_mime_type(mime_types) else {
+            return Ok(None);
+        };
+        let (mut reader, writer) = os_pipe::pipe()?;
+        offer.receive(mime_type.to_string(), writer.as_fd());
+        self.conn.flush().map_err(io::Error::other)?;
+        // 必须关闭本端的写端, 否则永远等不到 EOF
+        drop(writer);
+
+        let bytes = read_with_deadline(&mut reader, Instant::now() + READ_TIMEOUT)?;
+        Ok(Some(to_content(mime_type, bytes)))
+    }
+}
+
+// 用 poll 等待数据, 超过 deadline 时返回 TimedOut
+fn read_with_deadline(reader: &mut os_pipe::PipeReader, deadline: Instant) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "timed out reading wayland offer"));
+        }
+        let mut fd = libc::pollfd {
+            fd: reader.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout = remaining.as_millis().clamp(1, i32::MAX as u128) as libc::c_int;
+        match unsafe { libc::poll(&mut fd, 1, timeout) } {
+            -1 => {
+                let e = io::Error::last_os_error();
+                if e.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(e);
+            },
+            0 => continue,
+            _ => {},
+        }
+        match reader.read(&mut buf) {
+            Ok(0) => return Ok(bytes),
+            Ok(n) => bytes.extend_from_slice(&buf[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {},
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn pick_mime_type(mime_types: &[String]) -> Option<&'static str> {
+    let offered = |mime: &str| mime_types.iter().any(|m| m == mime);
+    if offered(URI_LIST_MIME) {
+        return Some(URI_LIST_MIME);
+    }
+    if offered(PNG_MIME) {
+        return Some(PNG_MIME);
+    }
+    TEXT_MIMES.into_iter().find(|mime| offered(mime))
+}
+
+fn to_content(mime_type: &str, bytes: Vec<u8>) -> WaylandContent {
+    match mime_type {
+        URI_LIST_MIME => WaylandContent::Files(parse_uri_list(&String::from_utf8_lossy(&bytes))),
+        PNG_MIME => WaylandContent::Image(bytes),
+        _ => WaylandContent::Text(String::from_utf8_lossy(&bytes).into_owned()),
+    }
+}
+
+fn parse_uri_list(uri_list: &str) -> Vec<String> {
+    uri_list
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("file://"))
+        .map(String::from)
+        .collect()
+}
+
+// 写入剪切板: wl-clipboard-rs 会在后台线程持有 selection, 直到被其他程序覆盖
+pub fn set_text(text: String) -> Result<(), Box<dyn Error>> {
+    Options::new().copy(Source::Bytes(text.into_bytes().into_boxed_slice()), MimeType::Text)?;
+    Ok(())
+}
+
+pub fn set_files(paths: Vec<String>) -> Result<(), Box<dyn Error>> {
+    Options::new().copy(uri_list_source(&paths), MimeType::Specific(URI_LIST_MIME.to_string()))?;
+    Ok(())
+}
+
+// 图片同时提供 png 内容和 uri-list, 多张图片时 png 只包括第一张
+pub fn set_image(paths: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let first = paths.first().ok_or("no image to set")?;
+    let png = read_png(first)?;
+    Options::new().copy_multi(vec![
+        MimeSource {
+            source: Source::Bytes(png.into_boxed_slice()),
+            mime_type: MimeType::Specific(PNG_MIME.to_string()),
+        },
+        MimeSource {
+            source: uri_list_source(&paths),
+            mime_type: MimeType::Specific(URI_LIST_MIME.to_string()),
+        },
+    ])?;
+    Ok(())
+}
+
+fn uri_list_source(paths: &[String]) -> Source {
+    let uri_list = paths
+        .iter()
+        .filter_map(|path| Url::from_file_path(path).ok())
+        .map(|url| url.to_string())
+        .collect::<Vec<_>>()
+        .join("\r\n");
+    Source::Bytes(uri_list.into_bytes().into_boxed_slice())
+}
+
+// 保存的图片一般已经是 png, 其他格式转换后再提供
+fn read_png(path: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let bytes = std::fs::read(path)?;
+    if image::guess_format(&bytes).ok() == Some(image::ImageFormat::Png) {
+        return Ok(bytes);
+    }
+    let mut png = Vec::new();
+    image::load_from_memory(&bytes)?.write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)?;
+    Ok(png)
+}
+
+impl Dispatch<WlRegistry, GlobalListContents> for WatchState {
+    fn event(_: &mut Self, _: &WlRegistry, _: <WlRegistry as Proxy>::Event, _: &GlobalListContents, _: &Connection, _: &QueueHandle<Self>) {}
+}
+
+impl Dispatch<WlSeat, ()> for WatchState {
+    fn event(_: &mut Self, _: &WlSeat, _: <WlSeat as Proxy>::Event, _: &(), _: &Connection, _: &QueueHandle<Self>) {}
+}
+
+impl Dispatch<WlCallback, ()> for WatchState {
+    fn event(_: &mut Self, _: &WlCallback, _: <WlCallback as Proxy>::Event, _: &(), _: &Connection, _: &QueueHandle<Self>) {}
+}
+
+impl Dispatch<ExtDataControlManagerV1, ()> for WatchState {
+    fn event(_: &mut Self, _: &ExtDataControlManagerV1, _: <ExtDataControlManagerV1 as Proxy>::Event, _: &(), _: &Connection, _: &QueueHandle<Self>) {
+    }
+}
+
+impl Dispatch<ZwlrDataControlManagerV1, ()> for WatchState {
+    fn event(
+        _: &mut Self,
+        _: &ZwlrDataControlManagerV1,
+        _: <ZwlrDataControlManagerV1 as Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ExtDataControlDeviceV1, ()> for WatchState {
+    fn event(state: &mut Self, _: &ExtDataControlDeviceV1, event: ext_data_control_device_v1::Event, _: &(), _: &Connection, _: &QueueHandle<Self>) {
+        match event {
+            ext_data_control_device_v1::Event::DataOffer { id } => {
+                state.mime_types.insert(id.id(), Vec::new());
+            },
+            ext_data_control_device_v1::Event::Selection { id } => state.set_selection(id.map(Offer::Ext)),
+            ext_data_control_device_v1::Event::PrimarySelection { id } => state.drop_offer(id.map(Offer::Ext)),
+            ext_data_control_device_v1::Event::Finished => state.finished = true,
+            _ => {},
+        }
+    }
+
+    event_created_child!(WatchState, ExtDataControlDeviceV1, [
+        ext_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ExtDataControlOfferV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrDataControlDeviceV1, ()> for WatchState {
+    fn event(
+        state: &mut Self,
+        _: &ZwlrDataControlDeviceV1,
+        event: zwlr_data_control_device_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_data_control_device_v1::Event::DataOffer { id } => {
+                state.mime_types.insert(id.id(), Vec::new());
+            },
+            zwlr_data_control_device_v1::Event::Selection { id } => state.set_selection(id.map(Offer::Zwlr)),
+            zwlr_data_control_device_v1::Event::PrimarySelection { id } => state.drop_offer(id.map(Offer::Zwlr)),
+            zwlr_data_control_device_v1::Event::Finished => state.finished = true,
+            _ => {},
+        }
+    }
+
+    event_created_child!(WatchState, ZwlrDataControlDeviceV1, [
+        zwlr_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ZwlrDataControlOfferV1, ()),
+    ]);
+}
+
+impl Dispatch<ExtDataControlOfferV1, ()> for WatchState {
+    fn event(
+        state: &mut Self,
+        offer: &ExtDataControlOfferV1,
+        event: ext_data_control_offer_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let ext_data_control_offer_v1::Event::Offer { mime_type } = event {
+            state.add_mime_type(offer.id(), mime_type);
+        }
+    }
+}
+
+impl Dispatch<ZwlrDataControlOfferV1, ()> for WatchState {
+    fn event(
+        state: &mut Self,
+        offer: &ZwlrDataControlOfferV1,
+        event: zwlr_data_control_offer_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let zwlr_data_control_offer_v1::Event::Offer { mime_type } = event {
+            state.add_mime_type(offer.id(), mime_type);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mimes(list: &[&str]) -> Vec<String> {
+        list.iter().map(|m| m.to_string()).collect()
+    }
+
+    #[test]
+    fn test_pick_mime_type() {
+        // 文件管理器复制文件时会同时提供 uri-list 和纯文本
+        let files = mimes(&["x-special/gnome-copied-files", "text/plain", "text/uri-list"]);
+        assert_eq!(pick_mime_type(&files), Some(URI_LIST_MIME));
+
+        let image = mimes(&["text/html", "image/png"]);
+        assert_eq!(pick_mime_type(&image), Some(PNG_MIME));
+
+        let text = mimes(&["TEXT", "text/plain", "text/plain;charset=utf-8"]);
+        assert_eq!(pick_mime_type(&text), Some("text/plain;charset=utf-8"));
+
+        assert_eq!(pick_mime_type(&mimes(&["application/x-unknown"])), None);
+    }
+
+    #[test]
+    fn test_parse_uri_list() {
+        let uri_list = "# comment\r\nfile:///home/user/a.txt\r\nfile:///home/user/b%20c.png\r\n\r\nhttps://example.com\r\n";
+        assert_eq!(
+            parse_uri_list(uri_list),
+            vec!["file:///home/user/a.txt".to_string(), "file:///home/user/b%20c.png".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_read_with_deadline() {
+        use std::io::Write;
+
+        let (mut reader, mut writer) = os_pipe::pipe().unwrap();
+        writer.write_all(b"content").unwrap();
+        drop(writer);
+        assert_eq!(read_with_deadline(&mut reader, Instant::now() + READ_TIMEOUT).unwrap(), b"content");
+
+        // 写端一直不关闭时超时返回
+        let (mut reader, _writer) = os_pipe::pipe().unwrap();
+        let started = Instant::now();
+        let err = read_with_deadline(&mut reader, started + Duration::from_millis(50)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+
+    // 需要支持 data-control 的合成器, 例如:
+    // WLR_BACKENDS=headless WLR_LIBINPUT_NO_DEVICES=1 sway &
+    // WAYLAND_DISPLAY=wayland-1 cargo test wayland -- --ignored
+    #[test]
+    #[ignore]
+    fn test_watch_headless_compositor() {
+        struct Collector(std::sync::mpsc::Sender<WaylandContent>);
+        impl WaylandClipboardHandler for Collector {
+            fn on_wayland_change(&mut self, content: WaylandContent) {
+                let _ = self.0.send(content);
+            }
+        }
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut watcher = WaylandWatcher::new().expect("compositor without data-control");
+        let shutdown = watcher.add_handler(Collector(sender)).get_shutdown_channel();
+        let handle = std::thread::spawn(move || watcher.start_watch());
+
+        set_text("SuperCV wayland test".to_string()).unwrap();
+        let content = receiver.recv_timeout(std::time::Duration::from_secs(5)).unwrap();
+        assert_eq!(content, WaylandContent::Text("SuperCV wayland test".to_string()));
+
+        shutdown.stop();
+        handle.join().unwrap();
+    }
+}
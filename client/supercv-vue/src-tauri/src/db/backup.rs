@@ -0,0 +1,372 @@
+// 数据库和文件目录的定期备份
+//
+// 每份备份是备份目录下的一个子目录:
+//   backup.json   { version, created_at, entries, files }
+//   db.sqlite     VACUUM INTO 得到的一致快照
+//   files/        files_path 的副本, 与上一份备份相同的文件使用硬链接, 不额外占用空间
+// 先写入 .tmp- 开头的临时目录, 完成后再重命名, 未完成的备份不会被列出或恢复
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::Local;
+use lazy_static::lazy_static;
+use log::{error, info, warn};
+use sea_orm::{ConnectionTrait, DatabaseConnection, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+use crate::db::connection::init_db_connection;
+use crate::db::crud::host_clipboard;
+use crate::error::{AppError, AppResult};
+use crate::utils::config::{BackupConfig, CONFIG};
+use crate::utils::time::get_current_timestamp;
+
+const MANIFEST_NAME: &str = "backup.json";
+const MANIFEST_VERSION: u32 = 1;
+const DB_NAME: &str = "db.sqlite";
+const FILES_DIR: &str = "files";
+const BACKUP_PREFIX: &str = "supercv-";
+const TMP_PREFIX: &str = ".tmp-";
+const CHECK_INTERVAL_SECS: u64 = 10 * 60;
+// 同步过程中的临时文件, 不需要备份
+const SKIPPED_DIRS: [&str; 2] = ["sync/upload", "sync/download"];
+
+lazy_static! {
+    static ref BACKUP_TASK: std::sync::Mutex<Option<JoinHandle<()>>> = std::sync::Mutex::new(None);
+    // 定时备份, 手动备份和恢复不能同时进行
+    static ref BACKUP_LOCK: Mutex<()> = Mutex::new(());
+}
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    created_at: i64,
+    entries: u64,
+    files: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BackupInfo {
+    pub name: String,
+    pub path: String,
+    pub created_at: i64,
+    pub entries: u64,
+    pub files: usize,
+    pub size: u64, // db.sqlite 的大小
+}
+
+impl BackupInfo {
+    pub fn db_file(&self) -> PathBuf {
+        Path::new(&self.path).join(DB_NAME)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RestoreReport {
+    pub backup: String,
+    pub entries: usize,
+    pub files: usize,
+    pub previous: String, // 恢复前自动备份的当前数据, 可以用来撤销这次恢复
+}
+
+// 按配置的间隔在后台备份, 已在运行时重新启动
+pub fn start(db: DatabaseConnection) {
+    let task = tokio::spawn(async move {
+        loop {
+            let config = CONFIG.read().unwrap().user_config.backup_config.clone();
+            if config.enabled && config.interval_hours > 0 {
+                match backup_if_due(&db, &config).await {
+                    Ok(Some(backup)) => info!("Backup created: {}", backup.path),
+                    Ok(None) => {},
+                    Err(e) => error!("Backup failed: {}", e),
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(CHECK_INTERVAL_SECS)).await;
+        }
+    });
+    if let Some(previous) = BACKUP_TASK.lock().unwrap().replace(task) {
+        previous.abort();
+    }
+}
+
+pub fn stop() {
+    if let Some(task) = BACKUP_TASK.lock().unwrap().take() {
+        task.abort();
+    }
+}
+
+// 距离最近一份备份超过间隔时才备份, 重启不会导致重复备份
+async fn backup_if_due(db: &DatabaseConnection, config: &BackupConfig) -> AppResult<Option<BackupInfo>> {
+    let dir = CONFIG.read().unwrap().backup_dir();
+    let latest = list_backups(&dir)?.first().map(|b| b.created_at);
+    if latest.is_some_and(|t| get_current_timestamp() - t < config.interval_hours as i64 * 60 * 60) {
+        return Ok(None);
+    }
+    Ok(Some(backup_now(db).await?))
+}
+
+// 立即备份并按配置删除多余的旧备份
+pub async fn backup_now(db: &DatabaseConnection) -> AppResult<BackupInfo> {
+    let (dir, files_path, config) = {
+        let config = CONFIG.read().unwrap();
+        (config.backup_dir(), config.files_path.clone(), config.user_config.backup_config.clone())
+    };
+    let _guard = BACKUP_LOCK.lock().await;
+    let backup = create_backup(db, &dir, config.include_files.then_some(files_path.as_path())).await?;
+    let removed = rotate(&dir, config.keep)?;
+    if removed > 0 {
+        info!("Removed {} old backups", removed);
+    }
+    Ok(backup)
+}
+
+pub fn list() -> AppResult<Vec<BackupInfo>> {
+    list_backups(&CONFIG.read().unwrap().backup_dir())
+}
+
+// backup 为备份名或备份目录的路径
+pub async fn restore(db: &DatabaseConnection, backup: &str) -> AppResult<RestoreReport> {
+    let (dir, files_path) = {
+        let config = CONFIG.read().unwrap();
+        (config.backup_dir(), config.files_path.clone())
+    };
+    let _guard = BACKUP_LOCK.lock().await;
+    restore_backup(db, &dir, backup, &files_path).await
+}
+
+pub async fn create_backup(db: &DatabaseConnection, dir: &Path, files_path: Option<&Path>) -> AppResult<BackupInfo> {
+    fs::create_dir_all(dir)?;
+    let stamp = Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let mut name = format!("{}{}", BACKUP_PREFIX, stamp);
+    let mut n = 1;
+    while dir.join(&name).exists() {
+        name = format!("{}{}-{}", BACKUP_PREFIX, stamp, n);
+        n += 1;
+    }
+
+    let tmp = dir.join(format!("{}{}", TMP_PREFIX, name));
+    let _ = fs::remove_dir_all(&tmp);
+    fs::create_dir_all(&tmp)?;
+    match write_backup(db, dir, &tmp, files_path).await {
+        Ok(_) => {
+            let path = dir.join(&name);
+            fs::rename(&tmp, &path)?;
+            read_backup(&path)
+        },
+        Err(e) => {
+            let _ = fs::remove_dir_all(&tmp);
+            Err(e)
+        },
+    }
+}
+
+async fn write_backup(db: &DatabaseConnection, dir: &Path, tmp: &Path, files_path: Option<&Path>) -> AppResult<()> {
+    let created_at = get_current_timestamp();
+    let db_file = tmp.join(DB_NAME).to_string_lossy().replace('\'', "''");
+    db.execute_unprepared(&format!("VACUUM INTO '{}'", db_file)).await?;
+    let entries = host_clipboard::count_clipboard_entries(db).await?;
+
+    let files = match files_path {
+        Some(files_path) if files_path.exists() => {
+            let previous = list_backups(dir)?.first().map(|b| Path::new(&b.path).join(FILES_DIR));
+            let mut skip: Vec<PathBuf> = SKIPPED_DIRS.iter().map(|d| files_path.join(d)).collect();
+            // 备份目录设置在文件目录中时不能把自己也复制进去
+            skip.push(dir.to_path_buf());
+            let (src, dst) = (files_path.to_path_buf(), tmp.join(FILES_DIR));
+            tokio::task::spawn_blocking(move || copy_tree(&src, &dst, previous.as_deref(), &skip))
+                .await
+                .map_err(|e| AppError::Internal(e.to_string()))??
+        },
+        _ => 0,
+    };
+
+    let manifest = Manifest {
+        version: MANIFEST_VERSION,
+        created_at,
+        entries,
+        files,
+    };
+    fs::write(tmp.join(MANIFEST_NAME), serde_json::to_vec_pretty(&manifest)?)?;
+    Ok(())
+}
+
+fn read_backup(path: &Path) -> AppResult<BackupInfo> {
+    let manifest: Manifest = serde_json::from_slice(&fs::read(path.join(MANIFEST_NAME))?)?;
+    Ok(BackupInfo {
+        name: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        path: path.to_string_lossy().to_string(),
+        created_at: manifest.created_at,
+        entries: manifest.entries,
+        files: manifest.files,
+        size: fs::metadata(path.join(DB_NAME)).map(|m| m.len()).unwrap_or(0),
+    })
+}
+
+// 从新到旧
+pub fn list_backups(dir: &Path) -> AppResult<Vec<BackupInfo>> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_backup = path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with(BACKUP_PREFIX));
+        if !is_backup || !path.join(MANIFEST_NAME).exists() {
+            continue;
+        }
+        match read_backup(&path) {
+            Ok(backup) => backups.push(backup),
+            Err(e) => warn!("Ignore invalid backup {}: {}", path.display(), e),
+        }
+    }
+    backups.sort_by(|a, b| (b.created_at, &b.name).cmp(&(a.created_at, &a.name)));
+    Ok(backups)
+}
+
+// 只保留最近 keep 份, 同时清理中断后留下的临时目录. keep 为 0 时不删除
+pub fn rotate(dir: &Path, keep: usize) -> AppResult<usize> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with(TMP_PREFIX)) {
+            let _ = fs::remove_dir_all(&path);
+        }
+    }
+    if keep == 0 {
+        return Ok(0);
+    }
+    let old = list_backups(dir)?.into_iter().skip(keep).collect::<Vec<_>>();
+    for backup in &old {
+        fs::remove_dir_all(&backup.path)?;
+    }
+    Ok(old.len())
+}
+
+// 恢复前先备份当前数据, 之后用备份中的记录替换全部记录, 文件复制回 files_path (不删除现有文件)
+pub async fn restore_backup(db: &DatabaseConnection, dir: &Path, backup: &str, files_path: &Path) -> AppResult<RestoreReport> {
+    let path = match Path::new(backup) {
+        p if p.is_absolute() => p.to_path_buf(),
+        _ => dir.join(backup),
+    };
+    if !path.join(MANIFEST_NAME).exists() || !path.join(DB_NAME).exists() {
+        return Err(AppError::NotFound(format!("backup {}", backup)));
+    }
+
+    // 在副本上执行迁移, 旧版本的备份也能读取
+    let copy = std::env::temp_dir().join(format!("supercv-restore-{}-{}.sqlite", std::process::id(), get_current_timestamp()));
+    fs::copy(path.join(DB_NAME), &copy)?;
+    let entries = async {
+        let backup_db = init_db_connection(copy.to_str()).await?;
+        let entries = host_clipboard::get_all_clipboard_entries(&backup_db).await?;
+        backup_db.close().await?;
+        Ok::<_, AppError>(entries)
+    }
+    .await;
+    let _ = fs::remove_file(&copy);
+    let entries = entries?;
+
+    let previous = create_backup(db, dir, Some(files_path)).await?;
+    let count = entries.len();
+    let txn = db.begin().await?;
+    host_clipboard::replace_all_clipboard_entries(&txn, entries).await?;
+    txn.commit().await?;
+
+    let files = match path.join(FILES_DIR) {
+        src if src.exists() => {
+            let dst = files_path.to_path_buf();
+            tokio::task::spawn_blocking(move || copy_tree(&src, &dst, None, &[]))
+                .await
+                .map_err(|e| AppError::Internal(e.to_string()))??
+        },
+        _ => 0,
+    };
+
+    Ok(RestoreReport {
+        backup: path.to_string_lossy().to_string(),
+        entries: count,
+        files,
+        previous: previous.name,
+    })
+}
+
+fn copy_tree(src: &Path, dst: &Path, previous: Option<&Path>, skip: &[PathBuf]) -> io::Result<usize> {
+    let mut count = 0;
+    let mut stack = vec![PathBuf::new()];
+    while let Some(rel) = stack.pop() {
+        fs::create_dir_all(dst.join(&rel))?;
+        for entry in fs::read_dir(src.join(&rel))? {
+            let entry = entry?;
+            let path = entry.path();
+            if skip.iter().any(|s| path.starts_with(s)) {
+                continue;
+            }
+            let rel = rel.join(entry.file_name());
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                stack.push(rel);
+            } else if file_type.is_file() {
+                let target = dst.join(&rel);
+                if !previous.is_some_and(|p| link_unchanged(&path, &p.join(&rel), &target)) {
+                    fs::copy(&path, &target)?;
+                }
+                count += 1;
+            }
+        }
+    }
+    Ok(count)
+}
+
+// 上一份备份中的副本晚于源文件的修改时间且大小相同时, 直接硬链接过去
+fn link_unchanged(src: &Path, previous: &Path, target: &Path) -> bool {
+    let (Ok(src_meta), Ok(prev_meta)) = (fs::metadata(src), fs::metadata(previous)) else {
+        return false;
+    };
+    let unchanged = src_meta.len() == prev_meta.len() && matches!((src_meta.modified(), prev_meta.modified()), (Ok(s), Ok(p)) if s <= p);
+    unchanged && fs::hard_link(previous, target).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::pasteboard::{ContentType, PasteboardContent};
+    use crate::db::testing::temp_db;
+    use crate::utils::hash::hash_str;
+
+    fn text_item(text: &str) -> PasteboardContent {
+        PasteboardContent::new(text.to_string(), ContentType::Text, hash_str(text), None)
+    }
+
+    #[tokio::test]
+    async fn test_backup_rotate_and_restore() {
+        let (db, dir) = temp_db("backup").await;
+        let (backups, files) = (dir.join("backups"), dir.join("files"));
+        fs::create_dir_all(files.join("sync/upload")).unwrap();
+        fs::write(files.join("a.png"), b"image").unwrap();
+        fs::write(files.join("sync/upload/tmp.bin"), b"temp").unwrap();
+        host_clipboard::add_clipboard_entry(&db, text_item("first")).await.unwrap();
+
+        let first = create_backup(&db, &backups, Some(&files)).await.unwrap();
+        assert_eq!((first.entries, first.files), (1, 1));
+        assert!(!Path::new(&first.path).join("files/sync/upload/tmp.bin").exists());
+
+        host_clipboard::add_clipboard_entry(&db, text_item("second")).await.unwrap();
+        let second = create_backup(&db, &backups, Some(&files)).await.unwrap();
+        assert_eq!(second.entries, 2);
+        assert_eq!(list_backups(&backups).unwrap()[0].name, second.name);
+
+        fs::remove_file(files.join("a.png")).unwrap();
+        let report = restore_backup(&db, &backups, &first.name, &files).await.unwrap();
+        assert_eq!((report.entries, report.files), (1, 1));
+        let entries = host_clipboard::get_all_clipboard_entries(&db).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].content, "first");
+        assert_eq!(fs::read(files.join("a.png")).unwrap(), b"image");
+
+        // 恢复前的数据也有一份备份
+        assert_eq!(list_backups(&backups).unwrap()[0].name, report.previous);
+        assert_eq!(rotate(&backups, 1).unwrap(), 2);
+        assert_eq!(list_backups(&backups).unwrap().len(), 1);
+    }
+}
@@ -1,12 +1,20 @@
 use std::path::Path;
 
-use log::info;
+use std::time::Duration;
+
+use log::{info, LevelFilter};
 use migration::Migrator;
 use migration::MigratorTrait;
-use sea_orm::{Database, DatabaseConnection, DbErr};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, RuntimeErr, SqlxSqliteConnector, Statement};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
+use sqlx::ConnectOptions;
 
 use crate::utils::config::CONFIG;
 
+// 写入在 SQLite 中仍是串行的, 连接数只影响并发读取
+const MAX_CONNECTIONS: u32 = 8;
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
 pub async fn init_db_connection(path: Option<&str>) -> Result<DatabaseConnection, DbErr> {
     let db_path = if let Some(p) = path {
         Path::new(p).to_path_buf()
@@ -14,8 +22,16 @@ pub async fn init_db_connection(path: Option<&str>) -> Result<DatabaseConnection
         CONFIG.read().unwrap().db_path.join("db.sqlite")
     };
 
-    let db_url = format!("sqlite:{}", db_path.display());
-    info!("{}", &db_url);
+    let db = connect(&db_path).await?;
+    Migrator::up(&db, None).await?;
+    Ok(db)
+}
+
+// 只建立连接, 不执行迁移. 文件不存在时创建空文件
+//
+// WAL 模式下读不阻塞写, 写入之间由 busy_timeout 等待, 多个连接可以并发查询
+pub(crate) async fn connect(db_path: &Path) -> Result<DatabaseConnection, DbErr> {
+    info!("sqlite:{}", db_path.display());
     if !db_path.exists() {
         if let Some(parent) = db_path.parent() {
             tokio::fs::create_dir_all(parent)
@@ -27,16 +43,133 @@ pub async fn init_db_connection(path: Option<&str>) -> Result<DatabaseConnection
             .await
             .map_err(|e| DbErr::Custom(format!("Failed to create file: {}", e)))?;
     }
-    establish_connection(&db_url).await
+    let options = SqliteConnectOptions::new()
+        .filename(db_path)
+        .journal_mode(SqliteJournalMode::Wal)
+        .synchronous(SqliteSynchronous::Normal)
+        .busy_timeout(BUSY_TIMEOUT)
+        .log_statements(LevelFilter::Info);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(MAX_CONNECTIONS)
+        .connect_with(options)
+        .await
+        .map_err(|e| DbErr::Conn(RuntimeErr::SqlxError(e)))?;
+    Ok(SqlxSqliteConnector::from_sqlx_sqlite_pool(pool))
+}
+
+// PRAGMA integrity_check 没有问题时只返回一行 ok, 否则返回问题列表
+pub(crate) async fn check_integrity(db: &DatabaseConnection) -> Result<Vec<String>, DbErr> {
+    let rows = db
+        .query_all(Statement::from_string(db.get_database_backend(), "PRAGMA integrity_check"))
+        .await?;
+    let problems = rows.iter().map(|row| row.try_get_by_index::<String>(0)).collect::<Result<Vec<_>, _>>()?;
+    Ok(problems.into_iter().filter(|p| p != "ok").collect())
 }
 
-async fn establish_connection(database_url: &str) -> Result<DatabaseConnection, DbErr> {
-    let db = Database::connect(database_url).await;
-    match db {
-        Ok(conn) => {
-            Migrator::up(&conn, None).await?;
-            Ok(conn)
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::core::pasteboard::{ContentType, PasteboardContent};
+    use crate::db::crud::host_clipboard;
+    use crate::db::testing::temp_db;
+    use crate::utils::hash::hash_str;
+
+    fn text_item(text: String) -> PasteboardContent {
+        let hash = hash_str(&text);
+        PasteboardContent::new(text, ContentType::Text, hash, None)
+    }
+
+    async fn timed_search(db: &DatabaseConnection) -> Result<Duration, DbErr> {
+        let start = Instant::now();
+        host_clipboard::get_clipboards_by_type_list(db, Some("entry 1"), Some(50), None, None).await?;
+        Ok(start.elapsed())
+    }
+
+    fn p95(mut samples: Vec<Duration>) -> Duration {
+        samples.sort();
+        samples[(samples.len() - 1) * 95 / 100]
+    }
+
+    // 写入和搜索使用连接池中不同的连接, 持续写入时搜索和写入都不会因为 SQLITE_BUSY 失败, 搜索的延迟也不会明显变长
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_search_latency_during_inserts() {
+        const BATCHES: usize = 10;
+        let (db, _dir) = temp_db("wal").await;
+        let mode = db
+            .query_one(Statement::from_string(db.get_database_backend(), "PRAGMA journal_mode"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(mode.try_get_by_index::<String>(0).unwrap(), "wal");
+
+        for chunk in 0..20 {
+            let items = (0..100).map(|i| text_item(format!("entry {}", chunk * 100 + i))).collect();
+            host_clipboard::add_clipboard_entries(&db, items).await.unwrap();
         }
-        Err(e) => Err(e),
+
+        let running = Arc::new(AtomicBool::new(true));
+        let writers: Vec<_> = (0..2)
+            .map(|writer| {
+                let db = db.clone();
+                tokio::spawn(async move {
+                    for batch in 0..BATCHES {
+                        let items = (0..200).map(|i| text_item(format!("capture {} {} {}", writer, batch, i))).collect();
+                        host_clipboard::add_clipboard_entries(&db, items).await?;
+                    }
+                    Ok::<_, DbErr>(())
+                })
+            })
+            .collect();
+        let readers: Vec<_> = (0..2)
+            .map(|_| {
+                let (db, running) = (db.clone(), running.clone());
+                tokio::spawn(async move {
+                    let mut samples = Vec::new();
+                    loop {
+                        samples.push(timed_search(&db).await?);
+                        if !running.load(Ordering::Relaxed) {
+                            return Ok::<_, DbErr>(samples);
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for writer in writers {
+            let result = writer.await.unwrap();
+            assert!(result.is_ok(), "insert failed: {:?}", result);
+        }
+        running.store(false, Ordering::Relaxed);
+        let mut busy = Vec::new();
+        for reader in readers {
+            let result = reader.await.unwrap();
+            assert!(matches!(&result, Ok(samples) if !samples.is_empty()), "search failed: {:?}", result.err());
+            busy.extend(result.unwrap());
+        }
+
+        // 写入结束后, 在同样大小的表上测没有写入时的延迟. 写入时搜索不等待写锁, p95 只因为争用 CPU 有限地变长,
+        // 上限放得很宽, 避免 CI 上偶发失败
+        let mut idle = Vec::new();
+        for _ in 0..50 {
+            idle.push(timed_search(&db).await.unwrap());
+        }
+        let (idle, busy) = (p95(idle), p95(busy));
+        println!("search p95: idle {:?}, during inserts {:?}", idle, busy);
+        assert!(
+            busy <= idle * 5 + Duration::from_millis(50),
+            "search p95 {:?} during inserts, {:?} when idle",
+            busy,
+            idle
+        );
+
+        // 所有写入都落盘
+        assert_eq!(
+            host_clipboard::count_clipboard_entries(&db).await.unwrap(),
+            (2000 + 2 * BATCHES * 200) as u64
+        );
     }
 }
@@ -0,0 +1,64 @@
+use sea_orm::ActiveValue::Set;
+use sea_orm::*;
+
+use crate::db::entities::prelude::ClipboardRevision;
+use crate::db::entities::{clipboard_revision, host_clipboard};
+
+// 保存记录当前的内容, 在修改记录之前调用
+pub async fn add_revision<C: ConnectionTrait>(db: &C, entry: &host_clipboard::Model, now: i64) -> Result<(), DbErr> {
+    ClipboardRevision::insert(clipboard_revision::ActiveModel {
+        clipboard_id: Set(entry.id),
+        content: Set(entry.content.clone()),
+        hash: Set(entry.hash.clone()),
+        title: Set(entry.title.clone()),
+        note: Set(entry.note.clone()),
+        created_at: Set(now),
+        ..Default::default()
+    })
+    .exec_without_returning(db)
+    .await?;
+    Ok(())
+}
+
+// 最新的在前
+pub async fn get_revisions<C: ConnectionTrait>(db: &C, clipboard_id: i32) -> Result<Vec<clipboard_revision::Model>, DbErr> {
+    ClipboardRevision::find()
+        .filter(clipboard_revision::Column::ClipboardId.eq(clipboard_id))
+        .order_by_desc(clipboard_revision::Column::Id)
+        .all(db)
+        .await
+}
+
+pub async fn get_latest_revision<C: ConnectionTrait>(db: &C, clipboard_id: i32) -> Result<Option<clipboard_revision::Model>, DbErr> {
+    ClipboardRevision::find()
+        .filter(clipboard_revision::Column::ClipboardId.eq(clipboard_id))
+        .order_by_desc(clipboard_revision::Column::Id)
+        .one(db)
+        .await
+}
+
+pub async fn delete_revision<C: ConnectionTrait>(db: &C, id: i32) -> Result<(), DbErr> {
+    ClipboardRevision::delete_by_id(id).exec(db).await?;
+    Ok(())
+}
+
+// 每条记录只保留最近 keep 个版本
+pub async fn prune_revisions<C: ConnectionTrait>(db: &C, clipboard_id: i32, keep: u64) -> Result<(), DbErr> {
+    // sqlite 的 OFFSET 必须和 LIMIT 一起使用, 版本数很少, 直接在这里跳过
+    let ids: Vec<i32> = ClipboardRevision::find()
+        .select_only()
+        .column(clipboard_revision::Column::Id)
+        .filter(clipboard_revision::Column::ClipboardId.eq(clipboard_id))
+        .order_by_desc(clipboard_revision::Column::Id)
+        .into_tuple()
+        .all(db)
+        .await?;
+    let stale: Vec<i32> = ids.into_iter().skip(keep as usize).collect();
+    if !stale.is_empty() {
+        ClipboardRevision::delete_many()
+            .filter(clipboard_revision::Column::Id.is_in(stale))
+            .exec(db)
+            .await?;
+    }
+    Ok(())
+}
@@ -1,14 +1,18 @@
 use sea_orm::*;
 use sea_orm::ActiveValue::Set;
 use sea_orm::sea_query::{Expr, OnConflict};
+use serde::{Deserialize, Serialize};
 
 use crate::core::pasteboard::PasteboardContent;
 use crate::db::entities::host_clipboard::{self, Entity as ClipboardEntries};
 use crate::db::entities::prelude::HostClipboard;
 use crate::utils::config::CONFIG;
 
-pub async fn add_clipboard_entry(
-    db: &DatabaseConnection,
+// 每页最多返回的记录数
+pub const MAX_PAGE: u64 = 500;
+
+pub async fn add_clipboard_entry<C: ConnectionTrait>(
+    db: &C,
     item: PasteboardContent,
 ) -> Result<host_clipboard::Model, DbErr> {
     let timestamp = item.date_time.timestamp();
@@ -20,11 +24,18 @@ pub async fn add_clipboard_entry(
         content: Set(item.text_content),
         timestamp: Set(timestamp),
         hash: Set(item.hash.clone()),
+        source: Set(item.source.to_i32()),
+        app_name: Set(item.app.app_name),
+        process_name: Set(item.app.process_name),
+        window_title: Set(item.app.window_title),
         ..Default::default()
     })
     .on_conflict(
         OnConflict::column(host_clipboard::Column::Hash)
             .update_column(host_clipboard::Column::Timestamp)
+            .value(host_clipboard::Column::CopyCount, Expr::col(host_clipboard::Column::CopyCount).add(1))
+            // 再次复制已删除的内容时恢复该记录
+            .value(host_clipboard::Column::DeletedAt, Expr::val(Option::<i64>::None))
             .to_owned(),
     )
     .exec(db)
@@ -40,19 +51,62 @@ pub async fn add_clipboard_entry(
         ))
 }
 
+// 在一个事务中批量写入, 任意一条失败则整体回滚
+pub async fn add_clipboard_entries(
+    db: &DatabaseConnection,
+    items: Vec<PasteboardContent>,
+) -> Result<Vec<host_clipboard::Model>, DbErr> {
+    let txn = db.begin().await?;
+    let mut entries = Vec::with_capacity(items.len());
+    for item in items {
+        entries.push(add_clipboard_entry(&txn, item).await?);
+    }
+    txn.commit().await?;
+    Ok(entries)
+}
+
+// 写入同步下载的记录. 本地已删除, 还在撤销窗口内的记录不恢复, 也不增加 copy_count, 返回 None
+pub async fn add_synced_clipboard_entry(db: &DatabaseConnection, item: PasteboardContent) -> Result<Option<host_clipboard::Model>, DbErr> {
+    let txn = db.begin().await?;
+    if let Some(existing) = get_clipboard_entry_by_hash_including_deleted(&txn, &item.hash).await? {
+        if existing.deleted_at.is_some() {
+            return Ok(None);
+        }
+    }
+    let entry = add_clipboard_entry(&txn, item).await?;
+    txn.commit().await?;
+    Ok(Some(entry))
+}
+
 pub async fn get_clipboards_by_type_list(
     db: &DatabaseConnection,
     text: Option<&str>,
     num: Option<u64>,
     type_list: Option<Vec<i32>>,
+    app: Option<&str>,
 ) -> Result<Vec<host_clipboard::Model>, DbErr> {
+    let mut query = visible_clipboards(text, type_list, app);
+
+    if let Some(num) = num {
+        query = query.limit(num);
+    }
+
+    // 按时间戳降序排序并限制结果数量
+    query = query.order_by_desc(host_clipboard::Column::Timestamp);
+
+    query.all(db).await
+}
+
+// 未过期且符合过滤条件的记录
+fn visible_clipboards(text: Option<&str>, type_list: Option<Vec<i32>>, app: Option<&str>) -> Select<HostClipboard> {
     let (text_ts, img_ts, file_ts) = {
         let config = CONFIG.read().unwrap(); // 获取读锁
         let (text_ts, img_ts, file_ts) = config.get_expired_ts();
         (text_ts, img_ts, file_ts) // 将值返回给外部变量
     };
 
-    let mut query = HostClipboard::find();
+    // 不包括已删除的记录
+    let mut query = HostClipboard::find().filter(host_clipboard::Column::DeletedAt.is_null());
 
     // 根据不同的类型指定不同的时间戳
     query = query.filter(
@@ -71,17 +125,30 @@ pub async fn get_clipboards_by_type_list(
                 Expr::col(host_clipboard::Column::Type)
                     .eq(2)
                     .and(host_clipboard::Column::Timestamp.gt(file_ts)),
-            ),
+            )
+            // 置顶的记录不受过期时间限制
+            .add(host_clipboard::Column::Pinned.eq(true)),
     );
 
+    // 同时匹配内容, 标题和备注
     if let Some(text) = text {
+        let pattern = format!("%{}%", text); // 直接使用原始文本进行模糊匹配
         query = query.filter(
-            Expr::cust("LOWER(content)").like(format!("%{}%", text)), // 直接使用原始文本进行模糊匹配
+            Condition::any()
+                .add(Expr::cust("LOWER(content)").like(pattern.clone()))
+                .add(Expr::cust("LOWER(title)").like(pattern.clone()))
+                .add(Expr::cust("LOWER(note)").like(pattern)),
         );
     }
 
-    if let Some(num) = num {
-        query = query.limit(num);
+    // 按来源应用过滤, 同时匹配应用名和进程名
+    if let Some(app) = app.filter(|app| !app.is_empty()) {
+        let pattern = format!("%{}%", app.to_lowercase());
+        query = query.filter(
+            Condition::any()
+                .add(Expr::cust("LOWER(app_name)").like(pattern.clone()))
+                .add(Expr::cust("LOWER(process_name)").like(pattern)),
+        );
     }
 
     // 如果提供了type_list，则添加类型过滤
@@ -89,24 +156,133 @@ pub async fn get_clipboards_by_type_list(
         query = query.filter(host_clipboard::Column::Type.is_in(type_list));
     }
 
-    // 按时间戳降序排序并限制结果数量
-    query = query.order_by_desc(host_clipboard::Column::Timestamp);
+    query
+}
 
-    query.all(db).await
+// 翻页位置, 指向上一页的最后一条记录
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Cursor {
+    pub timestamp: i64,
+    pub id: i32,
 }
 
-pub async fn _get_clipboard_entries_by_gt_timestamp(
+#[derive(Serialize, Debug, Clone)]
+pub struct ClipboardPage {
+    pub entries: Vec<host_clipboard::Model>,
+    pub next_cursor: Option<Cursor>, // 没有更多记录时为 None
+    pub total: u64,                  // 符合条件的记录总数, 不受 cursor 影响
+}
+
+// 按 (timestamp, id) 倒序的 keyset 分页, 时间相同的记录按 id 区分, 翻到深处也不需要 OFFSET
+// limit 限制在 1..=MAX_PAGE
+pub async fn get_clipboard_page(
+    db: &DatabaseConnection,
+    text: Option<&str>,
+    type_list: Option<Vec<i32>>,
+    app: Option<&str>,
+    cursor: Option<Cursor>,
+    limit: u64,
+) -> Result<ClipboardPage, DbErr> {
+    let limit = limit.clamp(1, MAX_PAGE);
+    let query = visible_clipboards(text, type_list, app);
+    let total = query.clone().count(db).await?;
+
+    let mut page = query;
+    if let Some(cursor) = cursor {
+        page = page.filter(
+            Condition::any().add(host_clipboard::Column::Timestamp.lt(cursor.timestamp)).add(
+                host_clipboard::Column::Timestamp
+                    .eq(cursor.timestamp)
+                    .and(host_clipboard::Column::Id.lt(cursor.id)),
+            ),
+        );
+    }
+    // 多取一条判断是否还有下一页
+    let mut entries = page
+        .order_by_desc(host_clipboard::Column::Timestamp)
+        .order_by_desc(host_clipboard::Column::Id)
+        .limit(limit + 1)
+        .all(db)
+        .await?;
+    let next_cursor = if entries.len() as u64 > limit {
+        entries.truncate(limit as usize);
+        entries.last().map(|entry| Cursor {
+            timestamp: entry.timestamp,
+            id: entry.id,
+        })
+    } else {
+        None
+    };
+
+    Ok(ClipboardPage { entries, next_cursor, total })
+}
+
+// 所有出现过的来源应用, 最近使用的在前
+pub async fn get_source_apps(db: &DatabaseConnection) -> Result<Vec<String>, DbErr> {
+    HostClipboard::find()
+        .select_only()
+        .column(host_clipboard::Column::AppName)
+        .filter(host_clipboard::Column::AppName.ne(""))
+        .filter(host_clipboard::Column::DeletedAt.is_null())
+        .group_by(host_clipboard::Column::AppName)
+        .order_by_desc(Expr::cust("MAX(timestamp)"))
+        .into_tuple::<String>()
+        .all(db)
+        .await
+}
+
+pub async fn get_clipboard_entries_by_gt_timestamp(
     db: &DatabaseConnection,
     timestamp: i64,
 ) -> Result<Vec<host_clipboard::Model>, DbErr> {
     let query = HostClipboard::find()
         .filter(host_clipboard::Column::Timestamp.gt(timestamp))
+        .filter(host_clipboard::Column::DeletedAt.is_null())
         .order_by_desc(host_clipboard::Column::Timestamp);
 
     query.all(db).await
 }
 
-pub async fn _get_clipboard_entries_by_id_list(
+// (start, end] 区间内的记录, 按时间升序
+pub async fn get_clipboard_entries_in_range(
+    db: &DatabaseConnection,
+    start: i64,
+    end: i64,
+) -> Result<Vec<host_clipboard::Model>, DbErr> {
+    HostClipboard::find()
+        .filter(host_clipboard::Column::Timestamp.gt(start))
+        .filter(host_clipboard::Column::Timestamp.lte(end))
+        .filter(host_clipboard::Column::DeletedAt.is_null())
+        .order_by_asc(host_clipboard::Column::Timestamp)
+        .all(db)
+        .await
+}
+
+pub async fn get_earliest_clipboard_timestamp(db: &DatabaseConnection) -> Result<Option<i64>, DbErr> {
+    HostClipboard::find()
+        .select_only()
+        .column_as(host_clipboard::Column::Timestamp.min(), "timestamp")
+        .into_tuple::<Option<i64>>()
+        .one(db)
+        .await
+        .map(Option::flatten)
+}
+
+// 全部未删除的记录 (包括已过期的), 按时间升序
+pub async fn get_all_clipboard_entries(db: &DatabaseConnection) -> Result<Vec<host_clipboard::Model>, DbErr> {
+    HostClipboard::find()
+        .filter(host_clipboard::Column::DeletedAt.is_null())
+        .order_by_asc(host_clipboard::Column::Timestamp)
+        .order_by_asc(host_clipboard::Column::Id)
+        .all(db)
+        .await
+}
+
+pub async fn count_clipboard_entries<C: ConnectionTrait>(db: &C) -> Result<u64, DbErr> {
+    HostClipboard::find().count(db).await
+}
+
+pub async fn get_clipboard_entries_by_id_list(
     db: &DatabaseConnection,
     id_list: Option<Vec<i32>>,
 ) -> Result<Vec<host_clipboard::Model>, DbErr> {
@@ -114,7 +290,8 @@ pub async fn _get_clipboard_entries_by_id_list(
         Some(ids) if !ids.is_empty() => {
             HostClipboard::find()
                 .filter(host_clipboard::Column::Id.is_in(ids))
-                .order_by_desc(host_clipboard::Column::Timestamp)
+                .filter(host_clipboard::Column::DeletedAt.is_null())
+                .order_by_asc(host_clipboard::Column::Timestamp)
                 .all(db)
                 .await
         }
@@ -122,24 +299,299 @@ pub async fn _get_clipboard_entries_by_id_list(
     }
 }
 
-// pub async fn update_clipboard_entry(
-//     db: &DatabaseConnection,
-//     id: i32,
-//     content: String,
-// ) -> Result<host_clipboard::Model, DbErr> {
-//     let entry = ClipboardEntries::find_by_id(id).one(db).await?;
-//     if let Some(entry) = entry {
-//         let mut entry: clipboard::ActiveModel = entry.into();
-//         entry.content = Set(content);
-//         entry.update(db).await
-//     } else {
-//         Err(DbErr::Custom("Entry not found".to_string()))
-//     }
-// }
+pub async fn get_clipboard_entry<C: ConnectionTrait>(db: &C, id: i32) -> Result<Option<host_clipboard::Model>, DbErr> {
+    HostClipboard::find_by_id(id)
+        .filter(host_clipboard::Column::DeletedAt.is_null())
+        .one(db)
+        .await
+}
+
+pub async fn get_clipboard_entry_by_hash<C: ConnectionTrait>(db: &C, hash: &str) -> Result<Option<host_clipboard::Model>, DbErr> {
+    HostClipboard::find()
+        .filter(host_clipboard::Column::Hash.eq(hash))
+        .filter(host_clipboard::Column::DeletedAt.is_null())
+        .one(db)
+        .await
+}
 
-pub async fn _delete_clipboard_entry(
-    db: &DatabaseConnection,
+// 包括已删除但还没有清除的记录, 写入前检查 hash 唯一索引时使用
+pub async fn get_clipboard_entry_by_hash_including_deleted<C: ConnectionTrait>(db: &C, hash: &str) -> Result<Option<host_clipboard::Model>, DbErr> {
+    HostClipboard::find().filter(host_clipboard::Column::Hash.eq(hash)).one(db).await
+}
+
+pub async fn set_clipboard_pinned(db: &DatabaseConnection, id: i32, pinned: bool) -> Result<host_clipboard::Model, DbErr> {
+    let entry = ClipboardEntries::find_by_id(id)
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotFound(format!("clipboard entry {}", id)))?;
+    let mut entry: host_clipboard::ActiveModel = entry.into();
+    entry.pinned = Set(pinned);
+    entry.update(db).await
+}
+
+// 从备份恢复时用备份中的记录 (包括 id) 替换全部记录, 需要在事务中调用
+pub async fn replace_all_clipboard_entries<C: ConnectionTrait>(db: &C, entries: Vec<host_clipboard::Model>) -> Result<(), DbErr> {
+    ClipboardEntries::delete_many().exec(db).await?;
+    for chunk in entries.chunks(500) {
+        let models = chunk.iter().cloned().map(|entry| host_clipboard::ActiveModel {
+            id: Set(entry.id),
+            r#type: Set(entry.r#type),
+            path: Set(entry.path),
+            content: Set(entry.content),
+            timestamp: Set(entry.timestamp),
+            hash: Set(entry.hash),
+            source: Set(entry.source),
+            app_name: Set(entry.app_name),
+            process_name: Set(entry.process_name),
+            window_title: Set(entry.window_title),
+            pinned: Set(entry.pinned),
+            copy_count: Set(entry.copy_count),
+            title: Set(entry.title),
+            note: Set(entry.note),
+            deleted_at: Set(entry.deleted_at),
+        });
+        ClipboardEntries::insert_many(models).exec_without_returning(db).await?;
+    }
+    Ok(())
+}
+
+// 迁移其他应用的记录, 依靠 hash 唯一索引去重, 已存在时不写入并返回 false
+pub async fn insert_clipboard_entry_if_absent<C: ConnectionTrait>(db: &C, entry: host_clipboard::Model) -> Result<bool, DbErr> {
+    let rows = ClipboardEntries::insert(host_clipboard::ActiveModel {
+        r#type: Set(entry.r#type),
+        path: Set(entry.path),
+        content: Set(entry.content),
+        timestamp: Set(entry.timestamp),
+        hash: Set(entry.hash),
+        source: Set(entry.source),
+        app_name: Set(entry.app_name),
+        process_name: Set(entry.process_name),
+        window_title: Set(entry.window_title),
+        pinned: Set(entry.pinned),
+        copy_count: Set(entry.copy_count),
+        title: Set(entry.title),
+        note: Set(entry.note),
+        ..Default::default()
+    })
+    .on_conflict(OnConflict::column(host_clipboard::Column::Hash).do_nothing().to_owned())
+    .exec_without_returning(db)
+    .await?;
+    Ok(rows > 0)
+}
+
+// 写入导入的记录, existing 为本地 hash 相同的记录 id, 有则覆盖该记录
+pub async fn import_clipboard_entry<C: ConnectionTrait>(
+    db: &C,
+    entry: host_clipboard::Model,
+    existing: Option<i32>,
+) -> Result<host_clipboard::Model, DbErr> {
+    let mut active = host_clipboard::ActiveModel {
+        r#type: Set(entry.r#type),
+        path: Set(entry.path),
+        content: Set(entry.content),
+        timestamp: Set(entry.timestamp),
+        hash: Set(entry.hash),
+        source: Set(entry.source),
+        app_name: Set(entry.app_name),
+        process_name: Set(entry.process_name),
+        window_title: Set(entry.window_title),
+        pinned: Set(entry.pinned),
+        copy_count: Set(entry.copy_count),
+        title: Set(entry.title),
+        note: Set(entry.note),
+        deleted_at: Set(None),
+        ..Default::default()
+    };
+    match existing {
+        Some(id) => {
+            active.id = ActiveValue::Unchanged(id);
+            active.update(db).await
+        },
+        None => active.insert(db).await,
+    }
+}
+
+// 修改记录的内容和标题备注, 调用方需要先确认新的 hash 没有被其他记录使用
+pub async fn update_clipboard_entry<C: ConnectionTrait>(
+    db: &C,
     id: i32,
+    content: String,
+    hash: String,
+    title: String,
+    note: String,
+) -> Result<host_clipboard::Model, DbErr> {
+    host_clipboard::ActiveModel {
+        id: ActiveValue::Unchanged(id),
+        content: Set(content),
+        hash: Set(hash),
+        title: Set(title),
+        note: Set(note),
+        ..Default::default()
+    }
+    .update(db)
+    .await
+}
+
+pub async fn delete_clipboard_entries<C: ConnectionTrait>(
+    db: &C,
+    ids: Vec<i32>,
 ) -> Result<DeleteResult, DbErr> {
-    ClipboardEntries::delete_by_id(id).exec(db).await
+    ClipboardEntries::delete_many()
+        .filter(host_clipboard::Column::Id.is_in(ids))
+        .exec(db)
+        .await
+}
+
+// 标记删除, 返回新标记的条数. deleted_at 同时用来标识这一次删除
+pub async fn soft_delete_clipboard_entries(db: &DatabaseConnection, ids: Vec<i32>, deleted_at: i64) -> Result<u64, DbErr> {
+    let result = ClipboardEntries::update_many()
+        .col_expr(host_clipboard::Column::DeletedAt, Expr::value(deleted_at))
+        .filter(host_clipboard::Column::Id.is_in(ids))
+        .filter(host_clipboard::Column::DeletedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+// 按类型和时间 [start, end) 标记删除, 条件都为空时删除全部记录
+pub async fn soft_delete_clipboards_by_filter(
+    db: &DatabaseConnection,
+    type_list: Option<Vec<i32>>,
+    start: Option<i64>,
+    end: Option<i64>,
+    include_pinned: bool,
+    deleted_at: i64,
+) -> Result<u64, DbErr> {
+    let mut query = ClipboardEntries::update_many()
+        .col_expr(host_clipboard::Column::DeletedAt, Expr::value(deleted_at))
+        .filter(host_clipboard::Column::DeletedAt.is_null());
+    if let Some(type_list) = type_list {
+        query = query.filter(host_clipboard::Column::Type.is_in(type_list));
+    }
+    if let Some(start) = start {
+        query = query.filter(host_clipboard::Column::Timestamp.gte(start));
+    }
+    if let Some(end) = end {
+        query = query.filter(host_clipboard::Column::Timestamp.lt(end));
+    }
+    if !include_pinned {
+        query = query.filter(host_clipboard::Column::Pinned.eq(false));
+    }
+    Ok(query.exec(db).await?.rows_affected)
+}
+
+// 撤销一次删除, 已经被清除的记录无法恢复
+pub async fn restore_deleted_clipboard_entries(db: &DatabaseConnection, deleted_at: i64) -> Result<u64, DbErr> {
+    let result = ClipboardEntries::update_many()
+        .col_expr(host_clipboard::Column::DeletedAt, Expr::val(Option::<i64>::None).into())
+        .filter(host_clipboard::Column::DeletedAt.eq(deleted_at))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+// 清除在 before 之前删除, 已经过了撤销窗口的记录, 返回实际清除的记录.
+// 判断和删除在同一条语句中, 期间被撤销或重新复制的记录不会被清除
+pub async fn purge_deleted_clipboard_entries(db: &DatabaseConnection, before: i64) -> Result<Vec<host_clipboard::Model>, DbErr> {
+    HostClipboard::find()
+        .from_raw_sql(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            "DELETE FROM host_clipboard WHERE deleted_at IS NOT NULL AND deleted_at <= ? RETURNING *",
+            [before.into()],
+        ))
+        .all(db)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::pasteboard::{ContentType, SourceApp};
+    use crate::db::testing::temp_db;
+
+    fn text_item(text: &str, app_name: &str, process_name: &str) -> PasteboardContent {
+        let mut item = PasteboardContent::new(text.to_string(), ContentType::Text, crate::utils::hash::hash_str(text), None);
+        item.app = SourceApp {
+            app_name: app_name.to_string(),
+            process_name: process_name.to_string(),
+            window_title: format!("{} window", app_name),
+        };
+        item
+    }
+
+    #[tokio::test]
+    async fn test_filter_by_source_app() {
+        let (db, _dir) = temp_db("host_clipboard").await;
+
+        add_clipboard_entry(&db, text_item("ls -la", "Gnome-terminal", "gnome-terminal-")).await.unwrap();
+        add_clipboard_entry(&db, text_item("https://example.com", "firefox", "firefox")).await.unwrap();
+        add_clipboard_entry(&db, text_item("no origin", "", "")).await.unwrap();
+
+        let entries = get_clipboards_by_type_list(&db, None, None, None, Some("Terminal")).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].content, "ls -la");
+        assert_eq!(entries[0].window_title, "Gnome-terminal window");
+
+        let entries = get_clipboards_by_type_list(&db, None, None, None, None).await.unwrap();
+        assert_eq!(entries.len(), 3);
+
+        let apps = get_source_apps(&db).await.unwrap();
+        assert_eq!(apps.len(), 2);
+        assert!(apps.contains(&"firefox".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_add_clipboard_entries() {
+        let (db, _dir) = temp_db("batch").await;
+
+        // 同一批次中重复的内容只保留一条
+        let items = vec![text_item("a", "", ""), text_item("b", "", ""), text_item("a", "", "")];
+        let entries = add_clipboard_entries(&db, items).await.unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].id, entries[2].id);
+
+        let entries = get_clipboards_by_type_list(&db, None, None, None, None).await.unwrap();
+        assert_eq!(entries.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_clipboard_page() {
+        let (db, _dir) = temp_db("page").await;
+
+        // 每 3 条共用一个时间戳, 翻页时需要按 id 区分
+        let now = chrono::Local::now().fixed_offset();
+        for i in 0..10 {
+            let mut item = text_item(&format!("entry {}", i), "", "");
+            item.date_time = now - chrono::Duration::seconds(10 - i / 3);
+            add_clipboard_entry(&db, item).await.unwrap();
+        }
+
+        let mut contents = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = get_clipboard_page(&db, None, None, None, cursor, 4).await.unwrap();
+            assert_eq!(page.total, 10);
+            assert!(page.entries.len() <= 4);
+            contents.extend(page.entries.into_iter().map(|entry| entry.content));
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        let expected: Vec<String> = (0..10).rev().map(|i| format!("entry {}", i)).collect();
+        assert_eq!(contents, expected);
+
+        // 过滤条件同样作用于总数
+        let page = get_clipboard_page(&db, Some("entry 1"), None, None, None, 4).await.unwrap();
+        assert_eq!(page.total, 1);
+        assert!(page.next_cursor.is_none());
+
+        // limit 超出范围时按边界处理
+        let page = get_clipboard_page(&db, None, None, None, None, 0).await.unwrap();
+        assert_eq!(page.entries.len(), 1);
+        assert!(page.next_cursor.is_some());
+        let page = get_clipboard_page(&db, None, None, None, None, u64::MAX).await.unwrap();
+        assert_eq!(page.entries.len(), 10);
+        assert!(page.next_cursor.is_none());
+    }
 }
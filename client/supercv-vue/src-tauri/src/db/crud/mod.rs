@@ -1 +1,3 @@
+pub mod clipboard_revision;
 pub mod host_clipboard;
+pub mod outbox;
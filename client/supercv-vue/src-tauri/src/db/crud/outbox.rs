@@ -0,0 +1,146 @@
+use sea_orm::sea_query::OnConflict;
+use sea_orm::ActiveValue::Set;
+use sea_orm::*;
+
+use crate::db::entities::outbox::{self, OutboxAction};
+use crate::db::entities::prelude::Outbox;
+
+// 同一 hash 只保留一条, 已在队列中时保持原来的重试进度. 已经在等待删除的记录不会改回上传
+pub async fn enqueue_outbox_entry(db: &DatabaseConnection, hash: &str, now: i64) -> Result<(), DbErr> {
+    Outbox::insert(outbox::ActiveModel {
+        hash: Set(hash.to_string()),
+        action: Set(OutboxAction::Upload.to_i32()),
+        attempts: Set(0),
+        next_attempt_at: Set(now),
+        last_error: Set(String::new()),
+        created_at: Set(now),
+        ..Default::default()
+    })
+    .on_conflict(OnConflict::column(outbox::Column::Hash).do_nothing().to_owned())
+    .exec_without_returning(db)
+    .await?;
+    Ok(())
+}
+
+// 本地清除的记录需要在服务端删除, 覆盖同一 hash 还没有完成的上传, 并重新开始计算重试
+pub async fn enqueue_outbox_delete<C: ConnectionTrait>(db: &C, hash: &str, now: i64) -> Result<(), DbErr> {
+    Outbox::insert(outbox::ActiveModel {
+        hash: Set(hash.to_string()),
+        action: Set(OutboxAction::Delete.to_i32()),
+        attempts: Set(0),
+        next_attempt_at: Set(now),
+        last_error: Set(String::new()),
+        created_at: Set(now),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::column(outbox::Column::Hash)
+            .update_columns([
+                outbox::Column::Action,
+                outbox::Column::Attempts,
+                outbox::Column::NextAttemptAt,
+                outbox::Column::LastError,
+            ])
+            .to_owned(),
+    )
+    .exec_without_returning(db)
+    .await?;
+    Ok(())
+}
+
+// 等待在服务端删除的记录, 同步时不再下载
+pub async fn is_outbox_delete_pending(db: &DatabaseConnection, hash: &str) -> Result<bool, DbErr> {
+    let count = Outbox::find()
+        .filter(outbox::Column::Hash.eq(hash))
+        .filter(outbox::Column::Action.eq(OutboxAction::Delete.to_i32()))
+        .count(db)
+        .await?;
+    Ok(count > 0)
+}
+
+pub async fn get_due_outbox_entries(db: &DatabaseConnection, now: i64, limit: u64) -> Result<Vec<outbox::Model>, DbErr> {
+    Outbox::find()
+        .filter(outbox::Column::NextAttemptAt.lte(now))
+        .order_by_asc(outbox::Column::NextAttemptAt)
+        .limit(limit)
+        .all(db)
+        .await
+}
+
+// 最近一次需要重放的时间, 队列为空时返回 None
+pub async fn get_next_outbox_attempt(db: &DatabaseConnection) -> Result<Option<i64>, DbErr> {
+    let entry = Outbox::find().order_by_asc(outbox::Column::NextAttemptAt).one(db).await?;
+    Ok(entry.map(|entry| entry.next_attempt_at))
+}
+
+pub async fn reschedule_outbox_entry(
+    db: &DatabaseConnection,
+    entry: outbox::Model,
+    next_attempt_at: i64,
+    error: String,
+) -> Result<outbox::Model, DbErr> {
+    let attempts = entry.attempts + 1;
+    let mut entry: outbox::ActiveModel = entry.into();
+    entry.attempts = Set(attempts);
+    entry.next_attempt_at = Set(next_attempt_at);
+    entry.last_error = Set(error);
+    entry.update(db).await
+}
+
+pub async fn delete_outbox_entry(db: &DatabaseConnection, id: i32) -> Result<DeleteResult, DbErr> {
+    Outbox::delete_by_id(id).exec(db).await
+}
+
+pub async fn count_outbox_entries(db: &DatabaseConnection) -> Result<u64, DbErr> {
+    Outbox::find().count(db).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::testing::temp_db;
+
+    #[tokio::test]
+    async fn test_outbox_dedup_and_schedule() {
+        let (db, _dir) = temp_db("outbox").await;
+
+        enqueue_outbox_entry(&db, "a", 100).await.unwrap();
+        enqueue_outbox_entry(&db, "b", 110).await.unwrap();
+        let due = get_due_outbox_entries(&db, 100, 10).await.unwrap();
+        assert_eq!(due.len(), 1);
+        let entry = reschedule_outbox_entry(&db, due[0].clone(), 200, "offline".to_string()).await.unwrap();
+        assert_eq!(entry.attempts, 1);
+
+        // 重复入队不会重置重试进度
+        enqueue_outbox_entry(&db, "a", 120).await.unwrap();
+        assert_eq!(count_outbox_entries(&db).await.unwrap(), 2);
+        let due = get_due_outbox_entries(&db, 150, 10).await.unwrap();
+        assert_eq!(due.iter().map(|e| e.hash.as_str()).collect::<Vec<_>>(), vec!["b"]);
+        assert_eq!(get_next_outbox_attempt(&db).await.unwrap(), Some(110));
+
+        delete_outbox_entry(&db, due[0].id).await.unwrap();
+        assert_eq!(get_next_outbox_attempt(&db).await.unwrap(), Some(200));
+    }
+
+    #[tokio::test]
+    async fn test_outbox_delete_replaces_upload() {
+        let (db, _dir) = temp_db("outbox_delete").await;
+
+        enqueue_outbox_entry(&db, "a", 100).await.unwrap();
+        let entry = get_due_outbox_entries(&db, 100, 10).await.unwrap().remove(0);
+        reschedule_outbox_entry(&db, entry, 500, "offline".to_string()).await.unwrap();
+        assert!(!is_outbox_delete_pending(&db, "a").await.unwrap());
+
+        // 删除覆盖还没有完成的上传, 立即重放
+        enqueue_outbox_delete(&db, "a", 200).await.unwrap();
+        let entry = get_due_outbox_entries(&db, 200, 10).await.unwrap().remove(0);
+        assert_eq!(OutboxAction::from_i32(entry.action), Some(OutboxAction::Delete));
+        assert_eq!(entry.attempts, 0);
+        assert!(is_outbox_delete_pending(&db, "a").await.unwrap());
+
+        // 之后的上传不会把删除改回来
+        enqueue_outbox_entry(&db, "a", 300).await.unwrap();
+        assert!(is_outbox_delete_pending(&db, "a").await.unwrap());
+        assert_eq!(count_outbox_entries(&db).await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,21 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// 记录编辑前的内容, clipboard_id 对应 host_clipboard.id
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "clipboard_revision")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub clipboard_id: i32,
+    pub content: String,
+    pub hash: String,
+    pub title: String,
+    pub note: String,
+    pub created_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -13,6 +13,29 @@ pub struct Model {
     pub content: String,
     pub timestamp: i64,
     pub hash: String,
+    #[serde(default)]
+    pub source: i32,
+    #[serde(default)]
+    pub app_name: String,
+    #[serde(default)]
+    pub process_name: String,
+    #[serde(default)]
+    pub window_title: String,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default = "default_copy_count")]
+    pub copy_count: i32,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub note: String,
+    #[serde(default)]
+    pub deleted_at: Option<i64>, // 毫秒, 为空表示未删除
+}
+
+// 没有复制次数的旧数据至少被复制过一次
+pub(crate) fn default_copy_count() -> i32 {
+    1
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
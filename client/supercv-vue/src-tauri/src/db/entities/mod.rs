@@ -2,4 +2,6 @@
 
 pub mod prelude;
 
+pub mod clipboard_revision;
 pub mod host_clipboard;
+pub mod outbox;
@@ -0,0 +1,45 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// 等待重新上传或在服务端删除的记录, hash 对应 host_clipboard.hash
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "outbox")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(unique)]
+    pub hash: String,
+    pub action: i32, // OutboxAction
+    pub attempts: i32,
+    pub next_attempt_at: i64,
+    pub last_error: String,
+    pub created_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutboxAction {
+    Upload,
+    Delete, // 本地已清除, 服务端的记录也需要删除
+}
+
+impl OutboxAction {
+    pub fn to_i32(&self) -> i32 {
+        match self {
+            OutboxAction::Upload => 0,
+            OutboxAction::Delete => 1,
+        }
+    }
+
+    pub fn from_i32(value: i32) -> Option<Self> {
+        match value {
+            0 => Some(OutboxAction::Upload),
+            1 => Some(OutboxAction::Delete),
+            _ => None,
+        }
+    }
+}
@@ -1,3 +1,5 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.15
+pub use super::clipboard_revision::Entity as ClipboardRevision;
 pub use super::host_clipboard::Entity as HostClipboard;
+pub use super::outbox::Entity as Outbox;
 
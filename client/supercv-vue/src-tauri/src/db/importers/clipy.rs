@@ -0,0 +1,72 @@
+// Clipy: 只支持导入片段编辑器导出的 snippets.xml, 片段默认置顶.
+// 历史记录保存在 Realm 数据库 (default.realm) 中, 没有可用的 Rust 读取库, 不支持导入, 选择该文件时返回 Unavailable
+//
+// <folders><folder><title>..</title><snippets><snippet><title>..</title><content>..</content></snippet></snippets></folder></folders>
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{ForeignContent, ForeignEntry, HistoryParser, ParsedHistory};
+use crate::error::{AppError, AppResult};
+
+// Realm 文件头的第 16 个字节开始为 "T-DB"
+const REALM_MAGIC: &[u8] = b"T-DB";
+
+pub struct ClipyParser;
+
+#[async_trait::async_trait]
+impl HistoryParser for ClipyParser {
+    fn default_path(&self) -> Option<PathBuf> {
+        None
+    }
+
+    async fn parse(&self, path: &Path) -> AppResult<ParsedHistory> {
+        let data = fs::read(path)?;
+        if data.get(16..20) == Some(REALM_MAGIC) {
+            return Err(AppError::Unavailable(
+                "Clipy history is stored in a Realm database and can not be imported, export snippets.xml instead".to_string(),
+            ));
+        }
+        let xml = String::from_utf8(data).map_err(|e| AppError::InvalidInput(format!("Invalid Clipy snippets: {}", e)))?;
+        let doc = roxmltree::Document::parse(&xml).map_err(|e| AppError::InvalidInput(format!("Invalid Clipy snippets: {}", e)))?;
+
+        let mut parsed = ParsedHistory::default();
+        for snippet in doc.descendants().filter(|n| n.has_tag_name("snippet")) {
+            match snippet.children().find(|n| n.has_tag_name("content")).and_then(|n| n.text()) {
+                Some(text) => {
+                    let mut entry = ForeignEntry::new(ForeignContent::Text(text.to_string()));
+                    entry.pinned = true;
+                    parsed.entries.push(entry);
+                },
+                None => parsed.unsupported += 1,
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::testing::TempDir;
+
+    #[tokio::test]
+    async fn test_snippets_only() {
+        let dir = TempDir::new("clipy");
+        let snippets = dir.join("snippets.xml");
+        fs::write(
+            &snippets,
+            "<folders><folder><title>f</title><snippets><snippet><title>t</title><content>hello</content></snippet></snippets></folder></folders>",
+        )
+        .unwrap();
+        let parsed = ClipyParser.parse(&snippets).await.unwrap();
+        assert_eq!(parsed.entries.len(), 1);
+        assert!(parsed.entries[0].pinned);
+
+        // 历史记录的 Realm 数据库
+        let realm = dir.join("default.realm");
+        let mut header = vec![0u8; 24];
+        header[16..20].copy_from_slice(REALM_MAGIC);
+        fs::write(&realm, header).unwrap();
+        assert!(matches!(ClipyParser.parse(&realm).await, Err(AppError::Unavailable(_))));
+    }
+}
@@ -0,0 +1,131 @@
+// CopyQ: 每个标签页一个 QDataStream 文件 copyq_tab_<base64(标签名)>.dat, 默认标签页为 &clipboard
+//
+// 文件: [QString "CopyQ v.."] qint32 条数, 之后每条为 MIME -> 数据的表
+//   -2 格式: qint32 -2, qint32 个数, (压缩后的 MIME, bool 是否 qCompress, QByteArray 数据)...
+//   旧格式: qint32 个数, (QString MIME, qCompress 后的 QByteArray)...
+// 不保存复制时间, 按标签页中的顺序推算
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::qt::{q_uncompress, QDataReader};
+use super::{url_to_path, ForeignContent, ForeignEntry, Formats, HistoryParser, ParsedHistory};
+use crate::error::{AppError, AppResult};
+
+// 压缩后的 MIME 以数字开头, 表示省略的前缀, 0 表示没有省略
+const MIME_PREFIXES: [&str; 5] = ["", "application/x-copyq-", "text/", "application/", "image/"];
+
+pub struct CopyqParser;
+
+#[async_trait::async_trait]
+impl HistoryParser for CopyqParser {
+    fn default_path(&self) -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("copyq").join("copyq_tab_JmNsaXBib2FyZA==.dat"))
+    }
+
+    async fn parse(&self, path: &Path) -> AppResult<ParsedHistory> {
+        let data = fs::read(path)?;
+        let mut parsed = ParsedHistory::default();
+        for item in read_items(&data)? {
+            match select_content(item) {
+                Some(content) => parsed.entries.push(ForeignEntry::new(content)),
+                None => parsed.unsupported += 1,
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+fn read_items(data: &[u8]) -> AppResult<Vec<Formats>> {
+    let mut reader = QDataReader::new(data);
+    // 新版本以版本字符串开头, 旧版本直接是条数
+    let mut probe = QDataReader::new(data);
+    if probe.string().is_ok_and(|header| header.starts_with("CopyQ")) {
+        reader = probe;
+    }
+
+    let count = reader.i32()?;
+    // 条数来自文件, 损坏时可能很大, 预分配的容量设上限
+    let mut items = Vec::with_capacity(count.clamp(0, 4096) as usize);
+    for _ in 0..count {
+        let marker = reader.i32()?;
+        let mut item = Vec::new();
+        match marker {
+            -2 => {
+                for _ in 0..reader.i32()? {
+                    let mime = decompress_mime(&reader.text()?);
+                    let compressed = reader.bool()?;
+                    let bytes = reader.byte_array()?;
+                    item.push((mime, if compressed { q_uncompress(bytes)? } else { bytes.to_vec() }));
+                }
+            },
+            n if n >= 0 => {
+                for _ in 0..n {
+                    let mime = reader.string()?;
+                    item.push((mime, q_uncompress(reader.byte_array()?)?));
+                }
+            },
+            n => return Err(AppError::InvalidInput(format!("Unsupported CopyQ item format {}", n))),
+        }
+        items.push(item);
+    }
+    Ok(items)
+}
+
+fn decompress_mime(mime: &str) -> String {
+    let mut chars = mime.chars();
+    match chars.next().and_then(|c| c.to_digit(10)).and_then(|i| MIME_PREFIXES.get(i as usize)) {
+        Some(prefix) => format!("{}{}", prefix, chars.as_str()),
+        None => mime.to_string(),
+    }
+}
+
+// 文件优先, 其次图片, 最后纯文本
+fn select_content(item: Formats) -> Option<ForeignContent> {
+    let find = |mime: &str| item.iter().find(|(m, _)| m == mime).map(|(_, d)| String::from_utf8_lossy(d).to_string());
+    if let Some(uris) = find("text/uri-list") {
+        let lines: Vec<&str> = uris.lines().filter(|l| !l.trim().is_empty() && !l.starts_with('#')).collect();
+        if !lines.is_empty() && lines.iter().all(|l| l.starts_with("file://")) {
+            return Some(ForeignContent::Files(lines.into_iter().map(url_to_path).collect()));
+        }
+    }
+    let image = item
+        .iter()
+        .find(|(m, _)| m == "image/png")
+        .or_else(|| item.iter().find(|(m, _)| m.starts_with("image/") && m != "image/svg+xml"));
+    if let Some((_, data)) = image {
+        return Some(ForeignContent::Image(data.clone()));
+    }
+    find("text/plain").map(ForeignContent::Text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::qt::writer::{q_compress, QDataWriter};
+    use super::*;
+
+    #[test]
+    fn test_read_tab() {
+        let mut w = QDataWriter::default();
+        w.string("CopyQ v3").i32(3);
+        // -2 格式, 附带 CopyQ 自己的数据
+        w.i32(-2).i32(2);
+        w.byte_array(b"2plain").bool(false).byte_array("新的文本".as_bytes());
+        w.byte_array(b"1owner-window-title").bool(true).byte_array(&q_compress(b"terminal"));
+        // 旧格式
+        w.i32(1).string("text/uri-list").byte_array(&q_compress(b"file:///tmp/a%20b.txt\n"));
+        w.i32(1).string("application/x-unknown").byte_array(&q_compress(b"?"));
+
+        let items = read_items(&w.0).unwrap();
+        assert_eq!(items[0][1].0, "application/x-copyq-owner-window-title");
+        assert_eq!(items[0][1].1, b"terminal");
+        let contents: Vec<_> = items.into_iter().map(select_content).collect();
+        assert!(matches!(&contents[0], Some(ForeignContent::Text(t)) if t == "新的文本"));
+        assert!(matches!(&contents[1], Some(ForeignContent::Files(f)) if f == &vec!["/tmp/a b.txt".to_string()]));
+        assert!(contents[2].is_none());
+
+        // 条数远大于实际内容时返回错误, 不按条数预分配
+        let mut w = QDataWriter::default();
+        w.string("CopyQ v3").i32(i32::MAX);
+        assert!(read_items(&w.0).is_err());
+    }
+}
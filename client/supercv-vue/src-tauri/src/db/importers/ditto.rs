@@ -0,0 +1,160 @@
+// Ditto: SQLite 数据库 Ditto.db
+//
+// Main 为一次复制 (mText 为文本内容, lDate 为 unix 时间, lDontAutoDelete 非 0 表示不会过期),
+// Data 为其中每种剪贴板格式的数据. 分组 (bIsGroup) 不导入
+use std::path::{Path, PathBuf};
+
+use super::{open_sqlite, query_all, ForeignContent, ForeignEntry, Formats, HistoryParser, ParsedHistory};
+use crate::error::AppResult;
+
+pub struct DittoParser;
+
+#[async_trait::async_trait]
+impl HistoryParser for DittoParser {
+    fn default_path(&self) -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("Ditto").join("Ditto.db"))
+    }
+
+    async fn parse(&self, path: &Path) -> AppResult<ParsedHistory> {
+        let db = open_sqlite(path).await?;
+        let rows = query_all(
+            &db,
+            "SELECT m.lID AS id, CAST(m.lDate AS INTEGER) AS date, m.mText AS text, CAST(COALESCE(m.lDontAutoDelete, 0) AS INTEGER) AS keep, \
+             d.strClipBoardFormat AS format, d.ooData AS data \
+             FROM Main m LEFT JOIN Data d ON d.lParentID = m.lID \
+             WHERE COALESCE(m.bIsGroup, 0) = 0 \
+             ORDER BY m.lDate DESC, m.lID, d.lID",
+        )
+        .await?;
+
+        let mut items: Vec<(i64, String, Formats, ForeignEntry)> = Vec::new();
+        for row in rows {
+            let id: i64 = row.try_get("", "id")?;
+            let format: Option<String> = row.try_get("", "format")?;
+            let data: Option<Vec<u8>> = row.try_get("", "data")?;
+            if items.last().map(|(last, ..)| *last) != Some(id) {
+                let mut entry = ForeignEntry::new(ForeignContent::Text(String::new()));
+                entry.timestamp = row.try_get::<Option<i64>>("", "date")?;
+                entry.pinned = row.try_get::<i64>("", "keep")? != 0;
+                let text = row.try_get::<Option<String>>("", "text")?.unwrap_or_default();
+                items.push((id, text, Vec::new(), entry));
+            }
+            if let (Some(format), Some(data), Some((_, _, formats, _))) = (format, data, items.last_mut()) {
+                formats.push((format, data));
+            }
+        }
+
+        let mut parsed = ParsedHistory::default();
+        for (_, text, formats, mut entry) in items {
+            match select_content(text, formats) {
+                Some(content) => {
+                    entry.content = content;
+                    parsed.entries.push(entry);
+                },
+                None => parsed.unsupported += 1,
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+// 文件优先, 其次图片, 最后文本
+fn select_content(text: String, formats: Formats) -> Option<ForeignContent> {
+    let find = |name: &str| formats.iter().find(|(f, _)| f == name).map(|(_, d)| d.as_slice());
+    if let Some(files) = find("CF_HDROP").and_then(parse_hdrop) {
+        return Some(ForeignContent::Files(files));
+    }
+    if let Some(png) = find("PNG") {
+        return Some(ForeignContent::Image(png.to_vec()));
+    }
+    if let Some(bmp) = find("CF_DIB").and_then(dib_to_bmp) {
+        return Some(ForeignContent::Image(bmp));
+    }
+    if !text.is_empty() {
+        return Some(ForeignContent::Text(text));
+    }
+    find("CF_UNICODETEXT").map(|data| ForeignContent::Text(utf16_le(data)))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn utf16_le(data: &[u8]) -> String {
+    let units: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|u| *u != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+// DROPFILES 结构: pFiles (文件列表偏移), pt, fNC, fWide, 之后是以两个 \0 结尾的路径列表
+fn parse_hdrop(data: &[u8]) -> Option<Vec<String>> {
+    let offset = u32_at(data, 0)? as usize;
+    let wide = u32_at(data, 16)? != 0;
+    let list = data.get(offset..)?;
+    let files: Vec<String> = if wide {
+        let units: Vec<u16> = list.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+        units
+            .split(|u| *u == 0)
+            .take_while(|s| !s.is_empty())
+            .map(String::from_utf16_lossy)
+            .collect()
+    } else {
+        list.split(|b| *b == 0)
+            .take_while(|s| !s.is_empty())
+            .map(|s| String::from_utf8_lossy(s).to_string())
+            .collect()
+    };
+    (!files.is_empty()).then_some(files)
+}
+
+// CF_DIB 是去掉文件头的 BMP, 补上 BITMAPFILEHEADER 后 image 才能读取
+fn dib_to_bmp(dib: &[u8]) -> Option<Vec<u8>> {
+    let header_size = u32_at(dib, 0)?;
+    let bit_count = u16::from_le_bytes([*dib.get(14)?, *dib.get(15)?]);
+    let compression = u32_at(dib, 16)?;
+    let colors_used = u32_at(dib, 32)?;
+    let palette = match (colors_used, bit_count) {
+        (0, 1..=8) => 1u32 << bit_count,
+        (n, _) => n,
+    } * 4;
+    // BI_BITFIELDS 时 BITMAPINFOHEADER 之后还有 3 个掩码
+    let masks = if compression == 3 && header_size == 40 { 12 } else { 0 };
+    let offset = 14 + header_size + palette + masks;
+
+    let mut bmp = Vec::with_capacity(14 + dib.len());
+    bmp.extend_from_slice(b"BM");
+    bmp.extend_from_slice(&(14 + dib.len() as u32).to_le_bytes());
+    bmp.extend_from_slice(&[0; 4]);
+    bmp.extend_from_slice(&offset.to_le_bytes());
+    bmp.extend_from_slice(dib);
+    Some(bmp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_formats() {
+        // DROPFILES 头 20 字节, 之后是 UTF-16 路径
+        let mut hdrop = vec![20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0];
+        for path in ["C:\\a.txt", "D:\\b c.txt", ""] {
+            hdrop.extend(path.encode_utf16().chain([0]).flat_map(|u| u.to_le_bytes()));
+        }
+        assert_eq!(parse_hdrop(&hdrop).unwrap(), vec!["C:\\a.txt", "D:\\b c.txt"]);
+
+        // 1x1 的 24 位 DIB, 每行补齐到 4 字节
+        let mut dib = vec![0u8; 40];
+        dib[0] = 40;
+        dib[4] = 1;
+        dib[8] = 1;
+        dib[12] = 1;
+        dib[14] = 24;
+        dib.extend_from_slice(&[0, 0, 255, 0]);
+        let img = image::load_from_memory(&dib_to_bmp(&dib).unwrap()).unwrap().to_rgb8();
+        assert_eq!(img.get_pixel(0, 0).0, [255, 0, 0]);
+    }
+}
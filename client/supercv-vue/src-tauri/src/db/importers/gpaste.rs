@@ -0,0 +1,62 @@
+// GPaste: ~/.local/share/gpaste/history.xml
+//
+// <history version="2.0">
+//   <item kind="Text|Uris|Image|Password" uuid=".." date=".."><value><![CDATA[..]]></value></item>
+// </history>
+// 1.0 版本没有 value 节点, 内容直接在 item 中. 图片保存在 images/<uuid>.png
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{normalize_timestamp, url_to_path, ForeignContent, ForeignEntry, HistoryParser, ParsedHistory};
+use crate::error::{AppError, AppResult};
+
+pub struct GpasteParser;
+
+#[async_trait::async_trait]
+impl HistoryParser for GpasteParser {
+    fn default_path(&self) -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("gpaste").join("history.xml"))
+    }
+
+    async fn parse(&self, path: &Path) -> AppResult<ParsedHistory> {
+        let xml = fs::read_to_string(path)?;
+        let doc = roxmltree::Document::parse(&xml).map_err(|e| AppError::InvalidInput(format!("Invalid GPaste history: {}", e)))?;
+        let image_dir = path.parent().map(|dir| dir.join("images")).unwrap_or_default();
+
+        let mut parsed = ParsedHistory::default();
+        for item in doc.root_element().children().filter(|n| n.has_tag_name("item")) {
+            let value = item
+                .children()
+                .find(|n| n.has_tag_name("value"))
+                .map_or_else(|| item.text(), |n| n.text())
+                .unwrap_or_default();
+            let content = match item.attribute("kind") {
+                Some("Text") => ForeignContent::Text(value.to_string()),
+                Some("Uris") => ForeignContent::Files(value.lines().filter(|l| !l.trim().is_empty()).map(url_to_path).collect()),
+                Some("Image") => {
+                    let file = match item.attribute("uuid") {
+                        Some(uuid) if value.trim().is_empty() => image_dir.join(format!("{}.png", uuid)),
+                        _ => PathBuf::from(value.trim()),
+                    };
+                    match fs::read(&file) {
+                        Ok(bytes) => ForeignContent::Image(bytes),
+                        Err(e) => {
+                            log::warn!("Skip GPaste image {}: {}", file.display(), e);
+                            parsed.unsupported += 1;
+                            continue;
+                        },
+                    }
+                },
+                // 密码不导入
+                _ => {
+                    parsed.unsupported += 1;
+                    continue;
+                },
+            };
+            let mut entry = ForeignEntry::new(content);
+            entry.timestamp = item.attribute("date").and_then(|d| d.parse::<i64>().ok()).map(normalize_timestamp);
+            parsed.entries.push(entry);
+        }
+        Ok(parsed)
+    }
+}
@@ -0,0 +1,171 @@
+// Klipper (KDE)
+//
+// Plasma 5: history2.lst, quint32 crc32 + QByteArray, 其中为 QString 版本号, 之后每条为
+//   "string" QString | "url" QList<QUrl> QMap<QString, QString> qint32 | "image" qint32 非空 + PNG 数据
+// Plasma 6: history3.sqlite, 只导入 main 表中的文本, 图片等数据保存在单独的文件中
+// history2.lst 不保存复制时间, 按顺序推算
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::qt::QDataReader;
+use super::{open_sqlite, query_all, url_to_path, ForeignContent, ForeignEntry, HistoryParser, ParsedHistory};
+use crate::error::{AppError, AppResult};
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+pub struct KlipperParser;
+
+#[async_trait::async_trait]
+impl HistoryParser for KlipperParser {
+    fn default_path(&self) -> Option<PathBuf> {
+        let dir = dirs::data_dir()?.join("klipper");
+        let sqlite = dir.join("history3.sqlite");
+        Some(if sqlite.exists() { sqlite } else { dir.join("history2.lst") })
+    }
+
+    async fn parse(&self, path: &Path) -> AppResult<ParsedHistory> {
+        if path.extension().is_some_and(|ext| ext == "sqlite") {
+            parse_sqlite(path).await
+        } else {
+            parse_lst(&fs::read(path)?)
+        }
+    }
+}
+
+fn parse_lst(bytes: &[u8]) -> AppResult<ParsedHistory> {
+    let mut reader = QDataReader::new(bytes);
+    let crc = reader.u32()?;
+    let data = reader.byte_array()?;
+    if crc32fast::hash(data) != crc {
+        return Err(AppError::InvalidInput("Klipper history checksum mismatch".to_string()));
+    }
+
+    let mut reader = QDataReader::new(data);
+    let _version = reader.string()?;
+    let mut parsed = ParsedHistory::default();
+    while !reader.is_empty() {
+        let content = match reader.string()?.as_str() {
+            "string" => ForeignContent::Text(reader.string()?),
+            "url" => {
+                let mut urls = Vec::new();
+                for _ in 0..reader.u32()? {
+                    urls.push(url_to_path(&String::from_utf8_lossy(reader.byte_array()?)));
+                }
+                for _ in 0..reader.u32()? {
+                    reader.string()?;
+                    reader.string()?;
+                }
+                let _cut = reader.i32()?;
+                ForeignContent::Files(urls)
+            },
+            "image" => {
+                if reader.i32()? == 0 {
+                    parsed.unsupported += 1;
+                    continue;
+                }
+                let len = png_len(reader.rest()).ok_or_else(|| AppError::InvalidInput("Invalid image in Klipper history".to_string()))?;
+                ForeignContent::Image(reader.take(len)?.to_vec())
+            },
+            // 无法得知未知类型的长度, 之后的数据都无法解析
+            other => {
+                log::warn!("Unknown Klipper item type {}, stop parsing", other);
+                parsed.unsupported += 1;
+                break;
+            },
+        };
+        parsed.entries.push(ForeignEntry::new(content));
+    }
+    Ok(parsed)
+}
+
+// QImage 以没有长度前缀的 PNG 写入, 按数据块找到 IEND 为止
+fn png_len(data: &[u8]) -> Option<usize> {
+    if !data.starts_with(PNG_SIGNATURE) {
+        return None;
+    }
+    let mut pos = PNG_SIGNATURE.len();
+    loop {
+        let len = u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        let kind = data.get(pos + 4..pos + 8)?;
+        pos += 12 + len;
+        if pos > data.len() {
+            return None;
+        }
+        if kind == b"IEND" {
+            return Some(pos);
+        }
+    }
+}
+
+async fn parse_sqlite(path: &Path) -> AppResult<ParsedHistory> {
+    let db = open_sqlite(path).await?;
+    let rows = query_all(
+        &db,
+        "SELECT CAST(added_time AS REAL) AS added, text, mimetypes, CAST(COALESCE(starred, 0) AS INTEGER) AS starred \
+         FROM main ORDER BY added_time DESC",
+    )
+    .await?;
+
+    let mut parsed = ParsedHistory::default();
+    for row in rows {
+        let text: Option<String> = row.try_get("", "text")?;
+        let mimetypes: String = row.try_get::<Option<String>>("", "mimetypes")?.unwrap_or_default();
+        let content = match text {
+            Some(text) if mimetypes.contains("text/uri-list") && text.lines().all(|l| l.starts_with("file://")) => {
+                ForeignContent::Files(text.lines().map(url_to_path).collect())
+            },
+            Some(text) if !text.is_empty() => ForeignContent::Text(text),
+            _ => {
+                parsed.unsupported += 1;
+                continue;
+            },
+        };
+        let mut entry = ForeignEntry::new(content);
+        entry.timestamp = row.try_get::<Option<f64>>("", "added")?.map(|t| t as i64);
+        entry.pinned = row.try_get::<i64>("", "starred")? != 0;
+        parsed.entries.push(entry);
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::super::qt::writer::QDataWriter;
+    use super::*;
+
+    #[test]
+    fn test_parse_history2() {
+        let mut png = Vec::new();
+        image::RgbImage::new(2, 1)
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let mut data = QDataWriter::default();
+        data.string("5.27.0");
+        data.string("string").string("echo hello");
+        data.string("url")
+            .u32(1)
+            .byte_array(b"file:///home/me/doc.txt")
+            .u32(1)
+            .string("key")
+            .string("value")
+            .i32(0);
+        data.string("image").i32(1);
+        data.0.extend_from_slice(&png);
+        data.string("string").string("after image");
+
+        let mut file = QDataWriter::default();
+        file.u32(crc32fast::hash(&data.0)).byte_array(&data.0);
+
+        let parsed = parse_lst(&file.0).unwrap();
+        assert_eq!(parsed.entries.len(), 4);
+        assert!(matches!(&parsed.entries[1].content, ForeignContent::Files(f) if f == &vec!["/home/me/doc.txt".to_string()]));
+        assert!(matches!(&parsed.entries[2].content, ForeignContent::Image(i) if i == &png));
+        assert!(matches!(&parsed.entries[3].content, ForeignContent::Text(t) if t == "after image"));
+
+        file.0[0] ^= 1;
+        assert!(parse_lst(&file.0).is_err());
+    }
+}
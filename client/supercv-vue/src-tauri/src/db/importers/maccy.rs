@@ -0,0 +1,83 @@
+// Maccy: Core Data 的 SQLite 数据库 Storage.sqlite
+//
+// ZHISTORYITEM 为一次复制, ZHISTORYITEMCONTENT 为其中每种 UTI 类型的数据.
+// 时间是从 2001-01-01 开始的秒数, ZPIN 不为空表示置顶
+use std::path::{Path, PathBuf};
+
+use super::{open_sqlite, query_all, url_to_path, ForeignContent, ForeignEntry, Formats, HistoryParser, ParsedHistory};
+use crate::error::AppResult;
+
+const CORE_DATA_EPOCH: i64 = 978_307_200;
+const IMAGE_TYPES: [&str; 3] = ["public.png", "public.tiff", "public.jpeg"];
+
+pub struct MaccyParser;
+
+#[async_trait::async_trait]
+impl HistoryParser for MaccyParser {
+    fn default_path(&self) -> Option<PathBuf> {
+        dirs::home_dir().map(|home| home.join("Library/Containers/org.p0deje.Maccy/Data/Library/Application Support/Maccy/Storage.sqlite"))
+    }
+
+    async fn parse(&self, path: &Path) -> AppResult<ParsedHistory> {
+        let db = open_sqlite(path).await?;
+        let rows = query_all(
+            &db,
+            "SELECT i.Z_PK AS id, CAST(i.ZLASTCOPIEDAT AS REAL) AS copied_at, CAST(i.ZPIN IS NOT NULL AS INTEGER) AS pinned, \
+             i.ZAPPLICATION AS app, c.ZTYPE AS type, c.ZVALUE AS value \
+             FROM ZHISTORYITEM i LEFT JOIN ZHISTORYITEMCONTENT c ON c.ZITEM = i.Z_PK \
+             ORDER BY i.ZLASTCOPIEDAT DESC, i.Z_PK, c.Z_PK",
+        )
+        .await?;
+
+        // 同一条记录的多种类型相邻, 按 id 分组
+        let mut items: Vec<(i64, Formats, ForeignEntry)> = Vec::new();
+        for row in rows {
+            let id: i64 = row.try_get("", "id")?;
+            let r#type: Option<String> = row.try_get("", "type")?;
+            let value: Option<Vec<u8>> = row.try_get("", "value")?;
+            if items.last().map(|(last, _, _)| *last) != Some(id) {
+                let mut entry = ForeignEntry::new(ForeignContent::Text(String::new()));
+                entry.timestamp = row.try_get::<Option<f64>>("", "copied_at")?.map(|t| t as i64 + CORE_DATA_EPOCH);
+                entry.pinned = row.try_get::<i64>("", "pinned")? != 0;
+                entry.app_name = row.try_get::<Option<String>>("", "app")?.unwrap_or_default();
+                items.push((id, Vec::new(), entry));
+            }
+            if let (Some(r#type), Some(value), Some((_, contents, _))) = (r#type, value, items.last_mut()) {
+                contents.push((r#type, value));
+            }
+        }
+
+        let mut parsed = ParsedHistory::default();
+        for (_, contents, mut entry) in items {
+            match select_content(contents) {
+                Some(content) => {
+                    entry.content = content;
+                    parsed.entries.push(entry);
+                },
+                None => parsed.unsupported += 1,
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+// 文件优先, 其次图片, 最后纯文本
+fn select_content(contents: Formats) -> Option<ForeignContent> {
+    let files: Vec<String> = contents
+        .iter()
+        .filter(|(t, _)| t == "public.file-url")
+        .map(|(_, v)| url_to_path(&String::from_utf8_lossy(v)))
+        .collect();
+    if !files.is_empty() {
+        return Some(ForeignContent::Files(files));
+    }
+    for image_type in IMAGE_TYPES {
+        if let Some((_, value)) = contents.iter().find(|(t, _)| t == image_type) {
+            return Some(ForeignContent::Image(value.clone()));
+        }
+    }
+    contents
+        .into_iter()
+        .find(|(t, _)| t == "public.utf8-plain-text")
+        .map(|(_, v)| ForeignContent::Text(String::from_utf8_lossy(&v).to_string()))
+}
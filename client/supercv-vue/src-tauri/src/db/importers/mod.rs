@@ -0,0 +1,380 @@
+// 从其他剪贴板管理器迁移历史记录
+//
+// 每种数据格式实现一个 HistoryParser, 解析出的条目统一转换成 host_clipboard 记录, 图片另存到文件目录.
+// 依靠 hash 唯一索引去重, dry_run 时只统计不写入. 导入前最好先退出对应的应用, 避免读到写了一半的数据
+mod clipy;
+mod copyq;
+mod ditto;
+mod gpaste;
+mod klipper;
+mod maccy;
+mod qt;
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use sea_orm::{ConnectionTrait, Database, DatabaseConnection, DbBackend, QueryResult, Statement};
+use serde::{Deserialize, Serialize};
+
+use crate::core::clipboard::string_is_large;
+use crate::core::history::file_name;
+use crate::core::pasteboard::ContentType;
+use crate::db::crud::host_clipboard;
+use crate::db::entities::host_clipboard::Model;
+use crate::error::{AppError, AppResult};
+use crate::utils::file::format_size;
+use crate::utils::hash::{hash_str, hash_vec};
+use crate::utils::time::get_current_timestamp;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportSource {
+    Copyq,
+    Clipy,
+    Maccy,
+    Ditto,
+    Gpaste,
+    Klipper,
+}
+
+impl ImportSource {
+    pub fn parser(self) -> Box<dyn HistoryParser> {
+        match self {
+            ImportSource::Copyq => Box::new(copyq::CopyqParser),
+            ImportSource::Clipy => Box::new(clipy::ClipyParser),
+            ImportSource::Maccy => Box::new(maccy::MaccyParser),
+            ImportSource::Ditto => Box::new(ditto::DittoParser),
+            ImportSource::Gpaste => Box::new(gpaste::GpasteParser),
+            ImportSource::Klipper => Box::new(klipper::KlipperParser),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ImportSource::Copyq => "CopyQ",
+            ImportSource::Clipy => "Clipy",
+            ImportSource::Maccy => "Maccy",
+            ImportSource::Ditto => "Ditto",
+            ImportSource::Gpaste => "GPaste",
+            ImportSource::Klipper => "Klipper",
+        }
+    }
+}
+
+pub enum ForeignContent {
+    Text(String),
+    Image(Vec<u8>), // 任意 image 支持的格式, 保存时统一为 PNG
+    Files(Vec<String>),
+}
+
+// 解析出的一条记录
+pub struct ForeignEntry {
+    pub content: ForeignContent,
+    pub timestamp: Option<i64>, // 没有时按顺序推算
+    pub app_name: String,       // 为空时使用来源应用的名字
+    pub pinned: bool,
+}
+
+impl ForeignEntry {
+    pub fn new(content: ForeignContent) -> Self {
+        ForeignEntry {
+            content,
+            timestamp: None,
+            app_name: String::new(),
+            pinned: false,
+        }
+    }
+}
+
+// 一条记录中每种格式 (MIME, UTI 或剪贴板格式名) 的数据
+type Formats = Vec<(String, Vec<u8>)>;
+
+#[derive(Default)]
+pub struct ParsedHistory {
+    pub entries: Vec<ForeignEntry>, // 从新到旧
+    pub unsupported: usize,         // 无法转换的条目, 例如密码和未知格式
+}
+
+#[async_trait::async_trait]
+pub trait HistoryParser: Send + Sync {
+    // 当前系统上该应用默认的数据文件, 没有时需要手动指定
+    fn default_path(&self) -> Option<PathBuf>;
+
+    async fn parse(&self, path: &Path) -> AppResult<ParsedHistory>;
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MigrationReport {
+    pub source: ImportSource,
+    pub path: String,
+    pub dry_run: bool,
+    pub found: usize,
+    pub imported: usize, // dry_run 时为将要导入的数量
+    pub duplicates: usize,
+    pub skipped: usize,
+    pub texts: usize,
+    pub images: usize,
+    pub files: usize,
+    pub oldest: Option<i64>,
+    pub newest: Option<i64>,
+}
+
+// 从 source 的数据文件导入, path 为空时使用默认位置. 图片保存到 files_dir/<source> 下
+pub async fn import_history(
+    db: &DatabaseConnection,
+    source: ImportSource,
+    path: Option<&Path>,
+    dry_run: bool,
+    files_dir: &Path,
+) -> AppResult<MigrationReport> {
+    let parser = source.parser();
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => parser
+            .default_path()
+            .ok_or_else(|| AppError::InvalidInput(format!("{} has no default data file, please specify one", source.name())))?,
+    };
+    if !path.exists() {
+        return Err(AppError::NotFound(path.display().to_string()));
+    }
+    let parsed = parser.parse(&path).await?;
+
+    let mut report = MigrationReport {
+        source,
+        path: path.display().to_string(),
+        dry_run,
+        found: parsed.entries.len() + parsed.unsupported,
+        imported: 0,
+        duplicates: 0,
+        skipped: parsed.unsupported,
+        texts: 0,
+        images: 0,
+        files: 0,
+        oldest: None,
+        newest: None,
+    };
+    let image_dir = files_dir.join(format!("{:?}", source).to_lowercase());
+    let now = get_current_timestamp();
+    let mut seen = HashSet::new();
+
+    for (i, entry) in parsed.entries.into_iter().enumerate() {
+        let timestamp = entry.timestamp.unwrap_or(now - i as i64);
+        let Some(mut model) = to_model(&entry, timestamp) else {
+            report.skipped += 1;
+            continue;
+        };
+        if model.app_name.is_empty() {
+            model.app_name = source.name().to_string();
+        }
+        // 已删除等待清除的记录同样占用 hash
+        if !seen.insert(model.hash.clone())
+            || host_clipboard::get_clipboard_entry_by_hash_including_deleted(db, &model.hash)
+                .await?
+                .is_some()
+        {
+            report.duplicates += 1;
+            continue;
+        }
+
+        if !dry_run {
+            if let ForeignContent::Image(bytes) = &entry.content {
+                model.path = match save_image(bytes, &image_dir, &model.hash) {
+                    Ok(path) => path,
+                    Err(e) => {
+                        log::warn!("Skip image from {}: {}", source.name(), e);
+                        report.skipped += 1;
+                        continue;
+                    },
+                };
+            }
+            if !host_clipboard::insert_clipboard_entry_if_absent(db, model).await? {
+                report.duplicates += 1;
+                continue;
+            }
+        }
+
+        match entry.content {
+            ForeignContent::Text(_) => report.texts += 1,
+            ForeignContent::Image(_) => report.images += 1,
+            ForeignContent::Files(_) => report.files += 1,
+        }
+        report.imported += 1;
+        report.oldest = Some(report.oldest.map_or(timestamp, |t| t.min(timestamp)));
+        report.newest = Some(report.newest.map_or(timestamp, |t| t.max(timestamp)));
+    }
+    Ok(report)
+}
+
+// 与采集时的 hash 和索引内容规则保持一致, 图片的 path 在保存后填写
+fn to_model(entry: &ForeignEntry, timestamp: i64) -> Option<Model> {
+    let (r#type, content, path, hash) = match &entry.content {
+        ForeignContent::Text(text) => {
+            if text.trim().is_empty() || string_is_large(text) {
+                return None;
+            }
+            (ContentType::Text, text.clone(), String::new(), hash_str(text))
+        },
+        ForeignContent::Image(bytes) => {
+            let img = image::load_from_memory(bytes).ok()?;
+            let content = format!("Img: {}x{} ({})", img.width(), img.height(), format_size(bytes.len()));
+            (ContentType::Image, content, String::new(), hash_vec(bytes))
+        },
+        ForeignContent::Files(paths) => {
+            let content = match paths.as_slice() {
+                [] => return None,
+                [path] => format!("File: {}", file_name(path)),
+                paths => format!(
+                    "{} Files: {}",
+                    paths.len(),
+                    paths.iter().map(|p| file_name(p)).collect::<Vec<_>>().join(", ")
+                ),
+            };
+            let path = serde_json::to_string(paths).ok()?;
+            let hash = hash_str(&path);
+            (ContentType::File, content, path, hash)
+        },
+    };
+    Some(Model {
+        id: 0,
+        r#type: r#type.to_i32(),
+        path,
+        content,
+        timestamp,
+        hash,
+        source: 0,
+        app_name: entry.app_name.clone(),
+        process_name: String::new(),
+        window_title: String::new(),
+        pinned: entry.pinned,
+        copy_count: 1,
+        title: String::new(),
+        note: String::new(),
+        deleted_at: None,
+    })
+}
+
+fn save_image(bytes: &[u8], dir: &Path, hash: &str) -> AppResult<String> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}.png", hash));
+    if image::guess_format(bytes).ok() == Some(image::ImageFormat::Png) {
+        fs::write(&path, bytes)?;
+    } else {
+        image::load_from_memory(bytes)
+            .and_then(|img| img.save_with_format(&path, image::ImageFormat::Png))
+            .map_err(|e| AppError::InvalidInput(format!("Invalid image: {}", e)))?;
+    }
+    Ok(path.to_string_lossy().to_string())
+}
+
+// 以只读方式打开其他应用的 SQLite 数据库
+async fn open_sqlite(path: &Path) -> AppResult<DatabaseConnection> {
+    let path = path.to_string_lossy().replace('%', "%25").replace('?', "%3F").replace('#', "%23");
+    Ok(Database::connect(format!("sqlite:{}?mode=ro", path)).await?)
+}
+
+async fn query_all(db: &DatabaseConnection, sql: &str) -> AppResult<Vec<QueryResult>> {
+    Ok(db.query_all(Statement::from_string(DbBackend::Sqlite, sql)).await?)
+}
+
+// file:// URL 转为本地路径, 其他 URL 原样保留
+fn url_to_path(url: &str) -> String {
+    match url::Url::parse(url.trim()) {
+        Ok(u) if u.scheme() == "file" => match u.to_file_path() {
+            Ok(path) => path.to_string_lossy().to_string(),
+            Err(_) => urlencoding::decode(u.path())
+                .map(|p| p.to_string())
+                .unwrap_or_else(|_| u.path().to_string()),
+        },
+        _ => url.trim().to_string(),
+    }
+}
+
+// 部分应用的时间单位是毫秒或微秒
+fn normalize_timestamp(value: i64) -> i64 {
+    match value {
+        v if v > 100_000_000_000_000 => v / 1_000_000,
+        v if v > 100_000_000_000 => v / 1_000,
+        v => v,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::connection::init_db_connection;
+    use crate::db::testing::temp_db;
+
+    #[tokio::test]
+    async fn test_dry_run_and_dedup() {
+        let (db, dir) = temp_db("importers_gpaste").await;
+        let history = dir.join("history.xml");
+        fs::write(
+            &history,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<history version="2.0">
+  <item kind="Text" uuid="a" date="1700000200"><value><![CDATA[echo hello]]></value></item>
+  <item kind="Uris" uuid="b" date="1700000100"><value><![CDATA[file:///tmp/a.txt
+file:///tmp/b%20c.txt]]></value></item>
+  <item kind="Password" uuid="c" name="mail"><value><![CDATA[secret]]></value></item>
+  <item kind="Text" uuid="d" date="1700000000"><value><![CDATA[echo hello]]></value></item>
+</history>"#,
+        )
+        .unwrap();
+
+        let report = import_history(&db, ImportSource::Gpaste, Some(&history), true, &dir).await.unwrap();
+        assert_eq!((report.found, report.imported, report.duplicates, report.skipped), (4, 2, 1, 1));
+        assert_eq!((report.oldest, report.newest), (Some(1700000100), Some(1700000200)));
+        assert!(host_clipboard::get_all_clipboard_entries(&db).await.unwrap().is_empty());
+
+        let report = import_history(&db, ImportSource::Gpaste, Some(&history), false, &dir).await.unwrap();
+        assert_eq!((report.imported, report.texts, report.files), (2, 1, 1));
+        let entries = host_clipboard::get_all_clipboard_entries(&db).await.unwrap();
+        assert_eq!(entries[0].content, "2 Files: a.txt, b c.txt");
+        assert_eq!(entries[1].timestamp, 1700000200);
+        assert_eq!(entries[1].app_name, "GPaste");
+
+        // 再次导入时全部重复
+        let report = import_history(&db, ImportSource::Gpaste, Some(&history), false, &dir).await.unwrap();
+        assert_eq!((report.imported, report.duplicates), (0, 3));
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_sources() {
+        let (db, dir) = temp_db("importers_sqlite").await;
+        let ditto = dir.join("Ditto.db");
+        let source = init_db_connection(ditto.to_str()).await.unwrap();
+        for sql in [
+            "CREATE TABLE Main (lID INTEGER PRIMARY KEY, lDate INTEGER, mText TEXT, lDontAutoDelete INTEGER, bIsGroup INTEGER)",
+            "CREATE TABLE Data (lID INTEGER PRIMARY KEY, lParentID INTEGER, strClipBoardFormat TEXT, ooData BLOB)",
+            "INSERT INTO Main VALUES (1, 1700000000, 'from ditto', 1700000000, 0), (2, 1700000100, '', 0, 1)",
+            "INSERT INTO Data VALUES (1, 1, 'CF_UNICODETEXT', x'66007200')",
+        ] {
+            source.execute_unprepared(sql).await.unwrap();
+        }
+        drop(source);
+
+        let maccy = dir.join("Storage.sqlite");
+        let source = init_db_connection(maccy.to_str()).await.unwrap();
+        for sql in [
+            "CREATE TABLE ZHISTORYITEM (Z_PK INTEGER PRIMARY KEY, ZLASTCOPIEDAT TIMESTAMP, ZPIN VARCHAR, ZAPPLICATION VARCHAR)",
+            "CREATE TABLE ZHISTORYITEMCONTENT (Z_PK INTEGER PRIMARY KEY, ZITEM INTEGER, ZTYPE VARCHAR, ZVALUE BLOB)",
+            "INSERT INTO ZHISTORYITEM VALUES (1, 721692800.5, 'b', 'com.apple.Safari')",
+            "INSERT INTO ZHISTORYITEMCONTENT VALUES (1, 1, 'public.utf8-plain-text', CAST('from maccy' AS BLOB))",
+            "INSERT INTO ZHISTORYITEMCONTENT VALUES (2, 1, 'public.html', CAST('<b>from maccy</b>' AS BLOB))",
+        ] {
+            source.execute_unprepared(sql).await.unwrap();
+        }
+        drop(source);
+
+        let report = import_history(&db, ImportSource::Ditto, Some(&ditto), false, &dir).await.unwrap();
+        assert_eq!((report.imported, report.newest), (1, Some(1700000000)));
+        let report = import_history(&db, ImportSource::Maccy, Some(&maccy), false, &dir).await.unwrap();
+        assert_eq!((report.imported, report.newest), (1, Some(1700000000)));
+
+        let entries = host_clipboard::get_all_clipboard_entries(&db).await.unwrap();
+        assert!(entries.iter().all(|e| e.pinned));
+        assert_eq!(entries[0].content, "from ditto");
+        assert_eq!(entries[1].app_name, "com.apple.Safari");
+    }
+}
@@ -0,0 +1,132 @@
+// Qt QDataStream 的读取, CopyQ 和 Klipper 的历史文件都是这种大端序的格式
+use std::io::Read;
+
+use flate2::read::ZlibDecoder;
+
+use crate::error::{AppError, AppResult};
+
+pub(super) struct QDataReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> QDataReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        QDataReader { data, pos: 0 }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    pub fn rest(&self) -> &'a [u8] {
+        &self.data[self.pos.min(self.data.len())..]
+    }
+
+    pub fn take(&mut self, n: usize) -> AppResult<&'a [u8]> {
+        if self.data.len() - self.pos < n {
+            return Err(AppError::InvalidInput("Unexpected end of data stream".to_string()));
+        }
+        let bytes = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    pub fn bool(&mut self) -> AppResult<bool> {
+        Ok(self.take(1)?[0] != 0)
+    }
+
+    pub fn u32(&mut self) -> AppResult<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn i32(&mut self) -> AppResult<i32> {
+        Ok(self.u32()? as i32)
+    }
+
+    // QByteArray: 长度 + 内容, 0xFFFFFFFF 表示 null
+    pub fn byte_array(&mut self) -> AppResult<&'a [u8]> {
+        match self.u32()? {
+            u32::MAX => Ok(&[]),
+            len => self.take(len as usize),
+        }
+    }
+
+    // QString: 字节长度 + UTF-16BE
+    pub fn string(&mut self) -> AppResult<String> {
+        Ok(utf16_be(self.byte_array()?))
+    }
+
+    // 不同版本中有的字段是 QString 有的是 QByteArray, 两者长度前缀相同, 按内容区分
+    pub fn text(&mut self) -> AppResult<String> {
+        let bytes = self.byte_array()?;
+        if bytes.len() % 2 == 0 && !bytes.is_empty() && bytes.iter().step_by(2).all(|b| *b == 0) {
+            Ok(utf16_be(bytes))
+        } else {
+            Ok(String::from_utf8_lossy(bytes).to_string())
+        }
+    }
+}
+
+fn utf16_be(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+    String::from_utf16_lossy(&units)
+}
+
+// qCompress 的结果: 4 字节原始长度 + zlib 数据
+pub(super) fn q_uncompress(data: &[u8]) -> AppResult<Vec<u8>> {
+    if data.len() < 4 {
+        return Err(AppError::InvalidInput("Invalid compressed data".to_string()));
+    }
+    let mut out = Vec::new();
+    ZlibDecoder::new(&data[4..]).read_to_end(&mut out)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+pub(super) mod writer {
+    use std::io::Write;
+
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+
+    // 测试中构造 QDataStream 数据
+    #[derive(Default)]
+    pub struct QDataWriter(pub Vec<u8>);
+
+    impl QDataWriter {
+        pub fn u32(&mut self, v: u32) -> &mut Self {
+            self.0.extend_from_slice(&v.to_be_bytes());
+            self
+        }
+
+        pub fn i32(&mut self, v: i32) -> &mut Self {
+            self.u32(v as u32)
+        }
+
+        pub fn bool(&mut self, v: bool) -> &mut Self {
+            self.0.push(v as u8);
+            self
+        }
+
+        pub fn byte_array(&mut self, v: &[u8]) -> &mut Self {
+            self.u32(v.len() as u32);
+            self.0.extend_from_slice(v);
+            self
+        }
+
+        pub fn string(&mut self, v: &str) -> &mut Self {
+            let bytes: Vec<u8> = v.encode_utf16().flat_map(|u| u.to_be_bytes()).collect();
+            self.byte_array(&bytes)
+        }
+    }
+
+    pub fn q_compress(data: &[u8]) -> Vec<u8> {
+        let mut out = (data.len() as u32).to_be_bytes().to_vec();
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        out.extend(encoder.finish().unwrap());
+        out
+    }
+}
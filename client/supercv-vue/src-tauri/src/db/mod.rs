@@ -1,3 +1,8 @@
+pub mod backup;
 pub mod connection;
 pub mod crud;
 pub mod entities;
+pub mod importers;
+pub mod recovery;
+#[cfg(test)]
+pub(crate) mod testing;
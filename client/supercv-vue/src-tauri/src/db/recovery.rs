@@ -0,0 +1,284 @@
+// 启动时的数据库检查
+//
+// 无法打开或 PRAGMA integrity_check 不通过时, 把数据库文件移到 quarantine 目录,
+// 从新到旧尝试备份, 都不可用时新建空数据库. 数据库被其他进程锁住或迁移失败 (例如由更新的版本创建) 时
+// 数据库没有损坏, 不动原文件, 本次运行使用内存数据库
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::Local;
+use log::{error, info, warn};
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{ConnectOptions, Database, DatabaseConnection, DbErr};
+use serde::Serialize;
+
+use crate::db::backup;
+use crate::db::connection::{check_integrity, connect};
+use crate::utils::config::CONFIG;
+
+const QUARANTINE_DIR: &str = "quarantine";
+// sqlite 放在数据库旁边的日志文件, 需要和数据库一起移走
+const SIDECAR_SUFFIXES: [&str; 3] = ["-wal", "-shm", "-journal"];
+// integrity_check 最多返回 100 条问题, 只保留前几条
+const MAX_PROBLEMS: usize = 3;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Recovery {
+    RestoredBackup { backup: String },
+    CreatedEmpty,
+    InMemory,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RecoveryReport {
+    pub reason: String,
+    pub quarantined: Option<String>, // 原数据库文件被移到的位置
+    #[serde(flatten)]
+    pub recovery: Recovery,
+}
+
+impl RecoveryReport {
+    // 通知中显示的说明
+    pub fn message(&self) -> String {
+        let result = match &self.recovery {
+            Recovery::RestoredBackup { backup } => format!("已从备份 {} 恢复", backup),
+            Recovery::CreatedEmpty => "没有可用的备份, 已创建新的数据库".to_string(),
+            Recovery::InMemory => "本次运行的剪贴板记录不会被保存".to_string(),
+        };
+        match &self.quarantined {
+            Some(path) => format!("{}. {}, 原文件已移至 {}", self.reason, result, path),
+            None => format!("{}. {}", self.reason, result),
+        }
+    }
+}
+
+enum Problem {
+    Locked(String),
+    Broken(String),
+    Migration(String),
+}
+
+pub async fn open_database() -> Result<(DatabaseConnection, Option<RecoveryReport>), DbErr> {
+    let (db_path, backup_dir) = {
+        let config = CONFIG.read().unwrap();
+        (config.db_path.join("db.sqlite"), config.backup_dir())
+    };
+    open_or_recover(&db_path, &backup_dir).await
+}
+
+// 数据库正常时报告为 None, 内存数据库也无法使用时返回错误
+pub async fn open_or_recover(db_path: &Path, backup_dir: &Path) -> Result<(DatabaseConnection, Option<RecoveryReport>), DbErr> {
+    let (db, report) = match try_open(db_path).await {
+        Ok(db) => return Ok((db, None)),
+        Err(Problem::Locked(reason)) => (open_in_memory().await?, in_memory(reason)),
+        Err(Problem::Migration(reason)) => {
+            error!("Failed to migrate database {}: {}", db_path.display(), reason);
+            (open_in_memory().await?, in_memory(reason))
+        },
+        Err(Problem::Broken(reason)) => recover(db_path, backup_dir, reason).await?,
+    };
+    warn!("{}", report.message());
+    Ok((db, Some(report)))
+}
+
+fn in_memory(reason: String) -> RecoveryReport {
+    RecoveryReport {
+        reason,
+        quarantined: None,
+        recovery: Recovery::InMemory,
+    }
+}
+
+async fn recover(db_path: &Path, backup_dir: &Path, reason: String) -> Result<(DatabaseConnection, RecoveryReport), DbErr> {
+    error!("Database {} is broken: {}", db_path.display(), reason);
+    let quarantined = match quarantine(db_path) {
+        Ok(path) => path.display().to_string(),
+        Err(e) => {
+            error!("Failed to quarantine {}: {}", db_path.display(), e);
+            return Ok((open_in_memory().await?, in_memory(reason)));
+        },
+    };
+    let report = |recovery| RecoveryReport {
+        reason: reason.clone(),
+        quarantined: Some(quarantined.clone()),
+        recovery,
+    };
+
+    let backups = backup::list_backups(backup_dir).unwrap_or_else(|e| {
+        warn!("Failed to list backups: {}", e);
+        vec![]
+    });
+    for backup in backups {
+        if let Err(e) = fs::copy(backup.db_file(), db_path) {
+            warn!("Failed to copy backup {}: {}", backup.name, e);
+            continue;
+        }
+        match try_open(db_path).await {
+            Ok(db) => {
+                info!("Database restored from backup {}", backup.name);
+                return Ok((db, report(Recovery::RestoredBackup { backup: backup.name })));
+            },
+            Err(Problem::Locked(e) | Problem::Broken(e) | Problem::Migration(e)) => {
+                warn!("Backup {} is not usable: {}", backup.name, e);
+                remove_database(db_path);
+            },
+        }
+    }
+
+    match try_open(db_path).await {
+        Ok(db) => Ok((db, report(Recovery::CreatedEmpty))),
+        Err(Problem::Locked(e) | Problem::Broken(e) | Problem::Migration(e)) => {
+            error!("Failed to create database {}: {}", db_path.display(), e);
+            Ok((open_in_memory().await?, report(Recovery::InMemory)))
+        },
+    }
+}
+
+// 先检查完整性再迁移, 不在损坏的数据库上写入. 完整性检查通过后的迁移失败不是损坏
+async fn try_open(db_path: &Path) -> Result<DatabaseConnection, Problem> {
+    let db = connect(db_path).await.map_err(|e| classify(e, "无法打开数据库"))?;
+    let result = match check_integrity(&db).await {
+        Ok(problems) if problems.is_empty() => Migrator::up(&db, None).await.map_err(|e| match classify(e, "数据库迁移失败") {
+            Problem::Broken(reason) => Problem::Migration(reason),
+            problem => problem,
+        }),
+        Ok(problems) => Err(Problem::Broken(format!(
+            "数据库已损坏: {}",
+            problems.into_iter().take(MAX_PROBLEMS).collect::<Vec<_>>().join("; ")
+        ))),
+        Err(e) => Err(classify(e, "数据库已损坏")),
+    };
+    match result {
+        Ok(_) => Ok(db),
+        Err(problem) => {
+            let _ = db.close().await;
+            Err(problem)
+        },
+    }
+}
+
+fn classify(e: DbErr, context: &str) -> Problem {
+    let message = e.to_string();
+    if message.contains("is locked") {
+        Problem::Locked(format!("数据库被其他程序占用: {}", message))
+    } else {
+        Problem::Broken(format!("{}: {}", context, message))
+    }
+}
+
+fn quarantine(db_path: &Path) -> io::Result<PathBuf> {
+    let dir = db_path.parent().unwrap_or(Path::new(".")).join(QUARANTINE_DIR);
+    fs::create_dir_all(&dir)?;
+    let name = db_path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let target = dir.join(format!("{}-{}", Local::now().format("%Y%m%d-%H%M%S"), name));
+    fs::rename(db_path, &target)?;
+    for suffix in SIDECAR_SUFFIXES {
+        let sidecar = with_suffix(db_path, suffix);
+        if sidecar.exists() {
+            fs::rename(sidecar, with_suffix(&target, suffix))?;
+        }
+    }
+    Ok(target)
+}
+
+fn remove_database(db_path: &Path) {
+    let _ = fs::remove_file(db_path);
+    for suffix in SIDECAR_SUFFIXES {
+        let _ = fs::remove_file(with_suffix(db_path, suffix));
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+// 所有连接共享同一个内存数据库, 连接全部关闭后数据就会丢失, 因此保持一个连接不回收
+async fn open_in_memory() -> Result<DatabaseConnection, DbErr> {
+    let mut options = ConnectOptions::new("sqlite::memory:");
+    options
+        .min_connections(1)
+        .max_connections(1)
+        .idle_timeout(Duration::from_secs(u32::MAX as u64))
+        .max_lifetime(Duration::from_secs(u32::MAX as u64));
+    let db = Database::connect(options).await?;
+    Migrator::up(&db, None).await?;
+    Ok(db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::pasteboard::{ContentType, PasteboardContent};
+    use crate::db::connection::init_db_connection;
+    use crate::db::crud::host_clipboard;
+    use crate::db::testing::TempDir;
+    use crate::utils::hash::hash_str;
+    use sea_orm::ConnectionTrait;
+
+    #[tokio::test]
+    async fn test_recover_broken_database() {
+        let dir = TempDir::new("recovery");
+        let (db_path, backups) = (dir.join("db").join("db.sqlite"), dir.join("backups"));
+
+        let db = init_db_connection(db_path.to_str()).await.unwrap();
+        let item = PasteboardContent::new("kept".to_string(), ContentType::Text, hash_str("kept"), None);
+        host_clipboard::add_clipboard_entry(&db, item).await.unwrap();
+        backup::create_backup(&db, &backups, None).await.unwrap();
+        db.close().await.unwrap();
+
+        // 正常的数据库不做处理
+        let (db, report) = open_or_recover(&db_path, &backups).await.unwrap();
+        assert!(report.is_none());
+        db.close().await.unwrap();
+
+        fs::write(&db_path, b"this is not a sqlite database, just some garbage bytes").unwrap();
+        let (db, report) = open_or_recover(&db_path, &backups).await.unwrap();
+        let report = report.unwrap();
+        assert!(matches!(report.recovery, Recovery::RestoredBackup { .. }));
+        assert!(Path::new(report.quarantined.as_ref().unwrap()).exists());
+        assert_eq!(host_clipboard::get_all_clipboard_entries(&db).await.unwrap()[0].content, "kept");
+        db.close().await.unwrap();
+
+        // 没有备份时使用空数据库
+        fs::write(&db_path, b"this is not a sqlite database, just some garbage bytes").unwrap();
+        let (db, report) = open_or_recover(&db_path, &dir.join("none")).await.unwrap();
+        assert_eq!(report.unwrap().recovery, Recovery::CreatedEmpty);
+        assert!(host_clipboard::get_all_clipboard_entries(&db).await.unwrap().is_empty());
+        db.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_migration_failure_keeps_database() {
+        let dir = TempDir::new("recovery_migration");
+        let (db_path, backups) = (dir.join("db").join("db.sqlite"), dir.join("backups"));
+
+        // 更新的版本创建的数据库, 当前版本没有对应的迁移
+        let db = init_db_connection(db_path.to_str()).await.unwrap();
+        let item = PasteboardContent::new("kept".to_string(), ContentType::Text, hash_str("kept"), None);
+        host_clipboard::add_clipboard_entry(&db, item).await.unwrap();
+        db.execute_unprepared("INSERT INTO seaql_migrations (version, applied_at) VALUES ('m99990101_000001_future', 0)")
+            .await
+            .unwrap();
+        db.close().await.unwrap();
+
+        let (db, report) = open_or_recover(&db_path, &backups).await.unwrap();
+        let report = report.unwrap();
+        assert!(report.reason.starts_with("数据库迁移失败"));
+        assert_eq!(report.recovery, Recovery::InMemory);
+        assert!(report.quarantined.is_none());
+        assert!(!dir.join("db").join(QUARANTINE_DIR).exists());
+        assert!(host_clipboard::get_all_clipboard_entries(&db).await.unwrap().is_empty());
+        db.close().await.unwrap();
+
+        // 原文件保持不变
+        let db = connect(&db_path).await.unwrap();
+        assert_eq!(host_clipboard::get_all_clipboard_entries(&db).await.unwrap()[0].content, "kept");
+        db.close().await.unwrap();
+    }
+}
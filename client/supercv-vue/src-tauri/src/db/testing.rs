@@ -0,0 +1,41 @@
+// 测试用的临时目录和数据库
+//
+// 目录名带随机后缀, 并行运行的测试互不影响. TempDir 离开作用域时删除整个目录, 测试失败时也会清理
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use sea_orm::DatabaseConnection;
+
+use crate::db::connection::init_db_connection;
+
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    pub(crate) fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("supercv_{}_{}", name, uuid::Uuid::new_v4().simple()));
+        fs::create_dir_all(&dir).unwrap();
+        TempDir(dir)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+// 在新的临时目录中创建数据库并执行迁移, 数据库文件为目录下的 db.sqlite
+pub(crate) async fn temp_db(name: &str) -> (DatabaseConnection, TempDir) {
+    let dir = TempDir::new(name);
+    let db = init_db_connection(dir.join("db.sqlite").to_str()).await.unwrap();
+    (db, dir)
+}
@@ -0,0 +1,185 @@
+// 全局错误类型. 命令和 IPC 返回给调用方时序列化为 { code, message }, code 取值见 ErrorCode
+use std::error::Error;
+use std::{fmt, io};
+
+use sea_orm::DbErr;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+use crate::api::common::ClientError;
+use crate::utils::cipher::CipherError;
+
+pub type AppResult<T> = Result<T, AppError>;
+
+// 前端按 code 区分错误, 新增时同步修改 clipboardHelper.ts 中的 ErrorCode
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    Database,
+    Io,
+    Network,
+    Api,
+    Crypto,
+    Clipboard,
+    Window,
+    NotFound,
+    InvalidInput,
+    CapturePaused,
+    Unavailable,
+    Internal,
+}
+
+#[derive(Debug)]
+pub enum AppError {
+    Db(DbErr),
+    Io(io::Error),
+    Client(ClientError),
+    Cipher(CipherError),
+    Clipboard(String), // clipboard-rs, Wayland 和 PRIMARY 等剪贴板后端的错误
+    Window(tauri::Error),
+    NotFound(String),
+    InvalidInput(String),
+    CapturePaused,
+    Unavailable(String), // 当前环境不支持, 例如没有可用的剪贴板
+    Internal(String),
+}
+
+impl AppError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::Db(_) => ErrorCode::Database,
+            AppError::Io(_) => ErrorCode::Io,
+            AppError::Client(ClientError::NetworkError(_) | ClientError::Http(_)) => ErrorCode::Network,
+            AppError::Client(ClientError::ApiError { .. }) => ErrorCode::Api,
+            AppError::Client(ClientError::Io(_)) => ErrorCode::Io,
+            AppError::Client(ClientError::Db(_)) => ErrorCode::Database,
+            AppError::Client(ClientError::Cipher(_)) => ErrorCode::Crypto,
+            AppError::Client(_) => ErrorCode::Internal,
+            AppError::Cipher(_) => ErrorCode::Crypto,
+            AppError::Clipboard(_) => ErrorCode::Clipboard,
+            AppError::Window(_) => ErrorCode::Window,
+            AppError::NotFound(_) => ErrorCode::NotFound,
+            AppError::InvalidInput(_) => ErrorCode::InvalidInput,
+            AppError::CapturePaused => ErrorCode::CapturePaused,
+            AppError::Unavailable(_) => ErrorCode::Unavailable,
+            AppError::Internal(_) => ErrorCode::Internal,
+        }
+    }
+
+    // Wayland 和 PRIMARY 的错误不是 Send, 不能直接用 From 转换
+    pub fn clipboard(e: impl fmt::Display) -> Self {
+        AppError::Clipboard(e.to_string())
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Db(e) => write!(f, "Database error: {}", e),
+            AppError::Io(e) => write!(f, "IO error: {}", e),
+            AppError::Client(e) => write!(f, "{}", e),
+            AppError::Cipher(e) => write!(f, "{}", e),
+            AppError::Clipboard(e) => write!(f, "Clipboard error: {}", e),
+            AppError::Window(e) => write!(f, "Window error: {}", e),
+            AppError::NotFound(e) => write!(f, "Not found: {}", e),
+            AppError::InvalidInput(e) => write!(f, "Invalid input: {}", e),
+            AppError::CapturePaused => write!(f, "Capture is paused"),
+            AppError::Unavailable(e) => write!(f, "Unavailable: {}", e),
+            AppError::Internal(e) => write!(f, "Internal error: {}", e),
+        }
+    }
+}
+
+impl Error for AppError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AppError::Db(e) => Some(e),
+            AppError::Io(e) => Some(e),
+            AppError::Client(e) => Some(e),
+            AppError::Cipher(e) => Some(e),
+            AppError::Window(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 2)?;
+        state.serialize_field("code", &self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl From<DbErr> for AppError {
+    fn from(e: DbErr) -> Self {
+        match e {
+            DbErr::RecordNotFound(e) => AppError::NotFound(e),
+            e => AppError::Db(e),
+        }
+    }
+}
+
+impl From<io::Error> for AppError {
+    fn from(e: io::Error) -> Self {
+        AppError::Io(e)
+    }
+}
+
+impl From<ClientError> for AppError {
+    fn from(e: ClientError) -> Self {
+        AppError::Client(e)
+    }
+}
+
+impl From<CipherError> for AppError {
+    fn from(e: CipherError) -> Self {
+        AppError::Cipher(e)
+    }
+}
+
+impl From<tauri::Error> for AppError {
+    fn from(e: tauri::Error) -> Self {
+        AppError::Window(e)
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        AppError::Internal(e.to_string())
+    }
+}
+
+// clipboard_rs::Result 的错误类型
+impl From<Box<dyn Error + Send + Sync>> for AppError {
+    fn from(e: Box<dyn Error + Send + Sync>) -> Self {
+        AppError::Clipboard(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_serialize_with_code() {
+        let e = AppError::from(ClientError::NetworkError("offline".to_string()));
+        assert_eq!(
+            serde_json::to_value(&e).unwrap(),
+            json!({ "code": "network", "message": "Network error: offline" })
+        );
+        assert!(e.source().is_some());
+
+        // 保留底层的错误
+        let e = AppError::from(ClientError::from(io::Error::new(io::ErrorKind::NotFound, "user.toml")));
+        assert_eq!(e.code(), ErrorCode::Io);
+        let source = e.source().and_then(|e| e.source()).unwrap();
+        assert_eq!(source.downcast_ref::<io::Error>().unwrap().kind(), io::ErrorKind::NotFound);
+
+        let e = AppError::from(DbErr::RecordNotFound("clipboard entry 1".to_string()));
+        assert_eq!(e.code(), ErrorCode::NotFound);
+        assert_eq!(serde_json::to_value(AppError::CapturePaused).unwrap()["code"], "capture_paused");
+    }
+}
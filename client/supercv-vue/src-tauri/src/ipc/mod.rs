@@ -0,0 +1,304 @@
+// 本地 IPC: 在 Unix domain socket (Windows 下为 named pipe) 上提供 JSON-RPC 2.0 服务,
+// 供编辑器, rofi/Alfred 等工具读写剪贴板历史.
+//
+// 每行一个 JSON 消息. 除 rs_invoke_* 对应的方法外, 还支持 subscribe/unsubscribe,
+// 订阅后新记录和采集状态变化会以通知的形式推送.
+pub mod rpc;
+
+use std::io;
+use std::sync::Arc;
+
+use log::{error, info, warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::task::JoinHandle;
+
+use crate::clipboard_helper::ClipboardHelper;
+use crate::core::events;
+use crate::ipc::rpc::{Notification, Response, RpcError};
+use crate::utils::config::CONFIG;
+
+#[cfg(windows)]
+pub const PIPE_NAME: &str = r"\\.\pipe\supercv";
+
+#[cfg(unix)]
+pub fn socket_path() -> std::path::PathBuf {
+    CONFIG.read().unwrap().cache_dir.join("supercv.sock")
+}
+
+pub fn start_server(helper: Arc<ClipboardHelper>) {
+    if !CONFIG.read().unwrap().user_config.ipc_config.enabled {
+        info!("IPC server is disabled");
+        return;
+    }
+    tokio::spawn(async move {
+        if let Err(e) = serve(helper).await {
+            error!("IPC server stopped: {}", e);
+        }
+    });
+}
+
+#[cfg(unix)]
+async fn serve(helper: Arc<ClipboardHelper>) -> io::Result<()> {
+    let path = socket_path();
+    let listener = bind_socket(&path).await?;
+    info!("IPC server listening on {}", path.display());
+    loop {
+        let (stream, _) = listener.accept().await?;
+        if let Err(e) = check_peer(&stream) {
+            warn!("Rejected IPC connection: {}", e);
+            continue;
+        }
+        tokio::spawn(handle_connection(stream, helper.clone()));
+    }
+}
+
+#[cfg(unix)]
+async fn bind_socket(path: &std::path::Path) -> io::Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if path.exists() {
+        // 桌面端和 daemon 可能同时运行, 已有实例在监听时不抢占
+        if tokio::net::UnixStream::connect(path).await.is_ok() {
+            return Err(io::Error::new(io::ErrorKind::AddrInUse, format!("{} is in use", path.display())));
+        }
+        std::fs::remove_file(path)?;
+    }
+    let listener = tokio::net::UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+// 只接受同一用户的连接
+#[cfg(unix)]
+fn check_peer(stream: &tokio::net::UnixStream) -> io::Result<()> {
+    let cred = stream.peer_cred()?;
+    let uid = unsafe { libc::getuid() };
+    if cred.uid() != uid {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("peer uid {} (pid {:?}) is not {}", cred.uid(), cred.pid(), uid),
+        ));
+    }
+    Ok(())
+}
+
+// pipe 名固定, 其他用户可以连接或抢先创建同名的 pipe. 只创建第一个实例, 已被占用时直接失败,
+// 每个连接再检查客户端进程的用户是否与当前用户相同
+#[cfg(windows)]
+async fn serve(helper: Arc<ClipboardHelper>) -> io::Result<()> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .reject_remote_clients(true)
+        .create(PIPE_NAME)?;
+    info!("IPC server listening on {}", PIPE_NAME);
+    loop {
+        server.connect().await?;
+        let client = server;
+        server = ServerOptions::new().reject_remote_clients(true).create(PIPE_NAME)?;
+        if let Err(e) = check_peer(&client) {
+            warn!("Rejected IPC connection: {}", e);
+            let _ = client.disconnect();
+            continue;
+        }
+        tokio::spawn(handle_connection(client, helper.clone()));
+    }
+}
+
+#[cfg(windows)]
+fn check_peer(pipe: &tokio::net::windows::named_pipe::NamedPipeServer) -> io::Result<()> {
+    use std::os::windows::io::AsRawHandle;
+
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::Security::EqualSid;
+    use windows_sys::Win32::System::Pipes::GetNamedPipeClientProcessId;
+    use windows_sys::Win32::System::Threading::{GetCurrentProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION};
+
+    let mut pid = 0u32;
+    if unsafe { GetNamedPipeClientProcessId(pipe.as_raw_handle() as HANDLE, &mut pid) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid) };
+    if process == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let client = token_user(process);
+    unsafe { CloseHandle(process) };
+    let client = client?;
+    // GetCurrentProcess 返回的伪句柄不需要关闭
+    let current = token_user(unsafe { GetCurrentProcess() })?;
+    if unsafe { EqualSid(client.sid(), current.sid()) } == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("peer pid {} belongs to another user", pid),
+        ));
+    }
+    Ok(())
+}
+
+// GetTokenInformation(TokenUser) 的结果, SID 指向缓冲区内部
+#[cfg(windows)]
+struct TokenUser(Vec<usize>);
+
+#[cfg(windows)]
+impl TokenUser {
+    fn sid(&self) -> windows_sys::Win32::Foundation::PSID {
+        unsafe { (*(self.0.as_ptr() as *const windows_sys::Win32::Security::TOKEN_USER)).User.Sid }
+    }
+}
+
+#[cfg(windows)]
+fn token_user(process: windows_sys::Win32::Foundation::HANDLE) -> io::Result<TokenUser> {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::Security::{GetTokenInformation, TokenUser as TokenUserClass, TOKEN_QUERY};
+    use windows_sys::Win32::System::Threading::OpenProcessToken;
+
+    let mut token = 0;
+    if unsafe { OpenProcessToken(process, TOKEN_QUERY, &mut token) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // 第一次调用只取得需要的长度
+    let mut len = 0u32;
+    unsafe { GetTokenInformation(token, TokenUserClass, std::ptr::null_mut(), 0, &mut len) };
+    let mut buf = vec![0usize; (len as usize).div_ceil(std::mem::size_of::<usize>())];
+    let ok = unsafe { GetTokenInformation(token, TokenUserClass, buf.as_mut_ptr().cast(), len, &mut len) };
+    let result = if ok == 0 { Err(io::Error::last_os_error()) } else { Ok(TokenUser(buf)) };
+    unsafe { CloseHandle(token) };
+    result
+}
+
+#[derive(Deserialize)]
+struct SubscribeParams {
+    // 为空时订阅全部事件
+    #[serde(default)]
+    events: Vec<String>,
+}
+
+async fn handle_connection<S>(stream: S, helper: Arc<ClipboardHelper>)
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    // 响应和订阅通知共用一个写入任务
+    let (sender, mut receiver) = mpsc::unbounded_channel::<String>();
+    let writer_task = tokio::spawn(async move {
+        while let Some(mut line) = receiver.recv().await {
+            line.push('\n');
+            if writer.write_all(line.as_bytes()).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut subscription: Option<JoinHandle<()>> = None;
+    let mut lines = BufReader::new(reader).lines();
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(e) => {
+                warn!("IPC connection error: {}", e);
+                break;
+            },
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match rpc::parse_request(&line) {
+            Ok(request) => {
+                let result = match request.method.as_str() {
+                    "subscribe" => subscribe(request.params, &sender, &mut subscription),
+                    "unsubscribe" => {
+                        let subscribed = subscription.take().map(|task| task.abort()).is_some();
+                        Ok(json!(subscribed))
+                    },
+                    method => rpc::dispatch(&helper, method, request.params).await,
+                };
+                request.id.map(|id| Response::new(id, result))
+            },
+            Err(response) => Some(response),
+        };
+        if let Some(response) = response {
+            send(&sender, &response);
+        }
+    }
+
+    if let Some(task) = subscription {
+        task.abort();
+    }
+    drop(sender);
+    let _ = writer_task.await;
+}
+
+fn subscribe(params: Value, sender: &UnboundedSender<String>, subscription: &mut Option<JoinHandle<()>>) -> Result<Value, RpcError> {
+    let params: SubscribeParams = rpc::parse_params(params)?;
+    if let Some(task) = subscription.take() {
+        task.abort();
+    }
+
+    let filter = params.events.clone();
+    let sender = sender.clone();
+    let mut receiver = events::subscribe();
+    *subscription = Some(tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    if !filter.is_empty() && !filter.iter().any(|name| name == event.name()) {
+                        continue;
+                    }
+                    let data = serde_json::to_value(&event).map(|mut v| v["data"].take()).unwrap_or(Value::Null);
+                    if !send(&sender, &Notification::new(event.name(), data)) {
+                        break;
+                    }
+                },
+                Err(RecvError::Lagged(skipped)) => warn!("IPC subscriber lagged, {} events skipped", skipped),
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }));
+    Ok(json!({ "events": params.events }))
+}
+
+// 连接已关闭时返回 false
+fn send<T: serde::Serialize>(sender: &UnboundedSender<String>, message: &T) -> bool {
+    match serde_json::to_string(message) {
+        Ok(line) => sender.send(line).is_ok(),
+        Err(e) => {
+            error!("Failed to serialize IPC message: {}", e);
+            true
+        },
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_check_peer_same_user() {
+        let (a, _b) = tokio::net::UnixStream::pair().unwrap();
+        assert!(check_peer(&a).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_bind_socket_rejects_live_listener() {
+        let path = std::env::temp_dir().join(format!("supercv_test_{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let listener = bind_socket(&path).await.unwrap();
+        let mode = std::os::unix::fs::PermissionsExt::mode(&std::fs::metadata(&path).unwrap().permissions());
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(bind_socket(&path).await.unwrap_err().kind(), io::ErrorKind::AddrInUse);
+
+        // 残留的 socket 文件可以被替换
+        drop(listener);
+        assert!(bind_socket(&path).await.is_ok());
+        let _ = std::fs::remove_file(&path);
+    }
+}
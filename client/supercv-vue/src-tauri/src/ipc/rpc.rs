@@ -0,0 +1,399 @@
+// JSON-RPC 2.0 消息定义与方法分发, 方法与 rs_invoke_* 命令对应.
+// 导出/导入, 备份, 同步登录和窗口相关的命令只在桌面端提供, 命令行使用 supercv 的子命令
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::clipboard_helper::ClipboardHelper;
+use crate::core::capture;
+use crate::core::clipboard::CAPTURE_METRICS;
+use crate::core::revision::EntryEdit;
+use crate::core::trash::DeleteFilter;
+use crate::db::crud::host_clipboard::Cursor;
+use crate::db::entities::host_clipboard::Model;
+use crate::error::{AppError, AppResult};
+use crate::utils::config::UserConfig;
+
+pub const JSONRPC_VERSION: &str = "2.0";
+
+#[derive(Deserialize, Debug)]
+pub struct Request {
+    pub jsonrpc: String,
+    // 没有 id 的请求是通知, 不需要响应
+    #[serde(default)]
+    pub id: Option<Value>,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Response {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+impl Response {
+    pub fn new(id: Value, result: Result<Value, RpcError>) -> Self {
+        let (result, error) = match result {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+        Self {
+            jsonrpc: JSONRPC_VERSION,
+            id,
+            result,
+            error,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct Notification {
+    jsonrpc: &'static str,
+    method: &'static str,
+    params: Value,
+}
+
+impl Notification {
+    pub fn new(method: &'static str, params: Value) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION,
+            method,
+            params,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    // 方法内部的错误附带 AppError 的 code, 例如 {"code": "capture_paused"}
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    pub fn parse_error(message: impl ToString) -> Self {
+        Self::new(-32700, message)
+    }
+
+    pub fn invalid_request(message: impl ToString) -> Self {
+        Self::new(-32600, message)
+    }
+
+    pub fn method_not_found(method: &str) -> Self {
+        Self::new(-32601, format!("method not found: {}", method))
+    }
+
+    pub fn invalid_params(message: impl ToString) -> Self {
+        Self::new(-32602, message)
+    }
+
+    pub fn internal(message: impl ToString) -> Self {
+        Self::new(-32603, message)
+    }
+
+    fn new(code: i64, message: impl ToString) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+            data: None,
+        }
+    }
+}
+
+impl From<AppError> for RpcError {
+    fn from(e: AppError) -> Self {
+        Self {
+            data: Some(json!({ "code": e.code() })),
+            ..Self::internal(e)
+        }
+    }
+}
+
+// 解析失败时直接返回错误响应, 错误响应只在连接上写出一次, 不需要装箱
+#[allow(clippy::result_large_err)]
+pub fn parse_request(line: &str) -> Result<Request, Response> {
+    let value: Value = serde_json::from_str(line).map_err(|e| Response::new(Value::Null, Err(RpcError::parse_error(e))))?;
+    let id = value.get("id").cloned().unwrap_or(Value::Null);
+    let request: Request = serde_json::from_value(value).map_err(|e| Response::new(id.clone(), Err(RpcError::invalid_request(e))))?;
+    if request.jsonrpc != JSONRPC_VERSION {
+        return Err(Response::new(id, Err(RpcError::invalid_request("jsonrpc must be \"2.0\""))));
+    }
+    Ok(request)
+}
+
+pub fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    // 省略 params 等同于空对象
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(RpcError::invalid_params)
+}
+
+#[derive(Deserialize)]
+struct GetClipboardsParams {
+    num: u64,
+    #[serde(default)]
+    type_list: Option<Vec<i32>>,
+    #[serde(default)]
+    app: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SearchClipboardsParams {
+    query: String,
+    num: u64,
+    #[serde(default)]
+    type_list: Option<Vec<i32>>,
+    #[serde(default)]
+    app: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GetClipboardPageParams {
+    #[serde(default)]
+    query: Option<String>,
+    #[serde(default)]
+    type_list: Option<Vec<i32>>,
+    #[serde(default)]
+    app: Option<String>,
+    #[serde(default)]
+    cursor: Option<Cursor>,
+    limit: u64,
+}
+
+#[derive(Deserialize)]
+struct GetStatsParams {
+    #[serde(default)]
+    days: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct EntryParams {
+    id: i32,
+}
+
+#[derive(Deserialize)]
+struct EditClipboardParams {
+    id: i32,
+    edit: EntryEdit,
+}
+
+#[derive(Deserialize)]
+struct DeleteClipboardsParams {
+    ids: Vec<i32>,
+}
+
+#[derive(Deserialize)]
+struct DeleteByFilterParams {
+    filter: DeleteFilter,
+}
+
+#[derive(Deserialize)]
+struct ClearClipboardsParams {
+    #[serde(default)]
+    include_pinned: bool,
+}
+
+#[derive(Deserialize)]
+struct UndoDeleteParams {
+    batch: i64,
+}
+
+#[derive(Deserialize)]
+struct SetClipboardsParams {
+    item: Model,
+}
+
+#[derive(Deserialize)]
+struct AddClipboardParams {
+    text: String,
+    // 调用方的名字, 记录为来源应用
+    #[serde(default)]
+    app: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PauseCaptureParams {
+    #[serde(default)]
+    minutes: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct SetUserConfigParams {
+    user_config: UserConfig,
+}
+
+pub async fn dispatch(helper: &ClipboardHelper, method: &str, params: Value) -> Result<Value, RpcError> {
+    let result = match method {
+        "get_clipboards" => {
+            let p: GetClipboardsParams = parse_params(params)?;
+            to_value(helper.get_clipboards(p.num, p.type_list, p.app.as_deref()).await)?
+        },
+        "search_clipboards" => {
+            let p: SearchClipboardsParams = parse_params(params)?;
+            let query = p.query.to_lowercase();
+            to_value(helper.search_clipboards(&query, p.num, p.type_list, p.app.as_deref()).await)?
+        },
+        "get_clipboard_page" => {
+            let p: GetClipboardPageParams = parse_params(params)?;
+            to_value(
+                helper
+                    .get_clipboard_page(p.query.as_deref(), p.type_list, p.app.as_deref(), p.cursor, p.limit)
+                    .await,
+            )?
+        },
+        "get_timeline" => {
+            let p: GetClipboardPageParams = parse_params(params)?;
+            to_value(
+                helper
+                    .get_timeline(p.query.as_deref(), p.type_list, p.app.as_deref(), p.cursor, p.limit)
+                    .await,
+            )?
+        },
+        "get_stats" => {
+            let p: GetStatsParams = parse_params(params)?;
+            to_value(helper.get_stats(p.days.unwrap_or(30)).await)?
+        },
+        "edit_clipboard" => {
+            let p: EditClipboardParams = parse_params(params)?;
+            to_value(helper.edit(p.id, p.edit).await)?
+        },
+        "undo_clipboard_edit" => {
+            let p: EntryParams = parse_params(params)?;
+            to_value(helper.undo_edit(p.id).await)?
+        },
+        "get_clipboard_revisions" => {
+            let p: EntryParams = parse_params(params)?;
+            to_value(helper.get_revisions(p.id).await)?
+        },
+        "delete_clipboard" => {
+            let p: EntryParams = parse_params(params)?;
+            to_value(helper.delete(vec![p.id]).await)?
+        },
+        "delete_clipboards" => {
+            let p: DeleteClipboardsParams = parse_params(params)?;
+            to_value(helper.delete(p.ids).await)?
+        },
+        "delete_clipboards_by_filter" => {
+            let p: DeleteByFilterParams = parse_params(params)?;
+            to_value(helper.delete_by_filter(p.filter).await)?
+        },
+        "clear_clipboards" => {
+            let p: ClearClipboardsParams = parse_params(params)?;
+            let filter = DeleteFilter {
+                include_pinned: p.include_pinned,
+                ..Default::default()
+            };
+            to_value(helper.delete_by_filter(filter).await)?
+        },
+        "undo_delete" => {
+            let p: UndoDeleteParams = parse_params(params)?;
+            to_value(helper.undo_delete(p.batch).await)?
+        },
+        "get_source_apps" => to_value(helper.get_source_apps().await)?,
+        "set_clipboards" => {
+            let p: SetClipboardsParams = parse_params(params)?;
+            helper.set(vec![p.item]).await?;
+            json!(true)
+        },
+        "add_clipboard" => {
+            let p: AddClipboardParams = parse_params(params)?;
+            to_value(helper.add_text(p.text, p.app).await)?
+        },
+        "get_capture_metrics" => json!(CAPTURE_METRICS.snapshot()),
+        "get_capture_state" => json!(capture::get_capture_state()),
+        "pause_capture" => {
+            let p: PauseCaptureParams = parse_params(params)?;
+            to_value(capture::pause(p.minutes))?
+        },
+        "resume_capture" => json!(capture::resume()),
+        "get_user_config" => json!(ClipboardHelper::get_user_config().await),
+        "set_user_config" => {
+            let p: SetUserConfigParams = parse_params(params)?;
+            ClipboardHelper::set_user_config(p.user_config).await?;
+            json!(true)
+        },
+        _ => return Err(RpcError::method_not_found(method)),
+    };
+    Ok(result)
+}
+
+fn to_value<T: Serialize>(result: AppResult<T>) -> Result<Value, RpcError> {
+    serde_json::to_value(result?).map_err(RpcError::internal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request() {
+        let request = parse_request(r#"{"jsonrpc":"2.0","id":1,"method":"get_clipboards","params":{"num":5}}"#).unwrap();
+        assert_eq!(request.id, Some(json!(1)));
+        assert_eq!(request.method, "get_clipboards");
+        let params: GetClipboardsParams = parse_params(request.params).unwrap();
+        assert_eq!(params.num, 5);
+        assert!(params.type_list.is_none());
+
+        // 通知没有 id
+        let request = parse_request(r#"{"jsonrpc":"2.0","method":"resume_capture"}"#).unwrap();
+        assert!(request.id.is_none());
+        assert!(parse_params::<PauseCaptureParams>(request.params).unwrap().minutes.is_none());
+    }
+
+    #[test]
+    fn test_parse_history_params() {
+        let params: GetClipboardPageParams = parse_params(json!({"limit": 20, "cursor": {"timestamp": 100, "id": 7}})).unwrap();
+        assert_eq!(params.cursor, Some(Cursor { timestamp: 100, id: 7 }));
+        assert!(params.query.is_none());
+
+        let params: DeleteByFilterParams = parse_params(json!({"filter": {"type_list": [1], "end": 100}})).unwrap();
+        assert_eq!(params.filter.type_list, Some(vec![1]));
+        assert!(!params.filter.include_pinned);
+        assert!(!parse_params::<ClearClipboardsParams>(Value::Null).unwrap().include_pinned);
+
+        let params: EditClipboardParams = parse_params(json!({"id": 3, "edit": {"title": "greeting"}})).unwrap();
+        assert_eq!(
+            (params.id, params.edit.title.as_deref(), params.edit.content),
+            (3, Some("greeting"), None)
+        );
+    }
+
+    #[test]
+    fn test_app_error_code_in_data() {
+        let error = RpcError::from(AppError::CapturePaused);
+        assert_eq!(error.code, -32603);
+        assert_eq!(error.data, Some(json!({ "code": "capture_paused" })));
+    }
+
+    #[test]
+    fn test_parse_request_errors() {
+        let response = serde_json::to_value(parse_request("{not json").unwrap_err()).unwrap();
+        assert_eq!(response["error"]["code"], -32700);
+        assert_eq!(response["id"], Value::Null);
+
+        let response = serde_json::to_value(parse_request(r#"{"jsonrpc":"1.0","id":"a","method":"x"}"#).unwrap_err()).unwrap();
+        assert_eq!(response["error"]["code"], -32600);
+        assert_eq!(response["id"], "a");
+
+        let err = parse_params::<GetClipboardsParams>(json!({"num": "ten"})).err().unwrap();
+        assert_eq!(err.code, -32602);
+    }
+
+    #[test]
+    fn test_serialize_response() {
+        let ok = serde_json::to_string(&Response::new(json!(1), Ok(json!(true)))).unwrap();
+        assert_eq!(ok, r#"{"jsonrpc":"2.0","id":1,"result":true}"#);
+        let err = serde_json::to_string(&Response::new(json!(2), Err(RpcError::method_not_found("foo")))).unwrap();
+        assert_eq!(
+            err,
+            r#"{"jsonrpc":"2.0","id":2,"error":{"code":-32601,"message":"method not found: foo"}}"#
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use tauri::Manager;
+#[allow(unused_imports)]
+use window_vibrancy::{apply_blur, apply_vibrancy, NSVisualEffectMaterial};
+
+use crate::clipboard_helper::{
+    rs_invoke_backup_now, rs_invoke_clear_clipboards, rs_invoke_delete_clipboard, rs_invoke_delete_clipboards, rs_invoke_delete_clipboards_by_filter,
+    rs_invoke_edit_clipboard, rs_invoke_export_clipboards, rs_invoke_get_capture_metrics, rs_invoke_get_capture_state, rs_invoke_get_clipboard_page,
+    rs_invoke_get_clipboard_revisions, rs_invoke_get_clipboards, rs_invoke_get_source_apps, rs_invoke_get_stats, rs_invoke_get_timeline,
+    rs_invoke_get_user_config, rs_invoke_import_clipboards, rs_invoke_import_from_app, rs_invoke_list_backups, rs_invoke_logout,
+    rs_invoke_open_settings, rs_invoke_pause_capture, rs_invoke_restore_backup, rs_invoke_resume_capture, rs_invoke_search_clipboards,
+    rs_invoke_set_clipboards, rs_invoke_set_user_config, rs_invoke_start_sync, rs_invoke_undo_clipboard_edit, rs_invoke_undo_delete, ClipboardHelper,
+};
+use crate::shortcut::{rs_invoke_register_global_shortcut, MainGlobalShortcut};
+
+#[cfg(target_os = "linux")]
+use {
+    log::warn,
+    x11rb::{connection::Connection, rust_connection::RustConnection},
+};
+
+pub mod api;
+pub mod clipboard_helper;
+pub mod core;
+pub mod db;
+pub mod error;
+pub mod ipc;
+mod shortcut;
+mod tray;
+pub mod utils;
+
+// 桌面端入口, 无界面的 daemon 和命令行见 bin/supercv.rs
+pub async fn run() {
+    // tauri 的异步任务和采集, 同步任务共用 main 中的运行时
+    tauri::async_runtime::set(tokio::runtime::Handle::current());
+    let clipboard_helper = match ClipboardHelper::new(None, Some(2)).await {
+        Ok(clipboard_helper) => Arc::new(clipboard_helper),
+        Err(e) => {
+            log::error!("Failed to open database: {}", e);
+            return;
+        },
+    };
+    ipc::start_server(clipboard_helper.clone());
+    let recovery = clipboard_helper.recovery().cloned();
+    // let clipboard_helper_clone = clipboard_helper.clone();
+
+    tauri::Builder::default()
+        .setup(move |app| {
+            let app_handle = app.handle();
+            // windows
+            let main_window = app.get_window("main").unwrap();
+            #[cfg(target_os = "macos")]
+            apply_vibrancy(&main_window, NSVisualEffectMaterial::HudWindow, None, Some(12.0))
+                .expect("Unsupported platform! 'apply_vibrancy' is only supported on macOS");
+
+            // #[cfg(target_os = "windows")]
+            // apply_blur(&main_window, None).expect("Unsupported platform! 'apply_blur' is only supported on Windows");
+            #[cfg(target_os = "windows")]
+            main_window.set_decorations(false).unwrap();
+
+            main_window.set_skip_taskbar(true).unwrap(); // 去除linux 唤出通知
+            let main_handle = main_window.clone();
+            main_handle.set_decorations(false).unwrap();
+            let settings_window = app.get_window("settings").unwrap();
+            settings_window.hide()?;
+
+            // 注册全局快捷键
+            let main_handle: tauri::Window = main_window.clone();
+            let mut main_global_shortcut = MainGlobalShortcut::new(main_handle);
+            // 纯 Wayland 下没有 X11, 跳过窗口激活而不是 panic
+            #[cfg(target_os = "linux")]
+            match RustConnection::connect(None) {
+                Ok((x11_conn, x11_screen_num)) => {
+                    let x11_conn = Arc::new(x11_conn);
+                    let x11_screen = x11_conn.setup().roots[x11_screen_num].clone();
+
+                    let x11_conn_clone = Arc::clone(&x11_conn);
+                    let x11_screen_clone = x11_screen.clone();
+                    main_global_shortcut.set_x11(x11_conn_clone, x11_screen_clone);
+                },
+                Err(e) => warn!("Failed to connect to X11, window activation is disabled: {}", e),
+            }
+            main_global_shortcut.register(&app_handle, "CommandOrControl+Shift+C")?;
+            app.manage(main_global_shortcut);
+            tauri::async_runtime::spawn(tray::watch_capture_state(app_handle.clone()));
+            tauri::async_runtime::spawn(clipboard_helper::watch_transfer_progress(app_handle.clone()));
+            if let Some(report) = &recovery {
+                tray::notify(&app_handle, "SuperCV 数据库异常", &report.message());
+            }
+
+            // 添加失去焦点事件处理
+            let window_handle = main_window.clone();
+            main_window.on_window_event(move |event| match event {
+                tauri::WindowEvent::CloseRequested { .. } => {
+                    window_handle.hide().unwrap();
+                },
+                tauri::WindowEvent::Focused(false) => {
+                    window_handle.hide().unwrap();
+                },
+                _ => {},
+            });
+
+            let settings_handle = settings_window.clone();
+            settings_window.on_window_event(move |event| {
+                if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                    // 阻止窗口关闭
+                    api.prevent_close();
+                    // 仅隐藏窗口
+                    settings_handle.hide().unwrap();
+                }
+            });
+
+            // 添加程序退出时的清理操作
+            // let clipboard_helper = clipboard_helper_clone.clone();
+            Ok(())
+        })
+        .system_tray(tray::build_system_tray())
+        .on_system_tray_event(tray::handle_system_tray_event)
+        .manage(clipboard_helper)
+        .invoke_handler(tauri::generate_handler![
+            rs_invoke_get_clipboards,
+            rs_invoke_get_clipboard_page,
+            rs_invoke_get_timeline,
+            rs_invoke_get_stats,
+            rs_invoke_edit_clipboard,
+            rs_invoke_undo_clipboard_edit,
+            rs_invoke_get_clipboard_revisions,
+            rs_invoke_delete_clipboard,
+            rs_invoke_delete_clipboards,
+            rs_invoke_delete_clipboards_by_filter,
+            rs_invoke_clear_clipboards,
+            rs_invoke_undo_delete,
+            rs_invoke_search_clipboards,
+            rs_invoke_get_source_apps,
+            rs_invoke_export_clipboards,
+            rs_invoke_import_clipboards,
+            rs_invoke_import_from_app,
+            rs_invoke_backup_now,
+            rs_invoke_list_backups,
+            rs_invoke_restore_backup,
+            rs_invoke_get_capture_metrics,
+            rs_invoke_get_capture_state,
+            rs_invoke_pause_capture,
+            rs_invoke_resume_capture,
+            rs_invoke_start_sync,
+            rs_invoke_logout,
+            rs_invoke_set_clipboards,
+            rs_invoke_get_user_config,
+            rs_invoke_set_user_config,
+            rs_invoke_open_settings,
+            rs_invoke_register_global_shortcut
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
@@ -1,6 +1,9 @@
+use log::error;
 use std::sync::Mutex;
 use tauri::{AppHandle, GlobalShortcutManager, Window};
 
+use crate::error::AppResult;
+
 #[cfg(target_os = "linux")]
 use {
     crate::utils,
@@ -43,13 +46,13 @@ impl MainGlobalShortcut {
         self.x11_screen = Some(screen);
     }
 
-    pub fn register(&self, app_handle: &AppHandle, new_shortcut: &str) -> Result<bool, String> {
+    pub fn register(&self, app_handle: &AppHandle, new_shortcut: &str) -> AppResult<bool> {
         let mut shortcut_manager = app_handle.global_shortcut_manager();
         let mut current_shortcut = self.shortcut.lock().unwrap();
 
         // 如果有之前的快捷键，先注销它
         if let Some(old_shortcut) = current_shortcut.as_ref() {
-            shortcut_manager.unregister(old_shortcut).map_err(|e| e.to_string())?;
+            shortcut_manager.unregister(old_shortcut)?;
         }
 
         // 注册新的快捷键
@@ -57,29 +60,26 @@ impl MainGlobalShortcut {
         #[cfg(target_os = "linux")]
         let (x11_conn, x11_screen) = (self.x11_conn.clone(), self.x11_screen.clone());
 
-        shortcut_manager
-            .register(new_shortcut, move || {
-                let window = window.clone();
-                #[cfg(target_os = "linux")]
-                let (x11_conn, x11_screen) = (x11_conn.clone(), x11_screen.clone());
-                tauri::async_runtime::spawn(async move {
-                    if window.is_visible().unwrap() {
-                        window.hide().unwrap();
-                    } else {
-                        window.show().unwrap();
-                        window.set_focus().unwrap();
-
-                        #[cfg(target_os = "linux")]
+        shortcut_manager.register(new_shortcut, move || {
+            let window = window.clone();
+            #[cfg(target_os = "linux")]
+            let (x11_conn, x11_screen) = (x11_conn.clone(), x11_screen.clone());
+            tauri::async_runtime::spawn(async move {
+                match toggle_window(&window) {
+                    #[cfg(target_os = "linux")]
+                    Ok(true) => {
                         if let (Some(conn), Some(screen)) = (x11_conn.as_ref(), x11_screen.as_ref()) {
                             tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-                            if let Err(e) = utils::x11_window::activate_window(&conn, &screen, "SuperCV") {
-                                eprintln!("Failed to activate window: {}", e);
+                            if let Err(e) = utils::x11_window::activate_window(conn, screen, "SuperCV") {
+                                error!("Failed to activate window: {}", e);
                             }
                         }
-                    }
-                });
-            })
-            .map_err(|e| e.to_string())?;
+                    },
+                    Ok(_) => {},
+                    Err(e) => error!("Failed to toggle main window: {}", e),
+                }
+            });
+        })?;
 
         // 更新当前注册的快捷键
         *current_shortcut = Some(new_shortcut.to_string());
@@ -88,13 +88,21 @@ impl MainGlobalShortcut {
     }
 }
 
+// 主窗口可见时隐藏, 否则显示并聚焦, 返回是否显示
+fn toggle_window(window: &Window) -> AppResult<bool> {
+    if window.is_visible()? {
+        window.hide()?;
+        Ok(false)
+    } else {
+        window.show()?;
+        window.set_focus()?;
+        Ok(true)
+    }
+}
+
 // Tauri命令
 #[tauri::command]
-pub fn rs_invoke_register_global_shortcut(
-    app_handle: AppHandle,
-    shortcut: String,
-    state: tauri::State<'_, MainGlobalShortcut>,
-) -> Result<bool, String> {
+pub fn rs_invoke_register_global_shortcut(app_handle: AppHandle, shortcut: String, state: tauri::State<'_, MainGlobalShortcut>) -> AppResult<bool> {
     state.register(&app_handle, &shortcut)
 }
 
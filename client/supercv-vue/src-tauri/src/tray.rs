@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use chrono::{Local, TimeZone};
+use log::error;
+use tauri::api::notification::Notification;
+use tauri::{AppHandle, CustomMenuItem, Manager, SystemTray, SystemTrayEvent, SystemTrayMenu};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::clipboard_helper::ClipboardHelper;
+use crate::core::capture::{self, CaptureState};
+use crate::core::events::{self, ClipboardEvent};
+
+const TOGGLE_CAPTURE: &str = "toggle_capture";
+const PAUSE_CAPTURE_HOUR: &str = "pause_capture_hour";
+
+pub fn build_system_tray() -> SystemTray {
+    let quit = CustomMenuItem::new("quit".to_string(), "退出");
+    let show_window = CustomMenuItem::new("show_window".to_string(), "显示页面");
+    let setting = CustomMenuItem::new("setting".to_string(), "设置");
+    let toggle_capture = CustomMenuItem::new(TOGGLE_CAPTURE.to_string(), capture_item_title(capture::get_capture_state()));
+    let pause_capture_hour = CustomMenuItem::new(PAUSE_CAPTURE_HOUR.to_string(), "暂停记录 1 小时");
+    let tray_menu = SystemTrayMenu::new()
+        .add_item(show_window)
+        .add_item(setting)
+        .add_item(toggle_capture)
+        .add_item(pause_capture_hour)
+        .add_item(quit);
+    SystemTray::new().with_menu(tray_menu)
+}
+
+pub fn handle_system_tray_event(app: &AppHandle, event: SystemTrayEvent) {
+    if let SystemTrayEvent::MenuItemClick { id, .. } = event {
+        match id.as_str() {
+            "quit" => quit(app),
+            "show_window" => show_window(app, "main"),
+            "setting" => show_window(app, "settings"),
+            TOGGLE_CAPTURE => {
+                capture::toggle();
+            },
+            PAUSE_CAPTURE_HOUR => {
+                if let Err(e) = capture::pause(Some(60)) {
+                    error!("Failed to pause capture: {}", e);
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
+// 等待队列中的剪贴板内容写入数据库后再退出
+fn quit(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let clipboard_helper = app.state::<Arc<ClipboardHelper>>().inner().clone();
+        clipboard_helper.shutdown().await;
+        app.exit(0);
+    });
+}
+
+fn show_window(app: &AppHandle, label: &str) {
+    if let Some(window) = app.get_window(label) {
+        if let Err(e) = window.show().and_then(|_| window.set_focus()) {
+            error!("Failed to show window {}: {}", label, e);
+        }
+    }
+}
+
+pub fn notify(app: &AppHandle, title: &str, body: &str) {
+    let notification = Notification::new(&app.config().tauri.bundle.identifier).title(title).body(body);
+    if let Err(e) = notification.show() {
+        error!("Failed to show notification: {}", e);
+    }
+}
+
+// 采集状态可能来自托盘, 前端或 IPC, 统一通过事件总线刷新
+pub async fn watch_capture_state(app: AppHandle) {
+    let mut receiver = events::subscribe();
+    loop {
+        match receiver.recv().await {
+            Ok(ClipboardEvent::CaptureStateChanged(state)) => on_capture_state_changed(&app, state),
+            Ok(_) => {},
+            Err(RecvError::Lagged(_)) => on_capture_state_changed(&app, capture::get_capture_state()),
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+// 更新托盘菜单并通知前端. 定时暂停到期时 capture 模块会再发布一次 Active
+fn on_capture_state_changed(app: &AppHandle, state: CaptureState) {
+    if let Err(e) = app.tray_handle().get_item(TOGGLE_CAPTURE).set_title(capture_item_title(state)) {
+        error!("Failed to update tray item: {}", e);
+    }
+    if let Err(e) = app.emit_all("capture-state-changed", state) {
+        error!("Failed to emit capture state: {}", e);
+    }
+}
+
+fn capture_item_title(state: CaptureState) -> String {
+    match state {
+        CaptureState::Active => "暂停记录".to_string(),
+        CaptureState::Paused => "恢复记录".to_string(),
+        CaptureState::PausedUntil { until } => match Local.timestamp_opt(until, 0).single() {
+            Some(time) => format!("恢复记录 (暂停至 {})", time.format("%H:%M")),
+            None => "恢复记录".to_string(),
+        },
+    }
+}
@@ -1,49 +1,264 @@
 use aes_gcm::{
-	aead::{Aead, KeyInit, OsRng},
+	aead::{
+		generic_array::GenericArray,
+		stream::{DecryptorBE32, EncryptorBE32},
+		Aead, KeyInit, OsRng,
+	},
 	AeadCore, Aes256Gcm, Nonce,
 };
+use hmac::{Hmac, Mac};
 use pbkdf2::pbkdf2_hmac_array;
 use rand::Rng;
 use sha2::Sha256; // For generating random salt
+use std::fmt;
+use std::path::Path;
+use tokio::fs::File;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader, BufWriter};
+
+use crate::utils::base64::{decode_base64, encode_base64};
 
 const ITERATIONS: u32 = 100_000;
 const SALT_SIZE: usize = 16;
+const NONCE_SIZE: usize = 12;
+const TAG_SIZE: usize = 16;
+
+// 分段加密的文件: magic + nonce 前缀 + 各段密文, 每段明文 FILE_SEGMENT_SIZE 字节
+const FILE_MAGIC: &[u8; 4] = b"SCVS";
+const FILE_NONCE_PREFIX_SIZE: usize = NONCE_SIZE - 5; // STREAM 占用 4 字节计数和 1 字节结尾标记
+const FILE_SEGMENT_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CipherError {
+	InvalidData(String), // 长度不对或 base64 解码失败
+	EncryptFailed,       // 加密失败, 例如分段数超出 nonce 的范围
+	DecryptFailed,       // 密钥错误或数据被篡改
+	Io(String),          // 读写文件失败
+}
+
+impl fmt::Display for CipherError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			CipherError::InvalidData(e) => write!(f, "Invalid cipher data: {}", e),
+			CipherError::EncryptFailed => write!(f, "Encrypt failed"),
+			CipherError::DecryptFailed => write!(f, "Decrypt failed"),
+			CipherError::Io(e) => write!(f, "Cipher io error: {}", e),
+		}
+	}
+}
+
+impl From<std::io::Error> for CipherError {
+	fn from(e: std::io::Error) -> Self {
+		CipherError::Io(e.to_string())
+	}
+}
+
+impl std::error::Error for CipherError {}
+
+// 用 super_key 派生的 KEK 加密 DEK, 输出为 salt + nonce + 密文, 解密时从中取回 salt
 pub struct CryptoHelper {
 	cipher: Aes256Gcm,
+	salt: [u8; SALT_SIZE],
 }
 
 impl CryptoHelper {
-	pub fn new(super_key: &String) -> CryptoHelper {
+	pub fn new(super_key: &str) -> CryptoHelper {
 		let mut rng = rand::thread_rng();
 		let salt: [u8; SALT_SIZE] = rng.gen(); // Generate random bytes for the salt
+		Self::with_salt(super_key, salt)
+	}
+
+	fn with_salt(super_key: &str, salt: [u8; SALT_SIZE]) -> CryptoHelper {
 		let kek = pbkdf2_hmac_array::<Sha256, 32>(super_key.as_bytes(), &salt, ITERATIONS);
 		let cipher = Aes256Gcm::new_from_slice(&kek).unwrap();
-		CryptoHelper { cipher }
+		CryptoHelper { cipher, salt }
 	}
 
 	pub fn encode_dek(&self, dek: &[u8]) -> Vec<u8> {
 		let nonce = Aes256Gcm::generate_nonce(&mut OsRng); // 随机生成 nonce
 		let ciphertext = self.cipher.encrypt(&nonce, dek).unwrap();
-		let mut result = nonce.to_vec();
+		let mut result = self.salt.to_vec();
+		result.extend_from_slice(&nonce);
 		result.extend_from_slice(&ciphertext);
 		result
 	}
 
-	pub fn decode_dek(&self, e_dek: &[u8]) -> Vec<u8> {
-		let nonce = Nonce::from_slice(&e_dek[0..12]);
-		self.cipher.decrypt(nonce, &e_dek[12..]).unwrap()
+	// 截断或被篡改的数据返回错误
+	pub fn decode_dek(&self, e_dek: &[u8]) -> Result<Vec<u8>, CipherError> {
+		if e_dek.len() <= SALT_SIZE + NONCE_SIZE {
+			return Err(CipherError::InvalidData("encrypted dek is too short".to_string()));
+		}
+		let nonce = Nonce::from_slice(&e_dek[SALT_SIZE..SALT_SIZE + NONCE_SIZE]);
+		self.cipher
+			.decrypt(nonce, &e_dek[SALT_SIZE + NONCE_SIZE..])
+			.map_err(|_| CipherError::DecryptFailed)
+	}
+
+	// 用 super_key 解开 User.encrypted_dek (base64)
+	pub fn open_dek(super_key: &str, encrypted_dek: &str) -> Result<Vec<u8>, CipherError> {
+		let e_dek = decode_base64(encrypted_dek).map_err(|e| CipherError::InvalidData(e.to_string()))?;
+		if e_dek.len() <= SALT_SIZE + NONCE_SIZE {
+			return Err(CipherError::InvalidData("encrypted dek is too short".to_string()));
+		}
+		let mut salt = [0u8; SALT_SIZE];
+		salt.copy_from_slice(&e_dek[..SALT_SIZE]);
+		Self::with_salt(super_key, salt).decode_dek(&e_dek)
 	}
 
 	pub fn gen_dek() -> [u8; 32] {
 		let mut rng = rand::thread_rng();
 		let dek: [u8; 32] = rng.gen();
-		return dek
+		dek
+	}
+}
+
+// 同步数据的端到端加密, 由 DEK 派生出加密密钥和 HMAC 密钥, 服务端只能看到密文和带密钥的哈希
+#[derive(Clone)]
+pub struct PayloadCipher {
+	cipher: Aes256Gcm,
+	hash_key: [u8; 32],
+}
+
+impl PayloadCipher {
+	pub fn new(dek: &[u8]) -> PayloadCipher {
+		let content_key = derive_key(dek, b"supercv-content");
+		PayloadCipher {
+			cipher: Aes256Gcm::new_from_slice(&content_key).unwrap(),
+			hash_key: derive_key(dek, b"supercv-hash"),
+		}
+	}
+
+	// nonce + 密文
+	pub fn encrypt(&self, data: &[u8]) -> Vec<u8> {
+		let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+		let ciphertext = self.cipher.encrypt(&nonce, data).unwrap();
+		let mut result = nonce.to_vec();
+		result.extend_from_slice(&ciphertext);
+		result
+	}
+
+	pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, CipherError> {
+		if data.len() < NONCE_SIZE {
+			return Err(CipherError::InvalidData("ciphertext is too short".to_string()));
+		}
+		let nonce = Nonce::from_slice(&data[..NONCE_SIZE]);
+		self.cipher.decrypt(nonce, &data[NONCE_SIZE..]).map_err(|_| CipherError::DecryptFailed)
+	}
+
+	// 分段加密文件 (STREAM), 内存占用与文件大小无关
+	pub async fn encrypt_file(&self, src: &Path, dst: &Path) -> Result<(), CipherError> {
+		let mut reader = BufReader::new(File::open(src).await?);
+		let mut writer = BufWriter::new(File::create(dst).await?);
+		let prefix: [u8; FILE_NONCE_PREFIX_SIZE] = rand::thread_rng().gen();
+		writer.write_all(FILE_MAGIC).await?;
+		writer.write_all(&prefix).await?;
+
+		let mut encryptor = EncryptorBE32::from_aead(self.cipher.clone(), GenericArray::from_slice(&prefix));
+		let mut current = vec![0u8; FILE_SEGMENT_SIZE];
+		let mut next = vec![0u8; FILE_SEGMENT_SIZE];
+		let mut current_len = read_full(&mut reader, &mut current).await?;
+		loop {
+			// 多读一段才能确定当前段是否为最后一段
+			let next_len = if current_len < FILE_SEGMENT_SIZE {
+				0
+			} else {
+				read_full(&mut reader, &mut next).await?
+			};
+			if next_len == 0 {
+				let segment = encryptor.encrypt_last(&current[..current_len]).map_err(|_| CipherError::EncryptFailed)?;
+				writer.write_all(&segment).await?;
+				break;
+			}
+			let segment = encryptor.encrypt_next(&current[..current_len]).map_err(|_| CipherError::EncryptFailed)?;
+			writer.write_all(&segment).await?;
+			std::mem::swap(&mut current, &mut next);
+			current_len = next_len;
+		}
+		writer.flush().await?;
+		Ok(())
+	}
+
+	// 兼容整体加密的旧文件 (nonce + 密文), 截断或篡改过的文件解密失败
+	pub async fn decrypt_file(&self, src: &Path, dst: &Path) -> Result<(), CipherError> {
+		let mut reader = BufReader::new(File::open(src).await?);
+		let mut header = [0u8; FILE_MAGIC.len() + FILE_NONCE_PREFIX_SIZE];
+		let header_len = read_full(&mut reader, &mut header).await?;
+		if header_len < header.len() || &header[..FILE_MAGIC.len()] != FILE_MAGIC {
+			let mut data = header[..header_len].to_vec();
+			reader.read_to_end(&mut data).await?;
+			return Ok(tokio::fs::write(dst, self.decrypt(&data)?).await?);
+		}
+
+		let mut writer = BufWriter::new(File::create(dst).await?);
+		let prefix = GenericArray::from_slice(&header[FILE_MAGIC.len()..]);
+		let mut decryptor = DecryptorBE32::from_aead(self.cipher.clone(), prefix);
+		let mut current = vec![0u8; FILE_SEGMENT_SIZE + TAG_SIZE];
+		let mut next = vec![0u8; FILE_SEGMENT_SIZE + TAG_SIZE];
+		let mut current_len = read_full(&mut reader, &mut current).await?;
+		loop {
+			let next_len = if current_len < current.len() {
+				0
+			} else {
+				read_full(&mut reader, &mut next).await?
+			};
+			if next_len == 0 {
+				let segment = decryptor.decrypt_last(&current[..current_len]).map_err(|_| CipherError::DecryptFailed)?;
+				writer.write_all(&segment).await?;
+				break;
+			}
+			let segment = decryptor.decrypt_next(&current[..current_len]).map_err(|_| CipherError::DecryptFailed)?;
+			writer.write_all(&segment).await?;
+			std::mem::swap(&mut current, &mut next);
+			current_len = next_len;
+		}
+		writer.flush().await?;
+		Ok(())
+	}
+
+	pub fn encrypt_str(&self, text: &str) -> String {
+		encode_base64(&self.encrypt(text.as_bytes()))
+	}
+
+	pub fn decrypt_str(&self, encoded: &str) -> Result<String, CipherError> {
+		let data = decode_base64(encoded).map_err(|e| CipherError::InvalidData(e.to_string()))?;
+		String::from_utf8(self.decrypt(&data)?).map_err(|e| CipherError::InvalidData(e.to_string()))
+	}
+
+	// 对 hash_str / hash_vec 的结果再做一次 HMAC, 同一用户的设备之间结果一致.
+	// 截取前 8 字节, 与 xxh3 的长度相同, 符合服务端 hash 列的长度
+	pub fn keyed_hash(&self, hash: &str) -> String {
+		hmac_sha256(&self.hash_key, hash.as_bytes())[..8]
+			.iter()
+			.map(|b| format!("{:02x}", b))
+			.collect()
 	}
 }
 
+// 读满 buffer, 只有到达文件末尾时才会少于 buffer 的长度
+async fn read_full<R: AsyncRead + Unpin>(reader: &mut R, buffer: &mut [u8]) -> Result<usize, CipherError> {
+	let mut len = 0;
+	while len < buffer.len() {
+		match reader.read(&mut buffer[len..]).await? {
+			0 => break,
+			n => len += n,
+		}
+	}
+	Ok(len)
+}
+
+fn derive_key(dek: &[u8], label: &[u8]) -> [u8; 32] {
+	hmac_sha256(dek, label)
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+	let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).unwrap();
+	mac.update(data);
+	mac.finalize().into_bytes().into()
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::db::testing::TempDir;
 	use rand::Rng;
 
 	#[test]
@@ -62,10 +277,16 @@ mod tests {
 		assert!(encoded_dek.len() > 12);
 
 		// 解码 DEK
-		let decoded_dek = crypto_helper.decode_dek(&encoded_dek);
+		let decoded_dek = crypto_helper.decode_dek(&encoded_dek).unwrap();
 
 		// 验证解码后的 DEK 与原始 DEK 相同
 		assert_eq!(dek.to_vec(), decoded_dek);
+
+		// 截断或被篡改时返回错误而不是 panic
+		assert!(matches!(crypto_helper.decode_dek(&encoded_dek[..SALT_SIZE]), Err(CipherError::InvalidData(_))));
+		let mut tampered = encoded_dek.clone();
+		*tampered.last_mut().unwrap() ^= 1;
+		assert_eq!(crypto_helper.decode_dek(&tampered), Err(CipherError::DecryptFailed));
 	}
 
 	#[test]
@@ -98,4 +319,62 @@ mod tests {
 		// 确保不同的super_key产生不同的编码结果
 		assert_ne!(encoded_dek1, encoded_dek2);
 	}
+
+	#[test]
+	fn test_open_dek_with_super_key() {
+		let super_key = "test_super_key".to_string();
+		let dek = CryptoHelper::gen_dek();
+		let encrypted_dek = encode_base64(&CryptoHelper::new(&super_key).encode_dek(&dek));
+
+		// 新的 CryptoHelper 使用不同的 salt, 只能通过 open_dek 解开
+		assert_eq!(CryptoHelper::open_dek(&super_key, &encrypted_dek).unwrap(), dek.to_vec());
+		assert_eq!(CryptoHelper::open_dek("wrong_key", &encrypted_dek), Err(CipherError::DecryptFailed));
+		assert!(matches!(CryptoHelper::open_dek(&super_key, "AAAA"), Err(CipherError::InvalidData(_))));
+	}
+
+	#[test]
+	fn test_payload_cipher() {
+		let dek = CryptoHelper::gen_dek();
+		let cipher = PayloadCipher::new(&dek);
+
+		let encrypted = cipher.encrypt_str("剪贴板内容");
+		assert_ne!(encrypted, cipher.encrypt_str("剪贴板内容"));
+		assert_eq!(cipher.decrypt_str(&encrypted).unwrap(), "剪贴板内容");
+
+		// 其他用户的 DEK 无法解密
+		let other = PayloadCipher::new(&CryptoHelper::gen_dek());
+		assert_eq!(other.decrypt_str(&encrypted), Err(CipherError::DecryptFailed));
+
+		// 带密钥的哈希: 同一 DEK 结果稳定, 不同 DEK 结果不同
+		assert_eq!(cipher.keyed_hash("abc"), PayloadCipher::new(&dek).keyed_hash("abc"));
+		assert_ne!(cipher.keyed_hash("abc"), other.keyed_hash("abc"));
+		assert_eq!(cipher.keyed_hash("abc").len(), 16);
+	}
+
+	#[tokio::test]
+	async fn test_encrypt_file() {
+		let dir = TempDir::new("cipher");
+		let cipher = PayloadCipher::new(&CryptoHelper::gen_dek());
+		let (src, encrypted, decrypted) = (dir.join("src"), dir.join("encrypted"), dir.join("decrypted"));
+
+		// 空文件, 不足一段, 正好一段, 多段
+		for len in [0, 100, FILE_SEGMENT_SIZE, FILE_SEGMENT_SIZE * 2 + 7] {
+			let data: Vec<u8> = (0..len).map(|_| rand::thread_rng().gen()).collect();
+			std::fs::write(&src, &data).unwrap();
+			cipher.encrypt_file(&src, &encrypted).await.unwrap();
+			cipher.decrypt_file(&encrypted, &decrypted).await.unwrap();
+			assert_eq!(std::fs::read(&decrypted).unwrap(), data, "len {}", len);
+		}
+
+		// 截断到段边界
+		let encrypted_data = std::fs::read(&encrypted).unwrap();
+		let header_len = FILE_MAGIC.len() + FILE_NONCE_PREFIX_SIZE;
+		std::fs::write(&encrypted, &encrypted_data[..header_len + FILE_SEGMENT_SIZE + TAG_SIZE]).unwrap();
+		assert_eq!(cipher.decrypt_file(&encrypted, &decrypted).await, Err(CipherError::DecryptFailed));
+
+		// 整体加密的旧文件
+		std::fs::write(&encrypted, cipher.encrypt(b"legacy")).unwrap();
+		cipher.decrypt_file(&encrypted, &decrypted).await.unwrap();
+		assert_eq!(std::fs::read(&decrypted).unwrap(), b"legacy");
+	}
 }
@@ -1,5 +1,5 @@
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::RwLock;
 use std::{env, fs};
 
@@ -44,12 +44,97 @@ pub struct PreviewConfig {
     pub preview_number: u32,
 }
 
+// PRIMARY 与 CLIPBOARD 之间的同步方向
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PrimarySync {
+    Off,
+    PrimaryToClipboard,
+    ClipboardToPrimary,
+    Both,
+}
+
+impl PrimarySync {
+    pub fn to_clipboard(&self) -> bool {
+        matches!(self, PrimarySync::PrimaryToClipboard | PrimarySync::Both)
+    }
+
+    pub fn to_primary(&self) -> bool {
+        matches!(self, PrimarySync::ClipboardToPrimary | PrimarySync::Both)
+    }
+}
+
+// X11 PRIMARY selection (选中即复制, 中键粘贴)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PrimaryConfig {
+    pub capture: bool,
+    // 拖选时 selection 会连续变化, 静默超过该时间才记录
+    pub debounce_ms: u64,
+    pub sync: PrimarySync,
+}
+
+// 剪贴板监听的合并与批量写入
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CaptureConfig {
+    // 连续变化时静默超过该时间才读取, 期间的通知合并为一次
+    pub debounce_ms: u64,
+    // 持续变化时最多等待的时间, 避免一直不记录
+    pub max_wait_ms: u64,
+    // 单个事务写入的最大条数
+    pub batch_size: usize,
+    // 待处理队列长度, 超出的事件直接丢弃
+    pub queue_size: usize,
+}
+
+// 本地 JSON-RPC 服务 (Unix socket / named pipe)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IpcConfig {
+    pub enabled: bool,
+}
+
+// 与服务端同步, 需要先登录并注册设备
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct SyncConfig {
+    pub enabled: bool,
+    pub server_url: String,
+    pub interval_secs: u64, // 两次同步的间隔
+    pub window_secs: i64,   // 每次请求覆盖的时间窗口
+    pub max_retries: u32,   // 网络错误的重试次数
+    pub retry_delay_ms: u64,
+    // 重试后仍失败的上传进入 outbox, 按指数退避重放, 间隔不超过 outbox_max_delay_secs
+    pub outbox_base_delay_secs: u64,
+    pub outbox_max_delay_secs: u64,
+    pub max_sync_bytes: u64, // 每次同步传输的文件总量上限, 0 为不限制
+}
+
+// 定期备份数据库和文件目录, 只保留最近 keep 份
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct BackupConfig {
+    pub enabled: bool,
+    pub interval_hours: u64,
+    pub keep: usize,
+    pub dir: String, // 为空时使用缓存目录下的 backups
+    pub include_files: bool,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct UserConfig {
     pub expired_config: ExpiredConfig,
     pub preview_config: PreviewConfig,
     pub global_shortcut: String,
     pub theme: String,
+    #[serde(default)]
+    pub primary_config: PrimaryConfig,
+    #[serde(default)]
+    pub capture_config: CaptureConfig,
+    #[serde(default)]
+    pub ipc_config: IpcConfig,
+    #[serde(default)]
+    pub sync_config: SyncConfig,
+    #[serde(default)]
+    pub backup_config: BackupConfig,
 }
 
 pub struct Config {
@@ -61,6 +146,12 @@ pub struct Config {
     pub icon_path: PathBuf,
 }
 
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Config {
     pub fn new() -> Self {
         let (cache_dir, config_dir) = get_paths();
@@ -71,7 +162,7 @@ impl Config {
 
         for p in [&db_path, &files_path, &logs_path, &config_dir].iter() {
             if !p.exists() {
-                fs::create_dir_all(p).expect(&format!("创建目录 {} 失败", p.display()));
+                fs::create_dir_all(p).unwrap_or_else(|_| panic!("创建目录 {} 失败", p.display()));
             }
         }
 
@@ -96,10 +187,17 @@ impl Config {
             now - self.user_config.expired_config.file * 24 * 60 * 60,
         )
     }
+
+    pub fn backup_dir(&self) -> PathBuf {
+        match self.user_config.backup_config.dir.as_str() {
+            "" => self.cache_dir.join("backups"),
+            dir => PathBuf::from(dir),
+        }
+    }
 }
 
 impl UserConfig {
-    pub fn load(config_dir: &PathBuf) -> io::Result<Self> {
+    pub fn load(config_dir: &Path) -> io::Result<Self> {
         let file_path = config_dir.join("config.toml");
 
         let config: UserConfig = if file_path.exists() {
@@ -117,27 +215,27 @@ impl UserConfig {
     }
 
     // 同步版本的 save
-    pub fn save(&self, config_path: &PathBuf) -> io::Result<()> {
+    pub fn save(&self, config_path: &Path) -> io::Result<()> {
         let content = toml::to_string(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-        fs::write(&config_path, content)?;
+        fs::write(config_path, content)?;
         Ok(())
     }
 
     // 异步版本的 save
-    pub async fn save_async(&self, config_path: &PathBuf) -> io::Result<()> {
+    pub async fn save_async(&self, config_path: &Path) -> io::Result<()> {
         use tokio::fs;
 
         let content = toml::to_string(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-        fs::write(&config_path, content).await?;
+        fs::write(config_path, content).await?;
         Ok(())
     }
 }
 
+#[rustfmt::skip]
 fn get_paths() -> (PathBuf, PathBuf) {
     let home = dirs::home_dir().expect("读取用户家目录失败");
 
     // 根据操作系统返回不同的路径
-    #[cfg_attr(rustfmt, rustfmt::skip)]
     match env::consts::OS {
         "macos" | "linux" => (
             home.join(".cache").join("super-cv"),
@@ -158,6 +256,104 @@ impl Default for UserConfig {
             preview_config: PreviewConfig { preview_number: 20 },
             global_shortcut: "CommandOrControl+Shift+C".to_string(),
             theme: "system".to_string(),
+            primary_config: PrimaryConfig::default(),
+            capture_config: CaptureConfig::default(),
+            ipc_config: IpcConfig::default(),
+            sync_config: SyncConfig::default(),
+            backup_config: BackupConfig::default(),
         }
     }
 }
+
+impl Default for PrimaryConfig {
+    fn default() -> Self {
+        Self {
+            capture: false,
+            debounce_ms: 500,
+            sync: PrimarySync::Off,
+        }
+    }
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            debounce_ms: 100,
+            max_wait_ms: 1000,
+            batch_size: 32,
+            queue_size: 256,
+        }
+    }
+}
+
+impl Default for IpcConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            server_url: "".to_string(),
+            interval_secs: 300,
+            window_secs: 24 * 60 * 60,
+            max_retries: 3,
+            retry_delay_ms: 1000,
+            outbox_base_delay_secs: 30,
+            outbox_max_delay_secs: 60 * 60,
+            max_sync_bytes: 1024 * 1024 * 1024,
+        }
+    }
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_hours: 24,
+            keep: 7,
+            dir: "".to_string(),
+            include_files: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_config_without_primary_config() {
+        // 旧版本的配置文件没有 primary_config, 不能因此回退到默认配置
+        let content = r#"
+global_shortcut = "CommandOrControl+Shift+L"
+theme = "dark"
+
+[expired_config]
+text = 30
+img = 7
+file = 7
+
+[preview_config]
+preview_number = 50
+"#;
+        let config: UserConfig = toml::from_str(content).unwrap();
+        assert_eq!(config.global_shortcut, "CommandOrControl+Shift+L");
+        assert_eq!(config.expired_config.text, 30);
+        assert!(!config.primary_config.capture);
+        assert_eq!(config.primary_config.sync, PrimarySync::Off);
+        assert_eq!(config.capture_config.debounce_ms, CaptureConfig::default().debounce_ms);
+    }
+
+    #[test]
+    fn test_primary_sync_direction() {
+        let config: PrimaryConfig = toml::from_str("capture = true\ndebounce_ms = 300\nsync = \"both\"").unwrap();
+        assert!(config.sync.to_clipboard());
+        assert!(config.sync.to_primary());
+        assert!(PrimarySync::PrimaryToClipboard.to_clipboard());
+        assert!(!PrimarySync::PrimaryToClipboard.to_primary());
+        assert!(!PrimarySync::ClipboardToPrimary.to_clipboard());
+    }
+}
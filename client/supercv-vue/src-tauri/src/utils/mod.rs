@@ -4,6 +4,7 @@ pub mod config;
 pub mod file;
 pub mod hash;
 pub mod logger;
+pub mod password;
 pub mod time;
 #[cfg(target_os = "linux")]
 pub mod x11_window;
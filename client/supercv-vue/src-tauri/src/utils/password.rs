@@ -0,0 +1,89 @@
+// 登录密码的派生. 新账户使用 Argon2id, salt 保存在服务端, 登录前先取回;
+// 旧账户使用不加盐的 xxh3 (Legacy), 只用于登录后迁移到 Argon2id
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::base64::encode_base64;
+use crate::utils::cipher::CipherError;
+use crate::utils::hash::hash_str;
+
+// OWASP 推荐的最低参数: 19 MiB, 2 次迭代, 1 个并行度
+const ARGON2_MEMORY_KIB: u32 = 19 * 1024;
+const ARGON2_ITERATIONS: u32 = 2;
+const ARGON2_PARALLELISM: u32 = 1;
+const HASH_LEN: usize = 32;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PasswordKdf {
+	#[default]
+	Legacy,
+	Argon2id,
+}
+
+impl PasswordKdf {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			PasswordKdf::Legacy => "legacy",
+			PasswordKdf::Argon2id => "argon2id",
+		}
+	}
+
+	// 未知的名称按 Legacy 处理, 与旧服务端不返回 kdf 时一致
+	pub fn from_name(name: &str) -> Self {
+		match name {
+			"argon2id" => PasswordKdf::Argon2id,
+			_ => PasswordKdf::Legacy,
+		}
+	}
+}
+
+pub fn gen_salt() -> String {
+	let salt: [u8; 16] = rand::thread_rng().gen();
+	encode_base64(&salt)
+}
+
+// 返回发送给服务端的 password_hash, 明文密码不离开本机
+pub fn hash_password(password: &str, kdf: PasswordKdf, salt: &str) -> Result<String, CipherError> {
+	match kdf {
+		PasswordKdf::Legacy => Ok(hash_str(password)),
+		PasswordKdf::Argon2id => {
+			let params = Params::new(ARGON2_MEMORY_KIB, ARGON2_ITERATIONS, ARGON2_PARALLELISM, Some(HASH_LEN))
+				.map_err(|e| CipherError::InvalidData(e.to_string()))?;
+			let mut output = [0u8; HASH_LEN];
+			Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+				.hash_password_into(password.as_bytes(), salt.as_bytes(), &mut output)
+				.map_err(|e| CipherError::InvalidData(e.to_string()))?;
+			Ok(output.iter().map(|b| format!("{:02x}", b)).collect())
+		},
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_hash_password() {
+		let salt = gen_salt();
+		let hash = hash_password("password", PasswordKdf::Argon2id, &salt).unwrap();
+		assert_eq!(hash.len(), HASH_LEN * 2);
+		assert_eq!(hash, hash_password("password", PasswordKdf::Argon2id, &salt).unwrap());
+		assert_ne!(hash, hash_password("password", PasswordKdf::Argon2id, &gen_salt()).unwrap());
+		assert_ne!(hash, hash_password("Password", PasswordKdf::Argon2id, &salt).unwrap());
+
+		assert_eq!(hash_password("password", PasswordKdf::Legacy, "").unwrap(), hash_str("password"));
+		// salt 过短
+		assert!(hash_password("password", PasswordKdf::Argon2id, "salt").is_err());
+	}
+
+	#[test]
+	fn test_kdf_names() {
+		for kdf in [PasswordKdf::Legacy, PasswordKdf::Argon2id] {
+			assert_eq!(PasswordKdf::from_name(kdf.as_str()), kdf);
+			assert_eq!(serde_json::to_string(&kdf).unwrap(), format!("\"{}\"", kdf.as_str()));
+		}
+		assert_eq!(PasswordKdf::from_name(""), PasswordKdf::Legacy);
+	}
+}
@@ -18,8 +18,7 @@ pub fn timestamp_to_datetime(timestamp: i64, timezone: &str) -> DateTime<FixedOf
     let tz: FixedOffset = timezone.parse().expect("Invalid timezone format");
     match Utc.timestamp_opt(timestamp, 0) {
         chrono::LocalResult::Single(datetime) => {
-            let datetime_with_timezone = datetime.with_timezone(&tz);
-            datetime_with_timezone
+            datetime.with_timezone(&tz)
         }
         chrono::LocalResult::Ambiguous(_, _) => {
             panic!("Ambiguous timestamp");
@@ -2,6 +2,8 @@ use x11rb::connection::Connection;
 use x11rb::protocol::xproto::*;
 use x11rb::rust_connection::RustConnection;
 
+use crate::core::pasteboard::SourceApp;
+
 // 激活指定窗口
 pub fn activate_window(conn: &RustConnection, screen: &Screen, target_name: &str) -> Result<(), Box<dyn std::error::Error>> {
     let children = conn.query_tree(screen.root)?.reply()?.children;
@@ -69,7 +71,7 @@ fn get_current_desktop(conn: &RustConnection, screen: &Screen) -> Result<i32, Bo
 }
 
 // 获取窗口的类名
-fn get_window_class(conn: &impl Connection, window: Window) -> Result<String, Box<dyn std::error::Error>> {
+pub fn get_window_class(conn: &impl Connection, window: Window) -> Result<String, Box<dyn std::error::Error>> {
     let class = conn.get_property(false, window, AtomEnum::WM_CLASS, AtomEnum::STRING, 0, 1024)?.reply()?;
 
     if class.type_ == u32::from(AtomEnum::STRING) {
@@ -98,7 +100,7 @@ fn get_window_desktop(conn: &impl Connection, window: Window) -> Result<i32, Box
 }
 
 // 获取窗口的标题
-fn get_window_title(conn: &impl Connection, window: Window) -> Result<String, Box<dyn std::error::Error>> {
+pub fn get_window_title(conn: &impl Connection, window: Window) -> Result<String, Box<dyn std::error::Error>> {
     let name = conn.get_property(false, window, AtomEnum::WM_NAME, AtomEnum::STRING, 0, 1024)?.reply()?;
 
     if name.type_ == u32::from(AtomEnum::STRING) {
@@ -107,3 +109,121 @@ fn get_window_title(conn: &impl Connection, window: Window) -> Result<String, Bo
         Ok(String::new())
     }
 }
+
+// 根据 selection owner 查询来源应用
+pub struct SourceAppResolver {
+    conn: RustConnection,
+    root: Window,
+    net_active_window: Atom,
+    net_wm_name: Atom,
+    net_wm_pid: Atom,
+    utf8_string: Atom,
+}
+
+impl SourceAppResolver {
+    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        let (conn, screen_num) = RustConnection::connect(None)?;
+        let root = conn.setup().roots[screen_num].root;
+        let net_active_window = conn.intern_atom(false, b"_NET_ACTIVE_WINDOW")?.reply()?.atom;
+        let net_wm_name = conn.intern_atom(false, b"_NET_WM_NAME")?.reply()?.atom;
+        let net_wm_pid = conn.intern_atom(false, b"_NET_WM_PID")?.reply()?.atom;
+        let utf8_string = conn.intern_atom(false, b"UTF8_STRING")?.reply()?.atom;
+        Ok(Self {
+            conn,
+            root,
+            net_active_window,
+            net_wm_name,
+            net_wm_pid,
+            utf8_string,
+        })
+    }
+
+    // selection: b"CLIPBOARD" 或 b"PRIMARY"
+    pub fn resolve(&self, selection: &[u8]) -> Result<SourceApp, Box<dyn std::error::Error>> {
+        let selection = self.conn.intern_atom(false, selection)?.reply()?.atom;
+        let owner = self.conn.get_selection_owner(selection)?.reply()?.owner;
+        if owner == x11rb::NONE {
+            return Ok(SourceApp::default());
+        }
+
+        // GTK/Qt 通常用隐藏窗口持有 selection, 取不到 WM_CLASS 时退回当前激活的窗口
+        let mut window = owner;
+        let mut app_name = parse_wm_class(&get_window_class(&self.conn, window)?);
+        if app_name.is_empty() {
+            if let Some(active) = self.get_active_window()? {
+                window = active;
+                app_name = parse_wm_class(&get_window_class(&self.conn, window)?);
+            }
+        }
+
+        let pid = self.get_window_pid(window)?.or(self.get_window_pid(owner)?);
+        // 自己写入的剪贴板不记录来源
+        if pid == Some(std::process::id()) {
+            return Ok(SourceApp::default());
+        }
+        let process_name = pid.and_then(get_process_name).unwrap_or_default();
+        Ok(SourceApp {
+            app_name: if app_name.is_empty() { process_name.clone() } else { app_name },
+            process_name,
+            window_title: self.get_window_name(window)?,
+        })
+    }
+
+    fn get_active_window(&self) -> Result<Option<Window>, Box<dyn std::error::Error>> {
+        let reply = self
+            .conn
+            .get_property(false, self.root, self.net_active_window, AtomEnum::WINDOW, 0, 1)?
+            .reply()?;
+        Ok(reply.value32().and_then(|mut v| v.next()).filter(|w| *w != x11rb::NONE))
+    }
+
+    fn get_window_pid(&self, window: Window) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+        let reply = self
+            .conn
+            .get_property(false, window, self.net_wm_pid, AtomEnum::CARDINAL, 0, 1)?
+            .reply()?;
+        Ok(reply.value32().and_then(|mut v| v.next()))
+    }
+
+    // 优先使用 UTF-8 的 _NET_WM_NAME, 没有时退回 WM_NAME
+    fn get_window_name(&self, window: Window) -> Result<String, Box<dyn std::error::Error>> {
+        let name = self
+            .conn
+            .get_property(false, window, self.net_wm_name, self.utf8_string, 0, 1024)?
+            .reply()?;
+        if name.type_ == self.utf8_string && !name.value.is_empty() {
+            return Ok(String::from_utf8_lossy(&name.value).to_string());
+        }
+        get_window_title(&self.conn, window)
+    }
+}
+
+// WM_CLASS 为 "instance\0class\0", 优先取 class
+fn parse_wm_class(raw: &str) -> String {
+    let mut parts = raw.split('\0').filter(|s| !s.is_empty());
+    let instance = parts.next().unwrap_or_default();
+    parts.next().unwrap_or(instance).to_string()
+}
+
+fn get_process_name(pid: u32) -> Option<String> {
+    let comm = std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?;
+    Some(comm.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wm_class() {
+        assert_eq!(parse_wm_class("gnome-terminal-server\0Gnome-terminal\0"), "Gnome-terminal");
+        assert_eq!(parse_wm_class("xterm\0"), "xterm");
+        assert_eq!(parse_wm_class(""), "");
+    }
+
+    #[test]
+    fn test_get_process_name() {
+        assert!(get_process_name(std::process::id()).is_some_and(|name| !name.is_empty()));
+        assert_eq!(get_process_name(u32::MAX), None);
+    }
+}
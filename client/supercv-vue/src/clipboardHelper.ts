@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api/tauri'
+import { listen, UnlistenFn } from '@tauri-apps/api/event'
 
 export interface ClipboardEntry {
   id: number
@@ -7,6 +8,75 @@ export interface ClipboardEntry {
   content: string
   timestamp: number
   hash: string
+  source: number // 0 CLIPBOARD, 1 PRIMARY
+  app_name: string // 来源应用
+  process_name: string
+  window_title: string
+  copy_count: number // 复制次数, 相同内容再次复制时加一
+  title: string // 用户添加的标题和备注
+  note: string
+}
+
+// 条件都为空时删除全部记录, start 包括, end 不包括
+export interface DeleteFilter {
+  type_list?: number[] | null
+  start?: number | null
+  end?: number | null
+  include_pinned?: boolean // 默认保留置顶的记录
+}
+
+// undo_until (毫秒) 之前可以用 batch 撤销
+export interface DeleteReport {
+  deleted: number
+  batch: number
+  undo_until: number
+}
+
+// 编辑前的记录内容
+export interface ClipboardRevision {
+  id: number
+  clipboard_id: number
+  content: string
+  hash: string
+  title: string
+  note: string
+  created_at: number
+}
+
+// 为 null 的字段保持不变, 只有文本记录可以修改 content
+export interface EntryEdit {
+  content?: string | null
+  title?: string | null
+  note?: string | null
+}
+
+// 翻页位置, 指向上一页的最后一条记录
+export interface Cursor {
+  timestamp: number
+  id: number
+}
+
+export interface ClipboardPage {
+  entries: ClipboardEntry[]
+  next_cursor: Cursor | null // 没有更多记录时为 null
+  total: number
+}
+
+// 同一天的记录可能分在相邻的两页, 按 date 合并
+export interface Timeline {
+  days: { date: string; entries: ClipboardEntry[] }[]
+  next_cursor: Cursor | null
+  total: number
+}
+
+export interface ClipboardStats {
+  total: number
+  per_day: { date: string; count: number }[] // 最近 days 天, 本地日期
+  per_hour: number[] // 24 项
+  per_type: { type: number; count: number; expired: number }[] // expired 为按当前过期配置已过期的记录
+  per_source: { source: number; count: number }[]
+  storage: { text_bytes: number; image_bytes: number; database_bytes: number }
+  top_repeated: { id: number; type: number; content: string; copy_count: number; timestamp: number }[]
 }
 
 export interface ExpiredConfig {
@@ -18,24 +88,163 @@ export interface PreviewConfig {
   preview_number: number
 }
 
+export type PrimarySync = 'off' | 'primary_to_clipboard' | 'clipboard_to_primary' | 'both'
+
+export interface PrimaryConfig {
+  capture: boolean
+  debounce_ms: number
+  sync: PrimarySync
+}
+
+export interface CaptureConfig {
+  debounce_ms: number
+  max_wait_ms: number
+  batch_size: number
+  queue_size: number
+}
+
+export interface CaptureMetrics {
+  notified: number
+  coalesced: number
+  dropped: number
+  captured: number
+  batches: number
+}
+
+export interface IpcConfig {
+  enabled: boolean
+}
+
+export interface SyncConfig {
+  enabled: boolean
+  server_url: string
+  interval_secs: number
+  window_secs: number
+  max_retries: number
+  retry_delay_ms: number
+  outbox_base_delay_secs?: number
+  outbox_max_delay_secs?: number
+  max_sync_bytes?: number // 每次同步传输的文件总量上限, 0 为不限制
+}
+
+export interface BackupConfig {
+  enabled: boolean
+  interval_hours: number
+  keep: number // 保留的备份数量, 0 为不删除
+  dir: string // 为空时使用缓存目录下的 backups
+  include_files: boolean
+}
+
+// 同步时的文件传输进度, 通过 transfer-progress 事件推送
+export interface TransferProgress {
+  name: string
+  direction: 'upload' | 'download'
+  transferred: number
+  total: number
+}
+
+// 命令失败时 invoke 抛出的错误
+export type ErrorCode =
+  | 'database'
+  | 'io'
+  | 'network'
+  | 'api'
+  | 'crypto'
+  | 'clipboard'
+  | 'window'
+  | 'not_found'
+  | 'invalid_input'
+  | 'capture_paused'
+  | 'unavailable'
+  | 'internal'
+
+export interface AppError {
+  code: ErrorCode
+  message: string
+}
+
+// 导入导出剪贴板历史, archive 为带上图片和文件的 .supercv 压缩包
+export type ExportFormat = 'json' | 'jsonl' | 'csv' | 'archive'
+
+// 导入的记录与本地记录 hash 相同时的处理方式
+export type ConflictPolicy = 'skip' | 'overwrite' | 'newer'
+
+export interface ExportReport {
+  entries: number
+  files: number
+}
+
+export interface ImportReport {
+  imported: number
+  updated: number
+  skipped: number
+  files: number
+}
+
+// 可以迁移历史记录的其他剪贴板管理器. clipy 只支持片段编辑器导出的 XML, 不支持 Clipy 的历史记录 (Realm 数据库)
+export type ImportSource = 'copyq' | 'clipy' | 'maccy' | 'ditto' | 'gpaste' | 'klipper'
+
+export interface MigrationReport {
+  source: ImportSource
+  path: string
+  dry_run: boolean
+  found: number
+  imported: number // dry_run 时为将要导入的数量
+  duplicates: number
+  skipped: number
+  texts: number
+  images: number
+  files: number
+  oldest: number | null
+  newest: number | null
+}
+
+export interface BackupInfo {
+  name: string
+  path: string
+  created_at: number
+  entries: number
+  files: number
+  size: number
+}
+
+export interface RestoreReport {
+  backup: string
+  entries: number
+  files: number
+  previous: string // 恢复前自动创建的备份, 可以用来撤销这次恢复
+}
+
+export type CaptureState =
+  | { state: 'active' }
+  | { state: 'paused' }
+  | { state: 'paused_until'; until: number } // until 为恢复记录的时间戳 (秒)
+
 export interface UserConfig {
   expired_config: ExpiredConfig
   preview_config: PreviewConfig
   global_shortcut: string
   theme: string
+  primary_config?: PrimaryConfig
+  capture_config?: CaptureConfig
+  ipc_config?: IpcConfig
+  sync_config?: SyncConfig
+  backup_config?: BackupConfig
 }
 
 export class ClipboardHelper {
   static async getClipboardEntries(
     num: number = 10,
-    typeList: number[] | null = null
+    typeList: number[] | null = null,
+    app: string | null = null
   ): Promise<ClipboardEntry[]> {
     // 打印调用参数 使用k:v
-    console.log('getClipboardEntries', { num, typeList })
+    console.log('getClipboardEntries', { num, typeList, app })
     try {
       const result = await invoke<ClipboardEntry[]>('rs_invoke_get_clipboards', {
         num,
         typeList,
+        app,
       })
       console.log(result)
       return result
@@ -49,15 +258,17 @@ export class ClipboardHelper {
   static async searchClipboardEntries(
     query: string,
     num: number = 10,
-    typeList: number[] | null = null
+    typeList: number[] | null = null,
+    app: string | null = null
   ): Promise<ClipboardEntry[]> {
     // 打印调用参数
-    console.log('searchClipboardEntries', { query, num, typeList })
+    console.log('searchClipboardEntries', { query, num, typeList, app })
     try {
       const result = await invoke<ClipboardEntry[]>('rs_invoke_search_clipboards', {
         query,
         num,
         typeList,
+        app,
       })
       return result
     } catch (error) {
@@ -67,6 +278,182 @@ export class ClipboardHelper {
     }
   }
 
+  // cursor 为空时返回第一页, query 为空时不过滤内容
+  static async getClipboardPage(
+    query: string | null = null,
+    cursor: Cursor | null = null,
+    limit: number = 10,
+    typeList: number[] | null = null,
+    app: string | null = null
+  ): Promise<ClipboardPage> {
+    console.log('getClipboardPage', { query, cursor, limit, typeList, app })
+    try {
+      return await invoke<ClipboardPage>('rs_invoke_get_clipboard_page', {
+        query,
+        typeList,
+        app,
+        cursor,
+        limit,
+      })
+    } catch (error) {
+      console.error('Failed to get clipboard page:', error)
+      return { entries: [], next_cursor: null, total: 0 }
+    }
+  }
+
+  static async getTimeline(
+    query: string | null = null,
+    cursor: Cursor | null = null,
+    limit: number = 50,
+    typeList: number[] | null = null,
+    app: string | null = null
+  ): Promise<Timeline> {
+    try {
+      return await invoke<Timeline>('rs_invoke_get_timeline', {
+        query,
+        typeList,
+        app,
+        cursor,
+        limit,
+      })
+    } catch (error) {
+      console.error('Failed to get timeline:', error)
+      return { days: [], next_cursor: null, total: 0 }
+    }
+  }
+
+  // days 为按天统计的天数
+  static async getStats(days: number = 30): Promise<ClipboardStats | null> {
+    try {
+      return await invoke<ClipboardStats>('rs_invoke_get_stats', { days })
+    } catch (error) {
+      console.error('Failed to get stats:', error)
+      return null
+    }
+  }
+
+  // 内容与其他记录相同时失败, 错误交给调用方显示
+  static async editClipboardEntry(id: number, edit: EntryEdit): Promise<ClipboardEntry> {
+    return await invoke<ClipboardEntry>('rs_invoke_edit_clipboard', { id, edit })
+  }
+
+  // 恢复到最近一次编辑之前, 没有可撤销的版本时失败
+  static async undoClipboardEdit(id: number): Promise<ClipboardEntry> {
+    return await invoke<ClipboardEntry>('rs_invoke_undo_clipboard_edit', { id })
+  }
+
+  static async getClipboardRevisions(id: number): Promise<ClipboardRevision[]> {
+    try {
+      return await invoke<ClipboardRevision[]>('rs_invoke_get_clipboard_revisions', { id })
+    } catch (error) {
+      console.error('Failed to get clipboard revisions:', error)
+      return []
+    }
+  }
+
+  static async deleteClipboardEntry(id: number): Promise<DeleteReport> {
+    return await invoke<DeleteReport>('rs_invoke_delete_clipboard', { id })
+  }
+
+  static async deleteClipboardEntries(ids: number[]): Promise<DeleteReport> {
+    return await invoke<DeleteReport>('rs_invoke_delete_clipboards', { ids })
+  }
+
+  static async deleteClipboardsByFilter(filter: DeleteFilter): Promise<DeleteReport> {
+    return await invoke<DeleteReport>('rs_invoke_delete_clipboards_by_filter', { filter })
+  }
+
+  static async clearClipboards(includePinned: boolean = false): Promise<DeleteReport> {
+    return await invoke<DeleteReport>('rs_invoke_clear_clipboards', { includePinned })
+  }
+
+  // 返回恢复的条数, 超过撤销窗口后失败
+  static async undoDelete(batch: number): Promise<number> {
+    return await invoke<number>('rs_invoke_undo_delete', { batch })
+  }
+
+  // 出现过的来源应用, 用于按应用过滤
+  static async getSourceApps(): Promise<string[]> {
+    try {
+      return await invoke<string[]>('rs_invoke_get_source_apps')
+    } catch (error) {
+      console.error('Failed to get source apps:', error)
+      return []
+    }
+  }
+
+  // ids 为空时导出全部记录, format 为空时按扩展名推断
+  static async exportClipboards(
+    path: string,
+    format: ExportFormat | null = null,
+    ids: number[] | null = null
+  ): Promise<ExportReport> {
+    return await invoke<ExportReport>('rs_invoke_export_clipboards', { path, format, ids })
+  }
+
+  static async importClipboards(
+    path: string,
+    format: ExportFormat | null = null,
+    onConflict: ConflictPolicy = 'skip'
+  ): Promise<ImportReport> {
+    return await invoke<ImportReport>('rs_invoke_import_clipboards', { path, format, onConflict })
+  }
+
+  // path 为空时使用该应用默认的数据文件
+  static async importFromApp(
+    source: ImportSource,
+    path: string | null = null,
+    dryRun = false
+  ): Promise<MigrationReport> {
+    return await invoke<MigrationReport>('rs_invoke_import_from_app', { source, path, dryRun })
+  }
+
+  static async backupNow(): Promise<BackupInfo> {
+    return await invoke<BackupInfo>('rs_invoke_backup_now')
+  }
+
+  // 从新到旧
+  static async listBackups(): Promise<BackupInfo[]> {
+    return await invoke<BackupInfo[]>('rs_invoke_list_backups')
+  }
+
+  // backup 为备份名或备份目录的路径
+  static async restoreBackup(backup: string): Promise<RestoreReport> {
+    return await invoke<RestoreReport>('rs_invoke_restore_backup', { backup })
+  }
+
+  static async getCaptureMetrics(): Promise<CaptureMetrics> {
+    return await invoke<CaptureMetrics>('rs_invoke_get_capture_metrics')
+  }
+
+  static async getCaptureState(): Promise<CaptureState> {
+    return await invoke<CaptureState>('rs_invoke_get_capture_state')
+  }
+
+  // minutes 为空时一直暂停, 定时暂停为 1 到 1440 分钟
+  static async pauseCapture(minutes: number | null = null): Promise<CaptureState> {
+    return await invoke<CaptureState>('rs_invoke_pause_capture', { minutes })
+  }
+
+  // 暂停, 恢复以及定时暂停到期时都会收到
+  static async onCaptureStateChanged(callback: (state: CaptureState) => void): Promise<UnlistenFn> {
+    return await listen<CaptureState>('capture-state-changed', (event) => callback(event.payload))
+  }
+
+  static async resumeCapture(): Promise<CaptureState> {
+    return await invoke<CaptureState>('rs_invoke_resume_capture')
+  }
+
+  // 同步数据端到端加密, 需要 super key 解开 DEK
+  static async startSync(superKey: string): Promise<void> {
+    await invoke('rs_invoke_start_sync', { superKey })
+  }
+
+  // 停止同步, 清除本地保存的登录信息
+  static async logout(): Promise<void> {
+    await invoke('rs_invoke_logout')
+  }
+
   static async setClipboardEntriy(item: ClipboardEntry): Promise<void> {
     try {
       await await invoke<ClipboardEntry[]>('rs_invoke_set_clipboards', {
@@ -26,3 +26,10 @@ def get_content(db: Session, content_id: int):
     if res := db.query(models.ClipboardEntry).filter_by(id=content_id).first():
         return res.to_dict()
     raise Exception(404, f"{content_id=} not exists")
+
+
+# 删除设备上传的记录, 已经不存在时返回 False
+def delete_content(db: Session, device_id: int, hash: str):
+    deleted = db.query(models.ClipboardEntry).filter_by(device_id=device_id, hash=hash).delete()
+    db.commit()
+    return deleted > 0
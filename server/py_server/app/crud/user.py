@@ -1,8 +1,20 @@
 from sqlalchemy.orm import Session
+import base64
+import hashlib
+import hmac
+import os
+import secrets
 import time
 
 from app import schemas, models
 from app.db.sql import update_to_db
+from configs import config, logger
+
+# 给不存在的用户生成 salt 的密钥, 重启后需要保持不变, 否则同一个用户名返回的 salt 会变化
+SALT_SECRET = os.environ.get("SUPERCV_SALT_SECRET") or config.get("SECURITY", "salt_secret", fallback="")
+if not SALT_SECRET:
+    logger.warning("salt_secret is not configured, fake salts change after restart")
+    SALT_SECRET = secrets.token_hex(32)
 
 
 def register_user(db: Session, item: schemas.UserRegister):
@@ -40,3 +52,30 @@ def reset_password_user(db: Session, item: schemas.UserResetPassword):
             raise Exception(401, f"email error db: {db_item.email} != {item.email}", )
     else:
         raise Exception(404, f"{item.username=} not found")
+
+
+def get_user_salt(db: Session, item: schemas.UserSaltQuery):
+    if db_item := db.query(models.User).filter_by(username=item.username).first():
+        return {"salt": db_item.salt, "kdf": db_item.kdf}
+    # 不存在的用户也返回固定的 salt, 格式和客户端生成的一致 (16 字节 base64), 避免通过这个接口判断用户是否存在
+    return {"salt": fake_salt(item.username), "kdf": "argon2id"}
+
+
+def fake_salt(username: str) -> str:
+    digest = hmac.new(SALT_SECRET.encode(), username.encode(), hashlib.sha256).digest()
+    return base64.b64encode(digest[:16]).decode()
+
+
+def upgrade_password_user(db: Session, item: schemas.UserUpgradePassword):
+    if db_item := db.query(models.User).filter_by(username=item.username).first():
+        if db_item.password_hash != item.password_hash:
+            raise Exception(401, f"password error")
+        # 只允许从 legacy 迁移, 不能降级
+        if db_item.kdf != "legacy" or item.kdf != "argon2id":
+            raise Exception(400, f"can not change kdf from {db_item.kdf} to {item.kdf}")
+        db_item.password_hash = item.new_password_hash
+        db_item.salt = item.salt
+        db_item.kdf = item.kdf
+        db_item.update(db)
+        return db_item.to_dict()
+    raise Exception(404, f"{item.username=} not found")
@@ -0,0 +1,74 @@
+# 数据库表结构的版本迁移, 由 migrate.py 手动执行, 服务启动时不修改已有的表
+# create_all 只创建缺少的表, 已有的表通过这里的迁移补上新字段, 每个版本都可以回滚
+from sqlalchemy import inspect, text
+
+from app.db.sql import engine
+from configs import logger
+
+VERSION_TABLE = "schema_version"
+
+
+def _columns(conn, table):
+    return {c["name"]: c for c in inspect(conn).get_columns(table)}
+
+
+# 1: user 表补上 salt 和 kdf, password_hash 限制为 64 位
+def _upgrade_user_kdf(conn):
+    columns = _columns(conn, "user")
+    # 只有长度不是 64 时才修改类型, 新建的表已经是 VARCHAR(64)
+    if getattr(columns["password_hash"]["type"], "length", None) != 64:
+        conn.execute(text('ALTER TABLE "user" ALTER COLUMN password_hash TYPE VARCHAR(64)'))
+    if "salt" not in columns:
+        conn.execute(text('ALTER TABLE "user" ADD COLUMN salt VARCHAR NOT NULL DEFAULT \'\''))
+    if "kdf" not in columns:
+        conn.execute(text('ALTER TABLE "user" ADD COLUMN kdf VARCHAR NOT NULL DEFAULT \'legacy\''))
+
+
+def _downgrade_user_kdf(conn):
+    columns = _columns(conn, "user")
+    if "kdf" in columns:
+        conn.execute(text('ALTER TABLE "user" DROP COLUMN kdf'))
+    if "salt" in columns:
+        conn.execute(text('ALTER TABLE "user" DROP COLUMN salt'))
+    conn.execute(text('ALTER TABLE "user" ALTER COLUMN password_hash TYPE VARCHAR'))
+
+
+# (版本, 升级, 回滚), 版本号递增
+MIGRATIONS = [
+    (1, _upgrade_user_kdf, _downgrade_user_kdf),
+]
+LATEST = MIGRATIONS[-1][0]
+
+
+def current_version(conn):
+    conn.execute(text(f"CREATE TABLE IF NOT EXISTS {VERSION_TABLE} (version INTEGER NOT NULL)"))
+    version = conn.execute(text(f"SELECT version FROM {VERSION_TABLE}")).scalar()
+    if version is None:
+        conn.execute(text(f"INSERT INTO {VERSION_TABLE} (version) VALUES (0)"))
+        return 0
+    return version
+
+
+# 升级或回滚到 target, 全部在一个事务中完成, 失败时不留下一半的修改
+def migrate(target=LATEST):
+    with engine.begin() as conn:
+        version = current_version(conn)
+        if target > version:
+            for v, upgrade, _ in MIGRATIONS:
+                if version < v <= target:
+                    logger.info(f"upgrade database to version {v}")
+                    upgrade(conn)
+        elif target < version:
+            for v, _, downgrade in reversed(MIGRATIONS):
+                if target < v <= version:
+                    logger.info(f"downgrade database from version {v}")
+                    downgrade(conn)
+        conn.execute(text(f"UPDATE {VERSION_TABLE} SET version = :version"), {"version": target})
+    return target
+
+
+def check_version():
+    with engine.begin() as conn:
+        version = current_version(conn)
+    if version != LATEST:
+        logger.warning(f"database version {version} != {LATEST}, run `python migrate.py` first")
@@ -9,7 +9,9 @@ class User(BaseModel):
     id = Column(Integer, primary_key=True, index=True, autoincrement=True, comment="User ID")
     username = Column(String, unique=True, nullable=False, comment="User name, 不可重复")
     email = Column(String, unique=True, nullable=False, comment="User email, 不可重复")
-    password_hash = Column(String(16), nullable=False, comment="User password hash")
+    password_hash = Column(String(64), nullable=False, comment="User password hash, Argon2id 为 64 位十六进制")
+    salt = Column(String, nullable=False, default="", server_default="", comment="派生 password_hash 的 salt, legacy 账户为空")
+    kdf = Column(String, nullable=False, default="legacy", server_default="legacy", comment="password_hash 的派生方式: legacy / argon2id")
     encrypted_dek = Column(String, nullable=False, comment="eDEK 48 字节")
     created_at = Column(Integer, comment="User creation date")
     updated_at = Column(Integer, comment="User last update date")
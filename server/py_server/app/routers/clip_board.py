@@ -21,3 +21,9 @@ def create_content(item: schemas.CreateContent, db: Session = Depends(get_db)):
 @web_try()
 def get_content(content_id: int, db: Session = Depends(get_db)):
     return crud.get_content(db, content_id)
+
+
+@router_content.delete("/{device_id}/{hash}")
+@web_try()
+def delete_content(device_id: int, hash: str, db: Session = Depends(get_db)):
+    return crud.delete_content(db, device_id, hash)
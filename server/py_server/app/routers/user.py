@@ -27,3 +27,14 @@ def login_user(item: schemas.UserLogin, db: Session = Depends(get_db)):
 def reset_user(item: schemas.UserResetPassword, db: Session = Depends(get_db)):
     return crud.user.reset_password_user(db, item)
 
+
+@router_user.post("/salt")
+@web_try()
+def get_user_salt(item: schemas.UserSaltQuery, db: Session = Depends(get_db)):
+    return crud.user.get_user_salt(db, item)
+
+
+@router_user.post("/password")
+@web_try()
+def upgrade_user_password(item: schemas.UserUpgradePassword, db: Session = Depends(get_db)):
+    return crud.user.upgrade_password_user(db, item)
@@ -6,6 +6,8 @@ class UserRegister(BaseModel):
     email: str
     password_hash: str
     encrypted_dek: str
+    salt: str = ""
+    kdf: str = "legacy"
 
 
 class UserLogin(BaseModel):
@@ -17,3 +19,18 @@ class UserResetPassword(BaseModel):
     username: str
     email: str
     password_hash: str
+    salt: str = ""
+    kdf: str = "legacy"
+
+
+class UserSaltQuery(BaseModel):
+    username: str
+
+
+# legacy 账户登录后换成新的 password_hash, 需要带上旧的 password_hash 校验
+class UserUpgradePassword(BaseModel):
+    username: str
+    password_hash: str
+    new_password_hash: str
+    salt: str
+    kdf: str
@@ -0,0 +1,13 @@
+import argparse
+
+from app import models  # noqa: F401, create_all 需要先导入模型
+from app.db.migration import LATEST, migrate
+from app.db.sql import engine, Base
+
+if __name__ == '__main__':
+    parser = argparse.ArgumentParser(description="升级或回滚数据库表结构")
+    parser.add_argument('--to', type=int, default=LATEST, help=f"目标版本, 默认升级到最新的 {LATEST}, 0 表示全部回滚")
+    opt = parser.parse_args()
+
+    Base.metadata.create_all(bind=engine)
+    print(f'database version: {migrate(opt.to)}')
@@ -1,10 +1,13 @@
 from fastapi import FastAPI
 from fastapi.middleware.cors import CORSMiddleware
 
+from app.db.migration import check_version
 from app.db.sql import engine, Base
 from app import routers
 
 Base.metadata.create_all(bind=engine)
+# create_all 不会修改已有的表, 表结构的变化通过 migrate.py 迁移
+check_version()
 app = FastAPI(title='HostClipboard python server')
 
 # CORS 跨源资源共享